@@ -1,4 +1,5 @@
 mod mmap_directory;
+mod prefix_directory;
 mod ram_directory;
 
 use std::io;
@@ -9,6 +10,7 @@ use std::sync::Arc;
 use std::sync::Weak;
 
 pub use mmap_directory::MmapDirectory;
+pub use prefix_directory::PrefixDirectory;
 pub use ram_directory::RamDirectory;
 
 /// Write object for Directory.
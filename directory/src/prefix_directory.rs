@@ -0,0 +1,86 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use ownedbytes::OwnedBytes;
+
+use crate::{Directory, WritePtr};
+
+/// A `PrefixDirectory` is a view on another [`Directory`], where every file name is prefixed.
+///
+/// This allows to store multiple independent sets of files in the same directory, without
+/// requiring subfolders, e.g. one set of index files per segment.
+#[derive(Clone)]
+pub struct PrefixDirectory {
+    prefix: String,
+    inner: Box<dyn Directory>,
+}
+
+impl PrefixDirectory {
+    /// Creates a view on `inner`, where all files are prefixed with `prefix`
+    pub fn new(prefix: &str, inner: Box<dyn Directory>) -> PrefixDirectory {
+        PrefixDirectory {
+            prefix: prefix.to_string(),
+            inner,
+        }
+    }
+
+    /// Returns the prefix, which is prepended to all file names
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn resolve_path(&self, path: &Path) -> PathBuf {
+        PathBuf::from(format!("{}{}", self.prefix, path.to_string_lossy()))
+    }
+}
+
+impl fmt::Debug for PrefixDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrefixDirectory").field("prefix", &self.prefix).field("inner", &self.inner).finish()
+    }
+}
+
+impl Directory for PrefixDirectory {
+    fn get_file_bytes(&self, path: &Path) -> Result<OwnedBytes, io::Error> {
+        self.inner.get_file_bytes(&self.resolve_path(path))
+    }
+
+    fn open_append(&self, path: &Path) -> Result<WritePtr, io::Error> {
+        self.inner.open_append(&self.resolve_path(path))
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), io::Error> {
+        self.inner.write(&self.resolve_path(path), data)
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), io::Error> {
+        self.inner.delete(&self.resolve_path(path))
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, io::Error> {
+        self.inner.exists(&self.resolve_path(path))
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.inner.sync_directory()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RamDirectory;
+
+    #[test]
+    fn test_prefix() {
+        let ram = RamDirectory::create();
+        let dir = PrefixDirectory::new("seg_1.", Box::new(ram.clone()));
+        dir.write(Path::new("data"), b"yep").unwrap();
+        assert!(dir.exists(Path::new("data")).unwrap());
+        assert!(ram.exists(Path::new("seg_1.data")).unwrap());
+        assert!(!ram.exists(Path::new("data")).unwrap());
+        assert_eq!(dir.get_file_bytes(Path::new("data")).unwrap().as_slice(), b"yep");
+    }
+}
@@ -7,6 +7,7 @@ pub fn convert_to_string(value: &Value) -> Cow<'_, str> {
     match *value {
         Value::String(ref s) => Cow::from(s.as_str()),
         Value::Number(ref i) if i.is_u64() => Cow::from(i.as_u64().unwrap().to_string()),
+        Value::Number(ref i) if i.is_i64() => Cow::from(i.as_i64().unwrap().to_string()),
         Value::Number(ref i) if i.is_f64() => Cow::from(i.as_f64().unwrap().to_string()),
         Value::Bool(ref i) => Cow::from(i.to_string()),
         _ => Cow::from(""),
@@ -222,3 +223,11 @@ fn test_foreach() {
     })
     .unwrap();
 }
+
+#[test]
+fn test_convert_to_string() {
+    assert_eq!(convert_to_string(&serde_json::json!(10)), "10");
+    assert_eq!(convert_to_string(&serde_json::json!(-10)), "-10");
+    assert_eq!(convert_to_string(&serde_json::json!(1.5)), "1.5");
+    assert_eq!(convert_to_string(&serde_json::json!(true)), "true");
+}
@@ -1,26 +1,46 @@
 mod leaf;
 mod operator;
+mod range;
 
 pub use leaf::UserFilter;
 pub use operator::Operator;
+pub use range::UserRange;
 
 use std::{collections::HashSet, convert::From, fmt};
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq)]
 pub enum UserAST {
     Attributed(String, Box<UserAST>),
     BinaryClause(Box<UserAST>, Operator, Box<UserAST>),
+    /// Excludes the documents matching the subtree
+    Not(Box<UserAST>),
+    /// The subtree is required, other clauses connected with OR only add to the score
+    Required(Box<UserAST>),
+    /// The terms within a distance of each other, e.g. `apple NEAR/5 pie` or `"search engine"~3`
+    Near(Vec<UserFilter>, u32),
     Leaf(Box<UserFilter>),
+    /// A range or comparison on an attribute, e.g. `year:[2000 TO 2010]` or `price:<20`
+    Range(Box<UserRange>),
+    /// The subtree with a boost, e.g. `term^2.5` or `(cool nice)^0.5`
+    Boosted(Box<UserAST>, f32),
 }
 
+// The parser only creates finite boosts
+impl Eq for UserAST {}
+
 // conversion used in tests
 impl From<&'static str> for UserAST {
     fn from(item: &str) -> Self {
         let mut filter = UserFilter {
             phrase: item.to_string(),
             levenshtein: None,
+            quoted: false,
+            wildcard: false,
         };
-        if item.chars().next().map(|c| c != '\"').unwrap_or(false) {
+        if item.len() > 1 && item.starts_with('"') && item.ends_with('"') {
+            filter.phrase = item[1..item.len() - 1].to_string();
+            filter.quoted = true;
+        } else if item.chars().next().map(|c| c != '\"').unwrap_or(false) {
             let parts_field = item.splitn(2, ':').collect::<Vec<_>>();
             if parts_field.len() > 1 {
                 filter.phrase = parts_field[1].to_string();
@@ -32,6 +52,7 @@ impl From<&'static str> for UserAST {
                 filter.phrase = parts_leven[0].to_string();
                 filter.levenshtein = Some(parts_leven[1].parse().unwrap());
             }
+            filter.wildcard = filter.phrase.contains(['*', '?']) && !filter.phrase.chars().all(|c| c == '*' || c == '?');
 
             if parts_field.len() > 1 {
                 return UserAST::Attributed(parts_field[0].to_string(), Box::new(UserAST::Leaf(Box::new(filter))));
@@ -52,7 +73,16 @@ impl fmt::Debug for UserAST {
         match self {
             UserAST::Attributed(attr, ast) => write!(formatter, "{}:{:?}", attr, ast),
             UserAST::BinaryClause(ast1, op, ast2) => write!(formatter, "({:?} {} {:?})", ast1, op, ast2),
+            UserAST::Not(ast) => write!(formatter, "NOT {:?}", ast),
+            UserAST::Required(ast) => write!(formatter, "+{:?}", ast),
+            UserAST::Near(filters, distance) if filters.len() == 1 => write!(formatter, "{:?}~{}", filters[0], distance),
+            UserAST::Near(filters, distance) => {
+                let filters: Vec<String> = filters.iter().map(|filter| format!("{:?}", filter)).collect();
+                write!(formatter, "({})", filters.join(&format!(" NEAR/{} ", distance)))
+            }
             UserAST::Leaf(filter) => write!(formatter, "{:?}", filter),
+            UserAST::Range(range) => write!(formatter, "{:?}", range),
+            UserAST::Boosted(ast, boost) => write!(formatter, "{:?}^{}", ast, boost),
         }
     }
 }
@@ -90,7 +120,10 @@ impl UserAST {
                     (None, None) => None,
                 };
             }
-            UserAST::Leaf(_filter) => {}
+            UserAST::Not(ast) => return UserAST::filter_ast(ast, should_filter, current_attr).map(|ast| UserAST::Not(ast.into())),
+            UserAST::Required(ast) => return UserAST::filter_ast(ast, should_filter, current_attr).map(|ast| UserAST::Required(ast.into())),
+            UserAST::Boosted(ast, boost) => return UserAST::filter_ast(ast, should_filter, current_attr).map(|ast| UserAST::Boosted(ast.into(), *boost)),
+            UserAST::Near(..) | UserAST::Leaf(_) | UserAST::Range(_) => {}
         }
 
         Some(self.clone())
@@ -112,7 +145,8 @@ impl UserAST {
                 *ast1 = Box::new(UserAST::map_ast(*ast1.clone(), map_fn, current_attr));
                 *ast2 = Box::new(UserAST::map_ast(*ast2.clone(), map_fn, current_attr));
             }
-            UserAST::Leaf(ref _filter) => {}
+            UserAST::Not(ref mut ast) | UserAST::Required(ref mut ast) | UserAST::Boosted(ref mut ast, _) => **ast = UserAST::map_ast(*ast.clone(), map_fn, current_attr),
+            UserAST::Near(..) | UserAST::Leaf(_) | UserAST::Range(_) => {}
         }
 
         map_fn(self, current_attr)
@@ -138,6 +172,19 @@ impl UserAST {
                 ast1._get_phrase_pairs(collect, last_term, curr_attr);
                 ast2._get_phrase_pairs(collect, last_term, curr_attr);
             }
+            UserAST::Required(ast) | UserAST::Boosted(ast, _) => ast._get_phrase_pairs(collect, last_term, curr_attr),
+            UserAST::Not(_) | UserAST::Range(_) => {
+                // negated terms and ranges are not part of a phrase
+                *last_term = None;
+            }
+            UserAST::Near(filters, _distance) => {
+                for filter in filters {
+                    if let Some(last_term) = last_term {
+                        collect.insert([last_term, &filter.phrase]);
+                    }
+                    *last_term = Some(&filter.phrase)
+                }
+            }
             UserAST::Leaf(filter) => {
                 if let Some(last_term) = last_term {
                     collect.insert([last_term, &filter.phrase]);
@@ -160,7 +207,16 @@ impl UserAST {
                 ast1.walk_terms(cb);
                 ast2.walk_terms(cb);
             }
+            UserAST::Not(ast) | UserAST::Required(ast) | UserAST::Boosted(ast, _) => {
+                ast.walk_terms(cb);
+            }
+            UserAST::Near(filters, _distance) => {
+                for filter in filters {
+                    cb(&filter.phrase);
+                }
+            }
             UserAST::Leaf(filter) => cb(&filter.phrase),
+            UserAST::Range(_) => {}
         }
     }
 }
@@ -171,6 +227,7 @@ mod test_ast {
         ast::{Operator::*, UserAST, UserFilter},
         parser::parse,
     };
+    use std::collections::HashSet;
 
     #[test]
     fn test_ast_external_lifetime() {
@@ -178,12 +235,16 @@ mod test_ast {
         let filter_1 = UserFilter {
             phrase: external_term_1,
             levenshtein: None,
+            quoted: false,
+            wildcard: false,
         };
         let left_ast: UserAST = UserAST::Leaf(Box::new(filter_1));
         let external_term_2 = "b".to_string();
         let filter_2 = UserFilter {
             phrase: external_term_2,
             levenshtein: None,
+            quoted: false,
+            wildcard: false,
         };
         let right_ast: UserAST = UserAST::Leaf(Box::new(filter_2));
 
@@ -222,6 +283,18 @@ mod test_ast {
             ),
             Some(UserAST::Attributed("myattr".to_string(), "super".into()))
         );
+
+        let ast: UserAST = parse("super -cool").unwrap();
+        assert_eq!(
+            ast.filter_ast(
+                &mut |ast, _attr| match ast {
+                    UserAST::Leaf(filter) => filter.phrase == "cool",
+                    _ => false,
+                },
+                None
+            ),
+            Some("super".into())
+        );
     }
     #[test]
     fn test_map_ast() {
@@ -231,6 +304,8 @@ mod test_ast {
                 UserAST::Leaf(ref map) if map.phrase == "cool" => UserAST::Leaf(Box::new(UserFilter {
                     phrase: "coolcool".to_string(),
                     levenshtein: None,
+                    quoted: false,
+                    wildcard: false,
                 })),
                 _ => ast,
             },
@@ -247,10 +322,14 @@ mod test_ast {
                     let leftast = UserAST::Leaf(Box::new(UserFilter {
                         phrase: "kawaii".to_string(),
                         levenshtein: None,
+                        quoted: false,
+                        wildcard: false,
                     }));
                     let rightast = UserAST::Leaf(Box::new(UserFilter {
                         phrase: "かわいい".to_string(),
                         levenshtein: None,
+                        quoted: false,
+                        wildcard: false,
                     }));
 
                     UserAST::BinaryClause(Box::new(leftast), Or, Box::new(rightast))
@@ -304,6 +383,11 @@ mod test_ast {
             [["super", "cool"], ["cool", "different"], ["different", "scope"]].iter().copied().collect()
         );
 
+        let ast: UserAST = parse("super -cool fancy").unwrap();
+        assert_eq!(ast.get_phrase_pairs(), HashSet::new());
+        let ast: UserAST = parse("super cool -fancy").unwrap();
+        assert_eq!(ast.get_phrase_pairs(), [["super", "cool"]].iter().copied().collect());
+
         // let ast: UserAST = parse("different scope OR myattr:(super cool)").unwrap();
         // assert_eq!(
         //     ast.get_phrase_pairs(),
@@ -4,6 +4,10 @@ pub struct UserFilter {
     pub phrase: String,
     /// levenshtein edit distance https://en.wikipedia.org/wiki/Levenshtein_distance
     pub levenshtein: Option<u8>,
+    /// the term was in quotes, e.g. `"red apple"`, and should match as exact phrase
+    pub quoted: bool,
+    /// the term contains the wildcards `*` or `?`, e.g. `haus*` or `te?m`
+    pub wildcard: bool,
 }
 
 impl std::fmt::Debug for UserFilter {
@@ -0,0 +1,103 @@
+/// A range on an attribute, e.g. `year:[2000 TO 2010]`, `year:{2000 TO *]` or `price:<20`
+#[derive(Clone, PartialEq, Eq)]
+pub struct UserRange {
+    /// the lower bound, `None` is unbounded
+    pub lower: Option<String>,
+    pub lower_inclusive: bool,
+    /// the upper bound, `None` is unbounded
+    pub upper: Option<String>,
+    pub upper_inclusive: bool,
+}
+
+impl UserRange {
+    /// Parses `[lower TO upper]` with inclusive `[]` and exclusive `{}` bounds, where `*` is unbounded,
+    /// or a comparison `<upper`, `<=upper`, `>lower` and `>=lower`.
+    ///
+    /// Returns `None` if the text is no range or the range has no bound.
+    pub(crate) fn parse(text: &str) -> Option<UserRange> {
+        let comparisons = [(">=", true, true), ("<=", false, true), (">", true, false), ("<", false, false)];
+        for (op, is_lower, inclusive) in comparisons {
+            if let Some(value) = text.strip_prefix(op) {
+                if value.is_empty() || value.starts_with(['<', '>', '=']) || value.contains(char::is_whitespace) {
+                    return None;
+                }
+                let value = Some(value.to_string());
+                return Some(if is_lower {
+                    UserRange {
+                        lower: value,
+                        lower_inclusive: inclusive,
+                        upper: None,
+                        upper_inclusive: false,
+                    }
+                } else {
+                    UserRange {
+                        lower: None,
+                        lower_inclusive: false,
+                        upper: value,
+                        upper_inclusive: inclusive,
+                    }
+                });
+            }
+        }
+
+        let lower_inclusive = match text.chars().next()? {
+            '[' => true,
+            '{' => false,
+            _ => return None,
+        };
+        let upper_inclusive = match text.chars().last()? {
+            ']' => true,
+            '}' => false,
+            _ => return None,
+        };
+        let inner = text.get(1..text.len() - 1)?;
+        let bound = |value: &str| if value == "*" { None } else { Some(value.to_string()) };
+        match inner.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [lower, "TO", upper] if *lower != "*" || *upper != "*" => Some(UserRange {
+                lower: bound(lower),
+                lower_inclusive,
+                upper: bound(upper),
+                upper_inclusive,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Debug for UserRange {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match (&self.lower, &self.upper) {
+            (Some(lower), Some(upper)) => {
+                let open = if self.lower_inclusive { '[' } else { '{' };
+                let close = if self.upper_inclusive { ']' } else { '}' };
+                write!(formatter, "{}{} TO {}{}", open, lower, upper, close)
+            }
+            (Some(lower), None) => write!(formatter, "{}{}", if self.lower_inclusive { ">=" } else { ">" }, lower),
+            (None, Some(upper)) => write!(formatter, "{}{}", if self.upper_inclusive { "<=" } else { "<" }, upper),
+            (None, None) => write!(formatter, "[* TO *]"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(format!("{:?}", UserRange::parse("[2000 TO 2010]").unwrap()), "[2000 TO 2010]");
+        assert_eq!(format!("{:?}", UserRange::parse("{2000 TO 2010]").unwrap()), "{2000 TO 2010]");
+        assert_eq!(format!("{:?}", UserRange::parse("[2000 TO *}").unwrap()), ">=2000");
+        assert_eq!(format!("{:?}", UserRange::parse("{* TO 2010}").unwrap()), "<2010");
+        assert_eq!(format!("{:?}", UserRange::parse(">=2023-01-01").unwrap()), ">=2023-01-01");
+        assert_eq!(format!("{:?}", UserRange::parse("<20").unwrap()), "<20");
+        assert!(UserRange::parse("<=20").unwrap().upper_inclusive);
+
+        assert_eq!(UserRange::parse("[* TO *]"), None);
+        assert_eq!(UserRange::parse("[2000 2010]"), None);
+        assert_eq!(UserRange::parse("[2000 TO 2010"), None);
+        assert_eq!(UserRange::parse("<"), None);
+        assert_eq!(UserRange::parse("<<2"), None);
+        assert_eq!(UserRange::parse("2000"), None);
+    }
+}
@@ -1,10 +1,60 @@
-#[derive(Debug, Eq, PartialEq)]
-pub enum ParseError {
-    EmptyParentheses(String),
-    UnexpectedTokenType(String, String),
-    ExpectedNumber(String),
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ParseErrorKind {
+    /// Parentheses without content, e.g. "()"
+    EmptyParentheses,
+    /// A "(" without ")" or a ")" without "("
+    UnbalancedParentheses,
+    /// A quote without closing quote, e.g. "\"red apple"
+    UnclosedQuote,
+    /// A token which is not allowed at this position, e.g. "fancy AND"
+    UnexpectedTokenType,
+    /// The number after "~", "^" or "NEAR/" is invalid
+    ExpectedNumber,
+}
+
+/// Error of the parser with the position in the query, which can be used to point the user to the problem.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub message: String,
+    /// Description of what is expected at the position, e.g. "a term, an attribute or (", empty if unknown
+    pub expected: String,
+    /// Offset in chars of the start of the problematic part in the query
+    pub start: usize,
+    /// Offset in chars of the end of the problematic part in the query, equal to `start` at the end of the query
+    pub stop: usize,
+    /// The query with the problematic part marked, e.g. "fancy~﹏﹏"
+    pub marked_in_orig: String,
+}
+
+impl ParseError {
+    /// Creates an error for the text between the byte positions `start` and `stop`
+    pub(crate) fn new(kind: ParseErrorKind, text: &str, start: usize, stop: usize, message: impl Into<String>, expected: impl Into<String>) -> Self {
+        ParseError {
+            kind,
+            message: message.into(),
+            expected: expected.into(),
+            start: text[..start].chars().count(),
+            stop: text[..stop].chars().count(),
+            marked_in_orig: marked_in_orig(text, start, stop),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{} at {}..{}", self.message.trim_end(), self.start, self.stop)?;
+        if !self.expected.is_empty() {
+            write!(formatter, ", expected {}", self.expected)?;
+        }
+        write!(formatter, ": {}", self.marked_in_orig)
+    }
 }
 
+impl std::error::Error for ParseError {}
+
 pub(crate) fn marked_in_orig(text: &str, start: usize, stop: usize) -> String {
     format!("{}﹏{}﹏{}", &text[..start], &text[start..stop], &text[stop..])
 }
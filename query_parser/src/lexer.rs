@@ -1,4 +1,8 @@
-use crate::{error::ParseError, Options};
+use crate::{
+    ast::UserRange,
+    error::{ParseError, ParseErrorKind},
+    Options,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum TokenType {
@@ -10,12 +14,22 @@ pub(crate) enum TokenType {
     ParenthesesOpen,
     ParenthesesClose,
     Tilde,
+    /// '^' before a boost, e.g. "term^2.5"
+    Caret,
 
     // 2-char
     Or,
 
     //3-char
     And,
+    /// "NOT " or a leading '-', e.g. "-term"
+    Not,
+    /// A leading '+', e.g. "+term"
+    Required,
+    /// "NEAR/" with a distance, e.g. "NEAR/5"
+    Near,
+    /// A range or comparison directly after an attribute, e.g. "[2000 TO 2010]" in "year:[2000 TO 2010]" or "<20" in "price:<20"
+    Range,
 }
 
 impl TokenType {
@@ -26,16 +40,36 @@ impl TokenType {
             // '"'  => Some(TokenType::DoubleQuotes),
             // '\'' => Some(TokenType::SingleQuotes),
             '~' if !options.no_levensthein => Some(TokenType::Tilde),
+            '^' if !options.no_boost => Some(TokenType::Caret),
             // ':' => Some(TokenType::Colon),
             _ => None,
         }
     }
+
+    /// Description of the token type for error messages
+    pub(crate) fn describe(self) -> &'static str {
+        match self {
+            TokenType::AttributeLiteral => "an attribute",
+            TokenType::Literal => "a term",
+            TokenType::ParenthesesOpen => "(",
+            TokenType::ParenthesesClose => ")",
+            TokenType::Tilde => "~",
+            TokenType::Caret => "^",
+            TokenType::Or => "OR",
+            TokenType::And => "AND",
+            TokenType::Not => "NOT",
+            TokenType::Required => "+",
+            TokenType::Near => "NEAR/",
+            TokenType::Range => "a range",
+        }
+    }
 }
 
 fn is_seperator(cha: char, options: &Options) -> bool {
     match cha {
         '(' | ')' if !options.no_parentheses => true,
         '~' if !options.no_levensthein => true,
+        '^' if !options.no_boost => true,
         ':' if !options.no_attributes => true,
         _ => false,
     }
@@ -46,7 +80,8 @@ pub(crate) struct Token {
     pub(crate) byte_start_pos: u32,
     pub(crate) byte_stop_pos: u32,
     pub(crate) token_type: TokenType,
-    // pub(crate) in_quotes: bool,
+    /// the token was in quotes, e.g. "my quote"
+    pub(crate) in_quotes: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -65,6 +100,8 @@ pub(crate) struct Lexer<'a> {
     current_pos: u32,
     current_byte_pos: u32,
     options: Options,
+    /// Positions of chars, which are part of a literal, even if they are syntax, e.g. an unbalanced parenthesis in lenient parsing
+    literal_positions: Vec<u32>,
 }
 
 impl<'a> Lexer<'a> {
@@ -75,6 +112,7 @@ impl<'a> Lexer<'a> {
             current_byte_pos: 0,
             text,
             options: Default::default(), // in_quotes:false,
+            literal_positions: vec![],
         }
     }
 
@@ -85,9 +123,15 @@ impl<'a> Lexer<'a> {
             current_byte_pos: 0,
             text,
             options, // in_quotes:false,
+            literal_positions: vec![],
         }
     }
 
+    pub(crate) fn with_literal_positions(mut self, literal_positions: Vec<u32>) -> Self {
+        self.literal_positions = literal_positions;
+        self
+    }
+
     pub(crate) fn get_tokens(&mut self) -> Result<Vec<Token>, ParseError> {
         let mut tokens = Vec::with_capacity(self.chars.len() / 4);
         while let Some(token) = self.next_token()? {
@@ -127,15 +171,59 @@ impl<'a> Lexer<'a> {
                     self.eat_chars(2);
                     Some(TokenType::Or)
                 }
+                ['N', 'O', 'T', ' ', ..] if !self.options.no_negation && self.is_start_of_clause() => {
+                    // NOT requires whitespace, but can also start the query
+                    self.eat_chars(3);
+                    Some(TokenType::Not)
+                }
+                ['N', 'E', 'A', 'R', '/', ..] if !self.options.no_proximity && self.prev_char_is_whitespace() && self.is_near_distance_at(self.current_pos + 5) => {
+                    // NEAR requires whitespace and is followed directly by the distance
+                    self.eat_chars(5);
+                    self.eat_while(|c| c.is_ascii_digit());
+                    Some(TokenType::Near)
+                }
+                ['-', next, ..] if !self.options.no_negation && !next.is_whitespace() => {
+                    // a minus only negates at the start of a token, "foo-bar" stays a literal
+                    self.eat_char();
+                    Some(TokenType::Not)
+                }
+                ['+', next, ..] if !self.options.no_required && !next.is_whitespace() => {
+                    // a plus only marks a required clause at the start of a token, "c++" stays a literal
+                    self.eat_char();
+                    Some(TokenType::Required)
+                }
                 _ => None,
             };
 
-            if self.is_doublequote(self.current_pos) {
+            if token_type.is_none() {
+                if let Some(range_len) = self.range_len_at(self.current_pos) {
+                    self.eat_chars(range_len);
+                    return Ok(Some(Token {
+                        token_type: TokenType::Range,
+                        byte_start_pos,
+                        byte_stop_pos: self.current_byte_pos,
+                        in_quotes: false,
+                    }));
+                }
+            }
+
+            if token_type.is_none() && self.is_doublequote(self.current_pos) {
+                let quote_byte_pos = byte_start_pos as usize;
                 self.eat_char();
                 byte_start_pos += 1; // move behind quote
                 while self.cur_char().is_some() && !self.is_doublequote(self.current_pos) {
                     self.eat_char();
                 }
+                if self.cur_char().is_none() {
+                    return Err(ParseError::new(
+                        ParseErrorKind::UnclosedQuote,
+                        self.text,
+                        quote_byte_pos,
+                        quote_byte_pos + 1,
+                        "quote is not closed ",
+                        "a closing quote",
+                    ));
+                }
                 // TODO next should not be a quote again, because "yeah""cool" - this weird
                 // if let Some(cur_char) = cur_char {
                 //     if cur_char == "\""{
@@ -157,10 +245,11 @@ impl<'a> Lexer<'a> {
                     token_type,
                     byte_start_pos,
                     byte_stop_pos,
+                    in_quotes: true,
                 }));
             }
 
-            if let Some(tt) = TokenType::from_single_char(c, &self.options) {
+            if let Some(tt) = TokenType::from_single_char(c, &self.options).filter(|_| !self.is_literal_at(self.current_pos)) {
                 token_type = Some(tt);
                 self.eat_char();
             }
@@ -171,14 +260,19 @@ impl<'a> Lexer<'a> {
                     token_type,
                     byte_start_pos,
                     byte_stop_pos,
+                    in_quotes: false,
                 });
                 return Ok(next_token);
             }
 
             // Literal
             self.eat_char();
-            let opt = self.options;
-            self.eat_while(|c| !c.is_whitespace() && !is_seperator(c, &opt));
+            while let Some(c) = self.cur_char() {
+                if c.is_whitespace() || (is_seperator(c, &self.options) && !self.is_literal_at(self.current_pos)) {
+                    break;
+                }
+                self.eat_char();
+            }
             let byte_stop_pos = self.current_byte_pos;
             let token_type = if self.is_attr_colon_at(self.current_pos) {
                 self.eat_char();
@@ -190,6 +284,7 @@ impl<'a> Lexer<'a> {
                 token_type,
                 byte_start_pos,
                 byte_stop_pos,
+                in_quotes: false,
             }))
         } else {
             Ok(None)
@@ -200,9 +295,13 @@ impl<'a> Lexer<'a> {
         !self.options.no_attributes && self.chars.get(pos as usize).map(|c| *c == ':').unwrap_or(false)
     }
 
+    fn is_literal_at(&self, pos: u32) -> bool {
+        self.literal_positions.contains(&pos)
+    }
+
     // is quote
     pub fn is_doublequote(&self, pos: u32) -> bool {
-        self.chars.get(pos as usize).cloned().map(|c| c == '"').unwrap_or(false)
+        !self.is_literal_at(pos) && self.chars.get(pos as usize).cloned().map(|c| c == '"').unwrap_or(false)
         // && (self.current_pos == 0 || self.chars.get(pos as usize - 1).cloned().map(|c| c != '\\').unwrap_or(false))
     }
 
@@ -226,6 +325,35 @@ impl<'a> Lexer<'a> {
         self.current_pos != 0 && self.chars.get(self.current_pos as usize - 1).cloned().map(char::is_whitespace).unwrap_or(false)
     }
 
+    /// true if there is a number at the position, which is followed by whitespace
+    pub fn is_near_distance_at(&self, pos: u32) -> bool {
+        let digits = self.chars[pos as usize..].iter().take_while(|c| c.is_ascii_digit()).count();
+        digits > 0 && self.chars.get(pos as usize + digits).map(|c| c.is_whitespace()).unwrap_or(false)
+    }
+
+    /// Returns the number of chars of a range or comparison, if there is one at the position directly after an attribute
+    pub fn range_len_at(&self, pos: u32) -> Option<usize> {
+        let pos = pos as usize;
+        if self.options.no_range || self.options.no_attributes || pos == 0 || self.chars[pos - 1] != ':' {
+            return None;
+        }
+        let len = match self.chars[pos] {
+            '[' | '{' => self.chars[pos..].iter().position(|c| *c == ']' || *c == '}')? + 1,
+            '<' | '>' => self.chars[pos..]
+                .iter()
+                .take_while(|c| !c.is_whitespace() && (self.options.no_parentheses || **c != ')'))
+                .count(),
+            _ => return None,
+        };
+        let text: String = self.chars[pos..pos + len].iter().collect();
+        UserRange::parse(&text).map(|_| len)
+    }
+
+    /// true at the start of the text, after whitespace or after an opening parentheses
+    pub fn is_start_of_clause(&self) -> bool {
+        self.current_pos == 0 || self.prev_char_is_whitespace() || (!self.options.no_parentheses && self.chars[self.current_pos as usize - 1] == '(')
+    }
+
     pub fn cur_char(&self) -> Option<char> {
         self.chars.get(self.current_pos as usize).cloned()
     }
@@ -275,6 +403,71 @@ mod tests {
         assert_eq!(Lexer::new("ANDand AND    ").get_tokens_text(), ["ANDand", "AND"]);
     }
 
+    #[test]
+    fn test_not() {
+        assert_eq!(Lexer::new("NOT cool").get_token_types(), [TT::Not, TT::Literal]);
+        assert_eq!(Lexer::new("nice NOT cool").get_token_types(), [TT::Literal, TT::Not, TT::Literal]);
+        assert_eq!(
+            Lexer::new("(NOT cool)").get_token_types(),
+            [TT::ParenthesesOpen, TT::Not, TT::Literal, TT::ParenthesesClose]
+        );
+        assert_eq!(Lexer::new("coolNOT cool").get_token_types(), [TT::Literal, TT::Literal]);
+        assert_eq!(Lexer::new("NOT").get_token_types(), [TT::Literal]);
+        assert_eq!(Lexer::new("-cool").get_token_types(), [TT::Not, TT::Literal]);
+        assert_eq!(Lexer::new("-cool").get_tokens_text(), ["-", "cool"]);
+        assert_eq!(Lexer::new("field:-cool").get_token_types(), [TT::AttributeLiteral, TT::Not, TT::Literal]);
+        assert_eq!(Lexer::new("-\"cool nice\"").get_token_types(), [TT::Not, TT::Literal]);
+        assert_eq!(Lexer::new("foo-bar - 5").get_token_types(), [TT::Literal, TT::Literal, TT::Literal]);
+        assert_eq!(
+            Lexer::new("+cool c++ + 5").get_token_types(),
+            [TT::Required, TT::Literal, TT::Literal, TT::Literal, TT::Literal]
+        );
+        assert_eq!(Lexer::new("field:+cool").get_token_types(), [TT::AttributeLiteral, TT::Required, TT::Literal]);
+    }
+
+    #[test]
+    fn test_not_disabled() {
+        let opt = Options {
+            no_negation: true,
+            ..Default::default()
+        };
+        assert_eq!(Lexer::new_with_opt("NOT -cool", opt).get_token_types(), [TT::Literal, TT::Literal]);
+        assert_eq!(Lexer::new_with_opt("NOT -cool", opt).get_tokens_text(), ["NOT", "-cool"]);
+    }
+
+    #[test]
+    fn test_near() {
+        assert_eq!(Lexer::new("apple NEAR/5 pie").get_token_types(), [TT::Literal, TT::Near, TT::Literal]);
+        assert_eq!(Lexer::new("apple NEAR/5 pie").get_tokens_text(), ["apple", "NEAR/5", "pie"]);
+        // without distance or whitespace it's a literal
+        assert_eq!(Lexer::new("apple NEAR/ pie").get_token_types(), [TT::Literal, TT::Literal, TT::Literal]);
+        assert_eq!(Lexer::new("apple NEAR/5pie").get_token_types(), [TT::Literal, TT::Literal]);
+        assert_eq!(Lexer::new("NEAR/5 pie").get_token_types(), [TT::Literal, TT::Literal]);
+
+        let opt = Options {
+            no_proximity: true,
+            ..Default::default()
+        };
+        assert_eq!(Lexer::new_with_opt("apple NEAR/5 pie", opt).get_token_types(), [TT::Literal, TT::Literal, TT::Literal]);
+    }
+
+    #[test]
+    fn test_range() {
+        assert_eq!(Lexer::new("year:[2000 TO 2010]").get_token_types(), [TT::AttributeLiteral, TT::Range]);
+        assert_eq!(Lexer::new("year:[2000 TO 2010] cool").get_tokens_text(), ["year", "[2000 TO 2010]", "cool"]);
+        assert_eq!(Lexer::new("(price:<20)").get_tokens_text(), ["(", "price", "<20", ")"]);
+        assert_eq!(Lexer::new("price:>=20^2").get_token_types(), [TT::AttributeLiteral, TT::Range]);
+        // only directly after an attribute
+        assert_eq!(Lexer::new("<20 [a TO b]").get_token_types(), [TT::Literal, TT::Literal, TT::Literal, TT::Literal]);
+        assert_eq!(Lexer::new("year:[2000 2010]").get_token_types(), [TT::AttributeLiteral, TT::Literal, TT::Literal]);
+
+        let opt = Options {
+            no_range: true,
+            ..Default::default()
+        };
+        assert_eq!(Lexer::new_with_opt("price:<20", opt).get_token_types(), [TT::AttributeLiteral, TT::Literal]);
+    }
+
     #[test]
     fn test_parentheses() {
         assert_eq!(Lexer::new("(cool)").get_tokens_text(), ["(", "cool", ")"]);
@@ -294,7 +487,10 @@ mod tests {
         assert_eq!(Lexer::new(r#"asdf""#).get_tokens_text(), [r#"asdf""#]);
 
         // assert_eq!(Lexer::new(r#""my quote""#).get_tokens_text(), ["\"", "my", "quote", "\""]);
-        // assert_eq!(Lexer::new(r#""my quote""#).get_tokens().into_iter().map(|t|t.in_quotes).collect::<Vec<_>>(), [false, true, true, true, false]);
+        assert_eq!(
+            Lexer::new(r#""my quote" other"#).get_tokens().unwrap().into_iter().map(|t| t.in_quotes).collect::<Vec<_>>(),
+            [true, false]
+        );
     }
 
     //TODO there is no quote escaping
@@ -25,11 +25,78 @@ title:(my booktitle)
 ## Parentheses
 (cool AND nice) OR (thick and woke)
 
+## Negation
+Excludes documents matching the negated term, attribute or parentheses.
+Negations only exclude, a query with only negations matches nothing.
+
+NOT fred
+
+-fred
+
+name:-fred
+
+cool -(thick nice)
+
+## Required
+Clauses with a leading + are required, the other clauses connected with OR are then optional.
+Optional clauses only add to the score of documents matching the required clauses.
+
++fred wilma -barney
+
++name:fred
+
+## Proximity
+Matches terms within a distance of each other, the distance is the number of other tokens allowed between them.
+The order of the terms doesn't matter.
+
+apple NEAR/5 pie
+
+"search engine"~3
+
+## Wildcards
+`*` matches any number of characters and `?` exactly one character.
+Wildcards in quotes are part of the searchterm.
+
+haus*
+
+te?m
+
+*term
+
+## Boost
+Multiplies the score of a term, an attribute or parentheses with the boost after ^
+
+searchterm^2.5
+
+title:searchterm^3
+
+(cool nice)^0.5
+
+## Ranges
+Ranges on an attribute with inclusive [] and exclusive {} bounds, * is unbounded.
+Comparisons with <, <=, > and >= on an attribute.
+
+year:[2000 TO 2010]
+
+year:{2000 TO *]
+
+price:<20
+
+date:>=2023-01-01
+
 ## Levenshtein Distance
 
 The levenshtein edit distance for a token can be defined with ~
 e.g. searchterm~2 means a edit distance of 2 for searchterm,
 that means searchtuam would be a hit, because the edit distance is 2.
+After quotes ~ defines the proximity instead, see above.
+
+# Errors
+A [`ParseError`](error::ParseError) contains the offsets in chars of the problematic part in the query
+and a description of the expected tokens, e.g. "fancy AND " expects "a term, an attribute, (, NOT or +" at 10..10.
+
+With the option `lenient` unbalanced parentheses and quotes are part of the searchterm, e.g. "(fancy nice" is parsed as
+the terms "(fancy" and "nice". Queries which are still invalid are parsed as OR connected words.
 
 */
 #![warn(missing_debug_implementations, rust_2018_idioms)]
@@ -51,5 +118,26 @@ pub struct Options {
     /// This setting will disable defining a levensthtein distance after a searchterm
     /// e.g. "searchterm~2"
     pub no_levensthein: bool,
+    /// This setting will disable parsing of negations
+    /// e.g. "NOT searchterm", "-searchterm" or "myfield:-searchterm"
+    pub no_negation: bool,
+    /// This setting will disable parsing of required clauses
+    /// e.g. "+searchterm" or "myfield:+searchterm"
+    pub no_required: bool,
+    /// This setting will disable parsing of proximity queries, a distance after quotes is then a levenshtein distance
+    /// e.g. "apple NEAR/5 pie" or "\"search engine\"~3"
+    pub no_proximity: bool,
+    /// This setting will disable parsing of wildcards, `*` and `?` are then part of the searchterm
+    /// e.g. "haus*" or "te?m"
+    pub no_wildcard: bool,
+    /// This setting will disable boosts, `^` is then part of the searchterm
+    /// e.g. "searchterm^2" or "(nice cool)^0.5"
+    pub no_boost: bool,
+    /// This setting will disable parsing of ranges and comparisons on attributes, they are then searchterms
+    /// e.g. "year:[2000 TO 2010]" or "price:<20"
+    pub no_range: bool,
+    /// Unbalanced parentheses and quotes are part of the searchterm instead of an error.
+    /// Queries which are still invalid are searched as OR connected words, e.g. "fancy AND" or "()".
+    pub lenient: bool,
     // pub no_quotes: bool
 }
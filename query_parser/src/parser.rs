@@ -1,6 +1,6 @@
 use crate::{
     ast::*,
-    error::{marked_in_orig, ParseError},
+    error::{ParseError, ParseErrorKind},
     lexer::{Lexer, Token, TokenType},
     Options,
 };
@@ -10,6 +10,9 @@ pub struct Parser<'a> {
     text: &'a str,
     tokens: Vec<Token>,
     pos: usize,
+    options: Options,
+    /// Number of currently open parentheses
+    depth: usize,
 }
 
 macro_rules! return_binary_clause {
@@ -23,44 +26,107 @@ pub(crate) fn get_text_for_token(text: &str, start: u32, stop: u32) -> &str {
 }
 
 pub fn parse(text: &str) -> Result<UserAST, ParseError> {
-    Parser::new(text)?._parse()
+    Parser::new(text)?.parse_all()
 }
 pub fn parse_with_opt(text: &str, options: Options) -> Result<UserAST, ParseError> {
-    Parser::new_with_opt(text, options)?._parse()
+    if options.lenient {
+        return parse_lenient(text, options);
+    }
+    Parser::new_with_opt(text, options)?.parse_all()
+}
+
+/// Unbalanced parentheses and quotes are parsed as part of a literal. If the query is still invalid,
+/// its words are OR connected terms. Only a query without words returns an error.
+fn parse_lenient(text: &str, options: Options) -> Result<UserAST, ParseError> {
+    let mut literal_positions = vec![];
+    loop {
+        let mut lexer = Lexer::new_with_opt(text, options).with_literal_positions(literal_positions.clone());
+        let err = match lexer.get_tokens().and_then(|tokens| Parser::from_tokens(text, tokens, options).parse_all()) {
+            Ok(ast) => return Ok(ast),
+            Err(err) => err,
+        };
+        let is_unbalanced = matches!(err.kind, ParseErrorKind::UnbalancedParentheses | ParseErrorKind::UnclosedQuote);
+        if is_unbalanced && !literal_positions.contains(&(err.start as u32)) {
+            literal_positions.push(err.start as u32);
+            continue;
+        }
+        return text
+            .split_whitespace()
+            .map(|word| {
+                UserAST::Leaf(Box::new(UserFilter {
+                    phrase: word.to_string(),
+                    levenshtein: None,
+                    quoted: false,
+                    wildcard: false,
+                }))
+            })
+            .rev()
+            .reduce(|right, left| UserAST::BinaryClause(Box::new(left), Operator::Or, Box::new(right)))
+            .ok_or(err);
+    }
+}
+
+fn describe_types(types: &[Option<TokenType>]) -> String {
+    let descriptions: Vec<&str> = types.iter().map(|token_type| token_type.map(TokenType::describe).unwrap_or("end of query")).collect();
+    match descriptions.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+        _ => descriptions.join(""),
+    }
 }
 
 impl<'a> Parser<'a> {
     pub fn new(text: &'a str) -> Result<Self, ParseError> {
         let tokens = Lexer::new(text).get_tokens()?;
-        Ok(Parser { tokens, pos: 0, text })
+        Ok(Self::from_tokens(text, tokens, Default::default()))
     }
 
     pub fn new_with_opt(text: &'a str, options: Options) -> Result<Self, ParseError> {
         let tokens = Lexer::new_with_opt(text, options).get_tokens()?;
-        Ok(Parser { tokens, pos: 0, text })
+        Ok(Self::from_tokens(text, tokens, options))
     }
 
-    fn unexpected_token_type(&self, message: &'static str, allowed_types: Option<&[Option<TokenType>]>) -> Result<(), ParseError> {
-        // generate snippet
+    fn from_tokens(text: &'a str, tokens: Vec<Token>, options: Options) -> Self {
+        Parser {
+            tokens,
+            pos: 0,
+            text,
+            options,
+            depth: 0,
+        }
+    }
+
+    /// Parses the whole query, only a closing parenthesis without opening one can be left over by `_parse`
+    fn parse_all(&mut self) -> Result<UserAST, ParseError> {
+        let ast = self._parse()?;
+        if self.pos < self.tokens.len() {
+            return Err(self.error_at_token(
+                ParseErrorKind::UnbalancedParentheses,
+                self.pos,
+                "closing parenthesis without opening parenthesis ",
+                describe_types(&[Some(TokenType::And), Some(TokenType::Or), None]),
+            ));
+        }
+        Ok(ast)
+    }
+
+    /// Creates an error for the token at `pos` or for the end of the query, if there is no token
+    fn error_at_token(&self, kind: ParseErrorKind, pos: usize, message: impl Into<String>, expected: impl Into<String>) -> ParseError {
         let [start, stop] = self
             .tokens
-            .get(self.pos)
-            .map(|next_token| [next_token.byte_start_pos as usize, next_token.byte_stop_pos as usize])
+            .get(pos)
+            .map(|token| [token.byte_start_pos as usize, token.byte_stop_pos as usize])
             .unwrap_or_else(|| [self.text.len(), self.text.len()]);
-        let marked_in_orig = marked_in_orig(self.text, start, stop);
-
-        let err = if message.is_empty() {
-            let message = format!(
-                "{} Unexpected token_type, got {}{:?}",
-                message,
-                self.get_type().map(|el| format!("{:?}", el)).unwrap_or_else(|| "EOF".to_string()),
-                allowed_types.map(|el| format!(" allowed_types: {:?}", el)).unwrap_or_default()
-            );
-            ParseError::UnexpectedTokenType(marked_in_orig, message)
+        ParseError::new(kind, self.text, start, stop, message, expected)
+    }
+
+    fn unexpected_token_type(&self, message: &'static str, allowed_types: Option<&[Option<TokenType>]>) -> Result<(), ParseError> {
+        let message = if message.is_empty() {
+            format!("Unexpected {} ", self.get_type().map(TokenType::describe).unwrap_or("end of query"))
         } else {
-            ParseError::UnexpectedTokenType(marked_in_orig, message.to_string())
+            message.to_string()
         };
-        Err(err)
+        let expected = allowed_types.map(describe_types).unwrap_or_default();
+        Err(self.error_at_token(ParseErrorKind::UnexpectedTokenType, self.pos, message, expected))
     }
 
     fn assert_allowed_types(&self, message: &'static str, allowed_types: &[Option<TokenType>]) -> Result<(), ParseError> {
@@ -70,33 +136,129 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
+    /// Like `assert_allowed_types` for a literal, but with a description of the expected value, e.g. "a positive number"
+    fn assert_literal(&self, message: &'static str, expected: &'static str) -> Result<(), ParseError> {
+        if !self.is_type(TokenType::Literal) {
+            return Err(self.error_at_token(ParseErrorKind::UnexpectedTokenType, self.pos, message, expected));
+        }
+        Ok(())
+    }
+
     fn next_token(&mut self) -> Result<Token, ParseError> {
-        let token = self.tokens.get(self.pos).unwrap();
+        let token = *self
+            .tokens
+            .get(self.pos)
+            .ok_or_else(|| self.error_at_token(ParseErrorKind::UnexpectedTokenType, self.pos, "unexpected end of query ", ""))?;
         self.pos += 1;
-        Ok(*token)
+        Ok(token)
     }
 
     fn parse_user_filter(&mut self, curr_token: Token) -> Result<UserFilter, ParseError> {
+        let phrase = get_text_for_token(self.text, curr_token.byte_start_pos, curr_token.byte_stop_pos).to_string();
+        // a term consisting only of wildcards, e.g. "???", is searched as is
+        let wildcard = !self.options.no_wildcard && !curr_token.in_quotes && phrase.contains(['*', '?']) && !phrase.chars().all(|c| c == '*' || c == '?');
         let mut curr_ast = UserFilter {
             levenshtein: None,
-            phrase: get_text_for_token(self.text, curr_token.byte_start_pos, curr_token.byte_stop_pos).to_string(),
+            phrase,
+            quoted: curr_token.in_quotes,
+            wildcard,
         };
 
         // Optional: Define Levenshtein distance
         if self.is_type(TokenType::Tilde) {
             self.next_token()?; // Remove Tilde
 
-            self.assert_allowed_types("Expecting a levenshtein number after a \'~\' ", &[Some(TokenType::Literal)])?;
+            self.assert_literal("Expecting a levenshtein number after a \'~\' ", "a number from 0 to 255")?;
 
             let lev_token = self.next_token()?; // Remove levenshtein number
-            let levenshtein: u8 = get_text_for_token(self.text, lev_token.byte_start_pos, lev_token.byte_stop_pos)
-                .parse()
-                .map_err(|_e| ParseError::ExpectedNumber(format!("Expected number after tilde to define levenshtein distance but got {:?}", lev_token)))?;
+            let levenshtein: u8 = get_text_for_token(self.text, lev_token.byte_start_pos, lev_token.byte_stop_pos).parse().map_err(|_e| {
+                self.error_at_token(
+                    ParseErrorKind::ExpectedNumber,
+                    self.pos - 1,
+                    "Expected number after tilde to define levenshtein distance ",
+                    "a number from 0 to 255",
+                )
+            })?;
             curr_ast.levenshtein = Some(levenshtein);
         }
         Ok(curr_ast)
     }
 
+    /// Parses a literal, which may be followed by `NEAR/distance` and further literals.
+    ///
+    /// A distance after quotes, e.g. `"search engine"~3`, is also a proximity.
+    fn parse_literal(&mut self, curr_token: Token) -> Result<UserAST, ParseError> {
+        let mut filter = self.parse_user_filter(curr_token)?;
+        let mut near: Option<(Vec<UserFilter>, u32)> = None;
+        if filter.quoted && !self.options.no_proximity {
+            if let Some(distance) = filter.levenshtein.take() {
+                near = Some((vec![filter.clone()], u32::from(distance)));
+            }
+        }
+        while self.is_type(TokenType::Near) {
+            if filter.levenshtein.is_some() {
+                self.unexpected_token_type("levenshtein distance not allowed in NEAR ", None)?;
+            }
+            let near_token = self.next_token()?;
+            let distance: u32 = get_text_for_token(self.text, near_token.byte_start_pos + 5, near_token.byte_stop_pos)
+                .parse()
+                .map_err(|_e| {
+                    self.error_at_token(
+                        ParseErrorKind::ExpectedNumber,
+                        self.pos - 1,
+                        "Expected number after NEAR/ to define the distance ",
+                        "a number",
+                    )
+                })?;
+            let (filters, near_distance) = near.get_or_insert_with(|| (vec![filter.clone()], distance));
+            if *near_distance != distance {
+                self.unexpected_token_type("only the same distance allowed in NEAR chain ", None)?;
+            }
+            self.assert_allowed_types("only token allowed after NEAR ", &[Some(TokenType::Literal)])?;
+            let next_token = self.next_token()?;
+            filter = self.parse_user_filter(next_token)?;
+            filters.push(filter.clone());
+        }
+        if filter.levenshtein.is_some() && near.is_some() {
+            self.unexpected_token_type("levenshtein distance not allowed in NEAR ", None)?;
+        }
+        let ast = match near {
+            Some((filters, distance)) => UserAST::Near(filters, distance),
+            None => UserAST::Leaf(Box::new(filter)),
+        };
+        self.parse_boost(ast)
+    }
+
+    /// Parses a range token, which was already validated by the lexer, e.g. "[2000 TO 2010]" or "<20"
+    fn parse_range(&mut self, curr_token: Token) -> Result<UserAST, ParseError> {
+        let text = get_text_for_token(self.text, curr_token.byte_start_pos, curr_token.byte_stop_pos);
+        let range = UserRange::parse(text).expect("range token is validated in the lexer");
+        self.parse_boost(UserAST::Range(Box::new(range)))
+    }
+
+    /// Parses an optional boost after a literal or parentheses, e.g. "term^2.5"
+    fn parse_boost(&mut self, ast: UserAST) -> Result<UserAST, ParseError> {
+        if !self.is_type(TokenType::Caret) {
+            return Ok(ast);
+        }
+        self.next_token()?; // Remove Caret
+        self.assert_literal("Expecting a boost number after a \'^\' ", "a positive number")?;
+        let boost_token = self.next_token()?;
+        let boost: f32 = get_text_for_token(self.text, boost_token.byte_start_pos, boost_token.byte_stop_pos)
+            .parse()
+            .ok()
+            .filter(|boost: &f32| boost.is_finite() && *boost >= 0.0)
+            .ok_or_else(|| {
+                self.error_at_token(
+                    ParseErrorKind::ExpectedNumber,
+                    self.pos - 1,
+                    "Expected positive number after ^ to define the boost ",
+                    "a positive number",
+                )
+            })?;
+        Ok(UserAST::Boosted(Box::new(ast), boost))
+    }
+
     fn parse_sub_expression(&mut self, curr_ast: UserAST) -> Result<UserAST, ParseError> {
         self.assert_allowed_types(
             "",
@@ -107,13 +269,15 @@ impl<'a> Parser<'a> {
                 Some(TokenType::ParenthesesClose),
                 Some(TokenType::And),
                 Some(TokenType::Or),
+                Some(TokenType::Not),
+                Some(TokenType::Required),
                 None,
             ],
         )?;
 
         if let Some(next_token_type) = self.get_type() {
             match next_token_type {
-                TokenType::AttributeLiteral | TokenType::Literal => {
+                TokenType::AttributeLiteral | TokenType::Literal | TokenType::ParenthesesOpen | TokenType::Not | TokenType::Required => {
                     return_binary_clause!(self, Operator::Or, curr_ast);
                 }
                 TokenType::Or => {
@@ -124,7 +288,7 @@ impl<'a> Parser<'a> {
                     self.next_token()?;
                     return_binary_clause!(self, Operator::And, curr_ast);
                 }
-                TokenType::ParenthesesOpen | TokenType::Tilde => unimplemented!(),
+                TokenType::Tilde | TokenType::Caret | TokenType::Near | TokenType::Range => unimplemented!(),
                 TokenType::ParenthesesClose => Ok(curr_ast),
             }
         } else {
@@ -132,7 +296,78 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Parses the content of parentheses, after the opening parentheses
+    fn parse_parentheses(&mut self) -> Result<UserAST, ParseError> {
+        let open_pos = self.pos - 1;
+        self.depth += 1;
+        let parenthesed_ast = self._parse()?;
+        if self.get_type().is_none() {
+            return Err(self.error_at_token(ParseErrorKind::UnbalancedParentheses, open_pos, "opening parenthesis is not closed ", ")"));
+        }
+        self.assert_allowed_types("", &[Some(TokenType::ParenthesesClose)])?;
+        self.next_token()?;
+        self.depth -= 1;
+        self.parse_boost(parenthesed_ast)
+    }
+
+    /// Error for a closing parenthesis where a term is expected
+    fn parentheses_close_error(&self) -> ParseError {
+        let expected = describe_types(&[Some(TokenType::Literal), Some(TokenType::AttributeLiteral), Some(TokenType::ParenthesesOpen)]);
+        if self.depth == 0 {
+            self.error_at_token(
+                ParseErrorKind::UnbalancedParentheses,
+                self.pos,
+                "closing parenthesis without opening parenthesis ",
+                expected,
+            )
+        } else if self.pos > 0 && self.tokens[self.pos - 1].token_type == TokenType::ParenthesesOpen {
+            self.error_at_token(ParseErrorKind::EmptyParentheses, self.pos, "empty parentheses ", expected)
+        } else {
+            self.error_at_token(ParseErrorKind::UnexpectedTokenType, self.pos, "expected a term before closing parenthesis ", expected)
+        }
+    }
+
+    /// Parses the part after a negation or a required modifier, which is a token, an attributed token or parentheses
+    fn parse_negated(&mut self) -> Result<UserAST, ParseError> {
+        self.assert_allowed_types(
+            "only token, attribute or ( allowed after negation ",
+            &[
+                Some(TokenType::Literal),
+                Some(TokenType::AttributeLiteral),
+                Some(TokenType::ParenthesesOpen),
+                Some(TokenType::Range),
+            ],
+        )?;
+        let curr_token = self.next_token()?;
+        match curr_token.token_type {
+            TokenType::Literal => self.parse_literal(curr_token),
+            TokenType::Range => self.parse_range(curr_token),
+            TokenType::AttributeLiteral => {
+                self.assert_allowed_types(
+                    "only token or ( allowed after attribute ('attr:') ",
+                    &[Some(TokenType::Literal), Some(TokenType::ParenthesesOpen), Some(TokenType::Range)],
+                )?;
+                let attr = get_text_for_token(self.text, curr_token.byte_start_pos, curr_token.byte_stop_pos).to_string();
+                Ok(UserAST::Attributed(attr, Box::new(self.parse_negated()?)))
+            }
+            _ => self.parse_parentheses(),
+        }
+    }
+
     fn _parse(&mut self) -> Result<UserAST, ParseError> {
+        if self.is_type(TokenType::ParenthesesClose) {
+            return Err(self.parentheses_close_error());
+        }
+        self.assert_allowed_types(
+            "",
+            &[
+                Some(TokenType::Literal),
+                Some(TokenType::AttributeLiteral),
+                Some(TokenType::ParenthesesOpen),
+                Some(TokenType::Not),
+                Some(TokenType::Required),
+            ],
+        )?;
         let curr_token = self.next_token()?;
         match curr_token.token_type {
             TokenType::AttributeLiteral => {
@@ -146,37 +381,68 @@ impl<'a> Parser<'a> {
                     }
                     Some(TokenType::Literal) => {
                         let token2 = self.next_token()?;
-                        let curr_ast = self.parse_user_filter(token2)?;
+                        let curr_ast = self.parse_literal(token2)?;
+                        let attributed_ast = UserAST::Attributed(
+                            get_text_for_token(self.text, curr_token.byte_start_pos, curr_token.byte_stop_pos).to_string(),
+                            Box::new(curr_ast),
+                        );
+                        return self.parse_sub_expression(attributed_ast);
+                    }
+                    Some(TokenType::Range) => {
+                        let token2 = self.next_token()?;
                         let attributed_ast = UserAST::Attributed(
                             get_text_for_token(self.text, curr_token.byte_start_pos, curr_token.byte_stop_pos).to_string(),
-                            Box::new(UserAST::Leaf(Box::new(curr_ast))),
+                            Box::new(self.parse_range(token2)?),
+                        );
+                        return self.parse_sub_expression(attributed_ast);
+                    }
+                    Some(TokenType::Not) => {
+                        self.next_token()?;
+                        let attributed_ast = UserAST::Attributed(
+                            get_text_for_token(self.text, curr_token.byte_start_pos, curr_token.byte_stop_pos).to_string(),
+                            Box::new(UserAST::Not(Box::new(self.parse_negated()?))),
+                        );
+                        return self.parse_sub_expression(attributed_ast);
+                    }
+                    Some(TokenType::Required) => {
+                        self.next_token()?;
+                        let attributed_ast = UserAST::Attributed(
+                            get_text_for_token(self.text, curr_token.byte_start_pos, curr_token.byte_stop_pos).to_string(),
+                            Box::new(UserAST::Required(Box::new(self.parse_negated()?))),
                         );
                         return self.parse_sub_expression(attributed_ast);
                     }
                     _ => self.unexpected_token_type(
                         "only token or ( allowed after attribute ('attr:') ",
-                        Some(&[Some(TokenType::Literal), Some(TokenType::ParenthesesOpen)]),
+                        Some(&[
+                            Some(TokenType::Literal),
+                            Some(TokenType::ParenthesesOpen),
+                            Some(TokenType::Range),
+                            Some(TokenType::Not),
+                            Some(TokenType::Required),
+                        ]),
                     )?,
                 };
             }
             TokenType::Literal => {
-                let curr_ast = self.parse_user_filter(curr_token)?;
-                return self.parse_sub_expression(UserAST::Leaf(Box::new(curr_ast)));
+                let curr_ast = self.parse_literal(curr_token)?;
+                return self.parse_sub_expression(curr_ast);
             }
 
             TokenType::ParenthesesOpen => {
-                let parenthesed_ast = self._parse()?;
-                self.assert_allowed_types("", &[Some(TokenType::ParenthesesClose)])?;
-                self.next_token()?;
+                let parenthesed_ast = self.parse_parentheses()?;
                 return self.parse_sub_expression(parenthesed_ast);
             }
-            TokenType::ParenthesesClose => unimplemented!(),
-            TokenType::Tilde => {
-                self.unexpected_token_type("", None)?; // IMPOSSIBURU!, should be covered by lookeaheads
+            TokenType::Not => {
+                let negated_ast = self.parse_negated()?;
+                return self.parse_sub_expression(UserAST::Not(Box::new(negated_ast)));
             }
-
-            TokenType::Or | TokenType::And => {
-                unimplemented!() // IMPOSSIBURU!, should be covered by lookeaheads
+            TokenType::Required => {
+                let required_ast = self.parse_negated()?;
+                return self.parse_sub_expression(UserAST::Required(Box::new(required_ast)));
+            }
+            TokenType::ParenthesesClose | TokenType::Tilde | TokenType::Caret | TokenType::Near | TokenType::Range | TokenType::Or | TokenType::And => {
+                self.unexpected_token_type("", None)?; // IMPOSSIBURU!, should be covered by lookeaheads
             }
         }
         unreachable!()
@@ -207,8 +473,9 @@ mod tests {
 
     #[test]
     fn test_phrases() {
-        assert_eq!(parse("\"cool\")").unwrap(), ("cool".into()));
-        assert_eq!(parse("\"cooles teil\")").unwrap(), ("cooles teil".into()));
+        assert_eq!(parse("\"cool\"").unwrap(), ("\"cool\"".into()));
+        assert_eq!(parse("\"cooles teil\"").unwrap(), ("\"cooles teil\"".into()));
+        assert_eq!(parse("\"cool\")").unwrap_err().kind, ParseErrorKind::UnbalancedParentheses);
     }
 
     #[test]
@@ -279,15 +546,12 @@ mod tests {
                 // field_name: None,
                 phrase: "fancy".to_string(),
                 levenshtein: Some(1),
+                quoted: false,
+                wildcard: false,
             }))
         );
-        assert_eq!(
-            parse("fancy~"),
-            Err(ParseError::UnexpectedTokenType(
-                "fancy~﹏﹏".to_string(),
-                "Expecting a levenshtein number after a \'~\' ".to_string()
-            ))
-        );
+        assert_parse_error("fancy~", ParseErrorKind::UnexpectedTokenType, "fancy~﹏﹏", "Expecting a levenshtein number after a \'~\' ");
+        assert_eq!(parse("fancy~").unwrap_err().expected, "a number from 0 to 255");
         assert_eq!(parse("fancy~1").unwrap(), "fancy~1".into());
         assert_eq!(
             parse("super cool OR fancy~1").unwrap(),
@@ -307,6 +571,8 @@ mod tests {
                 // field_name: None,
                 phrase: "fancy~1".to_string(),
                 levenshtein: None,
+                quoted: false,
+                wildcard: false,
             }))
         );
     }
@@ -320,6 +586,8 @@ mod tests {
                 Box::new(UserAST::Leaf(Box::new(UserFilter {
                     phrase: "fancy".to_string(),
                     levenshtein: Some(1),
+                    quoted: false,
+                    wildcard: false,
                 })))
             )
         );
@@ -331,6 +599,8 @@ mod tests {
                 Box::new(UserAST::Leaf(Box::new(UserFilter {
                     phrase: "fancy".to_string(),
                     levenshtein: Some(1),
+                    quoted: false,
+                    wildcard: false,
                 })))
             )
         );
@@ -356,7 +626,7 @@ mod tests {
     fn test_quote_on_quote() {
         assert_eq!(
             parse("\"field\"\"cool\"").unwrap(), // there should be a space
-            ("field".into(), Or, "cool".into()).into()
+            ("\"field\"".into(), Or, "\"cool\"".into()).into()
         );
     }
     // #[test]
@@ -377,6 +647,8 @@ mod tests {
                 Box::new(UserAST::Leaf(Box::new(UserFilter {
                     phrase: "fancy".to_string(),
                     levenshtein: None,
+                    quoted: false,
+                    wildcard: false,
                 })))
             )
         );
@@ -392,6 +664,8 @@ mod tests {
             UserAST::Leaf(Box::new(UserFilter {
                 phrase: "field:fancy".to_string(),
                 levenshtein: None,
+                quoted: false,
+                wildcard: false,
             }))
         );
     }
@@ -410,12 +684,11 @@ mod tests {
     }
     #[test]
     fn test_attribute_errors() {
-        assert_eq!(
-            parse("fancy:"),
-            Err(ParseError::UnexpectedTokenType(
-                "fancy:﹏﹏".to_string(),
-                "only token or ( allowed after attribute ('attr:') ".to_string()
-            )) // Err(UnexpectedTokenType("Expecting a levenshtein number after a \'~\' at position 2, but got None".to_string()))
+        assert_parse_error(
+            "fancy:",
+            ParseErrorKind::UnexpectedTokenType,
+            "fancy:﹏﹏",
+            "only token or ( allowed after attribute ('attr:') ",
         );
     }
 
@@ -427,11 +700,332 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_not() {
+        assert_eq!(parse("NOT fancy").unwrap(), UserAST::Not("fancy".into()));
+        assert_eq!(parse("-fancy").unwrap(), UserAST::Not("fancy".into()));
+        assert_eq!(parse("-fancy~1").unwrap(), UserAST::Not("fancy~1".into()));
+        assert_eq!(parse("cool NOT fancy").unwrap(), ("cool".into(), Or, UserAST::Not("fancy".into())).into());
+        assert_eq!(parse("cool -fancy").unwrap(), parse("cool NOT fancy").unwrap());
+        assert_eq!(parse("cool AND NOT fancy").unwrap(), ("cool".into(), And, UserAST::Not("fancy".into())).into());
+        assert_eq!(parse("-fancy cool").unwrap(), (UserAST::Not("fancy".into()), Or, "cool".into()).into());
+        assert_eq!(parse("-\"fancy cool\"").unwrap(), UserAST::Not("\"fancy cool\"".into()));
+        assert_eq!(parse("foo-bar").unwrap(), "foo-bar".into());
+    }
+
+    #[test]
+    fn test_not_attributes_and_parentheses() {
+        assert_eq!(
+            parse("field:-fancy").unwrap(),
+            UserAST::Attributed("field".to_string(), Box::new(UserAST::Not("fancy".into())))
+        );
+        assert_eq!(parse("-field:fancy").unwrap(), UserAST::Not("field:fancy".into()));
+        assert_eq!(parse("NOT field:fancy").unwrap(), UserAST::Not("field:fancy".into()));
+        assert_eq!(
+            parse("cool -(fancy nice)").unwrap(),
+            ("cool".into(), Or, UserAST::Not(Box::new(("fancy".into(), Or, "nice".into()).into()))).into()
+        );
+        assert_eq!(
+            parse("-field:(fancy nice) cool").unwrap(),
+            (
+                UserAST::Not(Box::new(UserAST::Attributed("field".to_string(), Box::new(("fancy".into(), Or, "nice".into()).into())))),
+                Or,
+                "cool".into()
+            )
+                .into()
+        );
+        test_parse_query_to_ast_helper("a -b:c", "(\"a\" OR NOT b:\"c\")");
+    }
+
+    #[test]
+    fn test_not_errors() {
+        assert_parse_error(
+            "cool NOT ",
+            ParseErrorKind::UnexpectedTokenType,
+            "cool NOT ﹏﹏",
+            "only token, attribute or ( allowed after negation ",
+        );
+        assert!(parse("--cool").is_err());
+    }
+
+    #[test]
+    fn test_not_disabled() {
+        let opt = Options {
+            no_negation: true,
+            ..Default::default()
+        };
+        assert_eq!(parse_with_opt("NOT -fancy", opt).unwrap(), ("NOT".into(), Or, "-fancy".into()).into());
+    }
+
+    #[test]
+    fn test_near() {
+        test_parse_query_to_ast_helper("apple NEAR/5 pie", "(\"apple\" NEAR/5 \"pie\")");
+        test_parse_query_to_ast_helper("apple NEAR/5 pie NEAR/5 tart cake", "((\"apple\" NEAR/5 \"pie\" NEAR/5 \"tart\") OR \"cake\")");
+        test_parse_query_to_ast_helper("title:apple NEAR/2 pie", "title:(\"apple\" NEAR/2 \"pie\")");
+        test_parse_query_to_ast_helper("cake -(apple NEAR/2 pie)", "(\"cake\" OR NOT (\"apple\" NEAR/2 \"pie\"))");
+        test_parse_query_to_ast_helper("\"search engine\"~3", "\"search engine\"~3");
+        assert_eq!(
+            parse("\"search engine\"~3").unwrap(),
+            UserAST::Near(
+                vec![UserFilter {
+                    phrase: "search engine".to_string(),
+                    levenshtein: None,
+                    quoted: true,
+                    wildcard: false,
+                }],
+                3
+            )
+        );
+        // levenshtein without quotes
+        assert_eq!(parse("engine~1").unwrap(), "engine~1".into());
+    }
+
+    #[test]
+    fn test_near_errors() {
+        assert!(parse("apple NEAR/5 pie NEAR/4 tart").is_err());
+        assert!(parse("apple~1 NEAR/5 pie").is_err());
+        assert!(parse("apple NEAR/5 pie~1").is_err());
+        assert!(parse("apple NEAR/5 (pie)").is_err());
+        assert!(parse("apple NEAR/5 ").is_err());
+    }
+
+    #[test]
+    fn test_near_disabled() {
+        let opt = Options {
+            no_proximity: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_with_opt("apple NEAR/5 pie", opt).unwrap(),
+            ("apple".into(), Or, ("NEAR/5".into(), Or, "pie".into()).into()).into()
+        );
+        let quoted_with_levenshtein = UserFilter {
+            phrase: "search engine".to_string(),
+            levenshtein: Some(3),
+            quoted: true,
+            wildcard: false,
+        };
+        assert_eq!(parse_with_opt("\"search engine\"~3", opt).unwrap(), UserAST::Leaf(Box::new(quoted_with_levenshtein)));
+    }
+
+    #[test]
+    fn test_wildcard() {
+        let wildcard = |phrase: &str| {
+            UserAST::Leaf(Box::new(UserFilter {
+                phrase: phrase.to_string(),
+                levenshtein: None,
+                quoted: false,
+                wildcard: true,
+            }))
+        };
+        assert_eq!(parse("haus*").unwrap(), wildcard("haus*"));
+        assert_eq!(parse("te?m").unwrap(), wildcard("te?m"));
+        assert_eq!(parse("*term").unwrap(), wildcard("*term"));
+        assert_eq!(parse("title:haus*").unwrap(), UserAST::Attributed("title".to_string(), Box::new(wildcard("haus*"))));
+        assert_eq!(parse("haus* boot").unwrap(), (wildcard("haus*"), Or, "boot".into()).into());
+        assert_eq!(parse("haus*").unwrap(), "haus*".into());
+
+        // quoted or only wildcards is no wildcard
+        assert_eq!(parse("\"haus*\"").unwrap(), "\"haus*\"".into());
+        assert_eq!(parse("???").unwrap(), "???".into());
+
+        let opt = Options {
+            no_wildcard: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_with_opt("haus*", opt).unwrap(),
+            UserAST::Leaf(Box::new(UserFilter {
+                phrase: "haus*".to_string(),
+                levenshtein: None,
+                quoted: false,
+                wildcard: false,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_boost() {
+        assert_eq!(parse("fancy^2.5").unwrap(), UserAST::Boosted("fancy".into(), 2.5));
+        assert_eq!(parse("fancy~1^2").unwrap(), UserAST::Boosted("fancy~1".into(), 2.0));
+        assert_eq!(
+            parse("field:fancy^3").unwrap(),
+            UserAST::Attributed("field".to_string(), Box::new(UserAST::Boosted("fancy".into(), 3.0)))
+        );
+        assert_eq!(
+            parse("(cool nice)^0.5 fancy").unwrap(),
+            (UserAST::Boosted(Box::new(("cool".into(), Or, "nice".into()).into()), 0.5), Or, "fancy".into()).into()
+        );
+        test_parse_query_to_ast_helper("field:(cool nice)^2", "field:(\"cool\" OR \"nice\")^2");
+        test_parse_query_to_ast_helper("-fancy^2 cool", "(NOT \"fancy\"^2 OR \"cool\")");
+        test_parse_query_to_ast_helper("\"red apple\"^2", "\"red apple\"^2");
+    }
+
+    #[test]
+    fn test_range() {
+        let range = |lower: Option<&str>, lower_inclusive: bool, upper: Option<&str>, upper_inclusive: bool| {
+            UserAST::Range(Box::new(UserRange {
+                lower: lower.map(|lower| lower.to_string()),
+                lower_inclusive,
+                upper: upper.map(|upper| upper.to_string()),
+                upper_inclusive,
+            }))
+        };
+        assert_eq!(
+            parse("year:[2000 TO 2010]").unwrap(),
+            UserAST::Attributed("year".to_string(), Box::new(range(Some("2000"), true, Some("2010"), true)))
+        );
+        assert_eq!(
+            parse("year:{2000 TO *]").unwrap(),
+            UserAST::Attributed("year".to_string(), Box::new(range(Some("2000"), false, None, true)))
+        );
+        assert_eq!(
+            parse("date:>=2023-01-01").unwrap(),
+            UserAST::Attributed("date".to_string(), Box::new(range(Some("2023-01-01"), true, None, false)))
+        );
+        test_parse_query_to_ast_helper("cool price:<20", "(\"cool\" OR price:<20)");
+        test_parse_query_to_ast_helper("cool AND price:[10 TO 20}^2", "(\"cool\" AND price:[10 TO 20}^2)");
+        test_parse_query_to_ast_helper("cool -price:<=20", "(\"cool\" OR NOT price:<=20)");
+        test_parse_query_to_ast_helper("(price:>20 OR year:<2000) cool", "((price:>20 OR year:<2000) OR \"cool\")");
+
+        // without valid range it's a token
+        test_parse_query_to_ast_helper("price:<", "price:\"<\"");
+        let opt = Options {
+            no_range: true,
+            ..Default::default()
+        };
+        assert_eq!(parse_with_opt("price:<20", opt).unwrap(), "price:<20".into());
+    }
+
+    #[test]
+    fn test_required() {
+        test_parse_query_to_ast_helper("+fred wilma -barney", "(+\"fred\" OR (\"wilma\" OR NOT \"barney\"))");
+        test_parse_query_to_ast_helper("+name:fred +(wilma betty)", "(+name:\"fred\" OR +(\"wilma\" OR \"betty\"))");
+        test_parse_query_to_ast_helper("name:+fred^2 c++", "(name:+\"fred\"^2 OR \"c++\")");
+        assert_eq!(parse("+fred").unwrap(), UserAST::Required("fred".into()));
+        assert!(parse("+").is_ok());
+        assert!(parse("fred +AND").is_ok());
+
+        let opt = Options {
+            no_required: true,
+            ..Default::default()
+        };
+        assert_eq!(parse_with_opt("+fred", opt).unwrap(), "+fred".into());
+    }
+
+    #[test]
+    fn test_boost_errors() {
+        assert!(parse("fancy^").is_err());
+        assert_eq!(parse("fancy^").unwrap_err().expected, "a positive number");
+        assert!(parse("fancy^cool").is_err());
+        assert!(parse("fancy^-1").is_err());
+        assert!(parse("fancy^NaN").is_err());
+        assert!(parse("^2").is_err());
+    }
+
+    #[test]
+    fn test_boost_disabled() {
+        let opt = Options {
+            no_boost: true,
+            ..Default::default()
+        };
+        assert_eq!(parse_with_opt("fancy^2", opt).unwrap(), "fancy^2".into());
+    }
+
     fn test_parse_query_to_ast_helper(query: &str, expected: &str) {
         let query_str = parse(query).unwrap();
         assert_eq!(format!("{:?}", query_str), expected);
     }
 
+    fn assert_parse_error(query: &str, kind: ParseErrorKind, marked_in_orig: &str, message: &str) {
+        let err = parse(query).unwrap_err();
+        assert_eq!((err.kind, err.marked_in_orig.as_str(), err.message.as_str()), (kind, marked_in_orig, message));
+    }
+
+    fn test_lenient_helper(query: &str, expected: &str) {
+        let opt = Options {
+            lenient: true,
+            ..Default::default()
+        };
+        assert_eq!(format!("{:?}", parse_with_opt(query, opt).unwrap()), expected);
+    }
+
+    #[test]
+    fn test_error_positions() {
+        let err = parse("fancy AND ").unwrap_err();
+        assert_eq!((err.start, err.stop), (10, 10));
+        assert_eq!(err.expected, "a term, an attribute, (, NOT or +");
+        assert_eq!(
+            err.to_string(),
+            "Unexpected end of query at 10..10, expected a term, an attribute, (, NOT or +: fancy AND ﹏﹏"
+        );
+
+        // offsets are in chars
+        let err = parse("größe:").unwrap_err();
+        assert_eq!((err.start, err.stop), (6, 6));
+        assert_eq!(err.expected, "a term, (, a range, NOT or +");
+
+        let err = parse("fancy^x").unwrap_err();
+        assert_eq!((err.kind, err.start, err.stop), (ParseErrorKind::ExpectedNumber, 6, 7));
+        assert_eq!(err.expected, "a positive number");
+
+        assert_parse_error("fancy AND OR nice", ParseErrorKind::UnexpectedTokenType, "fancy AND ﹏OR﹏ nice", "Unexpected OR ");
+        assert_eq!(parse("fancy AND OR nice").unwrap_err().expected, "a term, an attribute, (, NOT or +");
+    }
+
+    #[test]
+    fn test_unbalanced() {
+        assert_parse_error(
+            "(fancy nice",
+            ParseErrorKind::UnbalancedParentheses,
+            "﹏(﹏fancy nice",
+            "opening parenthesis is not closed ",
+        );
+        assert_parse_error(
+            "fancy) nice",
+            ParseErrorKind::UnbalancedParentheses,
+            "fancy﹏)﹏ nice",
+            "closing parenthesis without opening parenthesis ",
+        );
+        assert_parse_error(")", ParseErrorKind::UnbalancedParentheses, "﹏)﹏", "closing parenthesis without opening parenthesis ");
+        assert_parse_error("fancy ()", ParseErrorKind::EmptyParentheses, "fancy (﹏)﹏", "empty parentheses ");
+        assert_parse_error(
+            "(fancy OR )",
+            ParseErrorKind::UnexpectedTokenType,
+            "(fancy OR ﹏)﹏",
+            "expected a term before closing parenthesis ",
+        );
+        assert_parse_error("fancy \"nice", ParseErrorKind::UnclosedQuote, "fancy ﹏\"﹏nice", "quote is not closed ");
+        assert_eq!(parse("fancy \"nice").unwrap_err().start, 6);
+    }
+
+    #[test]
+    fn test_literal_before_parentheses() {
+        test_parse_query_to_ast_helper("a (b c)", "(\"a\" OR (\"b\" OR \"c\"))");
+        test_parse_query_to_ast_helper("a AND (b c)", "(\"a\" AND (\"b\" OR \"c\"))");
+    }
+
+    #[test]
+    fn test_lenient() {
+        test_lenient_helper("(fancy nice", "(\"(fancy\" OR \"nice\")");
+        test_lenient_helper("fancy) nice", "(\"fancy)\" OR \"nice\")");
+        test_lenient_helper("(fancy) nice)", "(\"fancy\" OR \"nice)\")");
+        test_lenient_helper("((fancy) nice", "(\"(\" OR (\"fancy\" OR \"nice\"))");
+        test_lenient_helper("fancy \"nice", "(\"fancy\" OR \"\"nice\")");
+        test_lenient_helper("\"fancy\" \"nice", "(\"fancy\" OR \"\"nice\")");
+        // still invalid queries are searched as words
+        test_lenient_helper("fancy AND ", "(\"fancy\" OR \"AND\")");
+        test_lenient_helper("fancy~x nice", "(\"fancy~x\" OR \"nice\")");
+        // valid queries are unchanged
+        test_lenient_helper("-cool title:(fancy nice)", "(NOT \"cool\" OR title:(\"fancy\" OR \"nice\"))");
+
+        let opt = Options {
+            lenient: true,
+            ..Default::default()
+        };
+        assert!(parse_with_opt("  ", opt).is_err());
+    }
+
     #[test]
     fn test_attributed_block() {
         test_parse_query_to_ast_helper("field:(fancy unlimited)", "field:(\"fancy\" OR \"unlimited\")");
@@ -0,0 +1,107 @@
+use crate::{
+    error::VelociError,
+    facet::{get_facet, get_stats, get_text_ids_for_anchor},
+    persistence::Persistence,
+    search::{apply_top_skip, get_text_for_id, AggregationBucket, AggregationRequest, FacetRequest, Stats},
+    util,
+};
+use fnv::FnvHashMap;
+
+/// Computes the buckets of a term aggregation and their nested aggregations over the ids.
+///
+/// Without nested aggregations this is a plain facet. Otherwise the ids are grouped per term and the nested aggregations are computed on the ids of each bucket.
+pub fn get_aggregation(persistence: &Persistence, req: &AggregationRequest, ids: &[u32]) -> Result<Vec<AggregationBucket>, VelociError> {
+    info_time!("aggregation in field {:?}", req.field);
+    if req.aggregations.is_empty() && req.stats.is_empty() {
+        let facet_req = FacetRequest {
+            field: req.field.to_string(),
+            top: req.top,
+            ..Default::default()
+        };
+        let terms = get_facet(persistence, &facet_req, ids)?.as_terms().cloned().unwrap_or_default();
+        return Ok(terms.into_iter().map(|(key, count)| AggregationBucket { key, count, ..Default::default() }).collect());
+    }
+
+    let steps = util::get_steps_to_anchor(&req.field);
+    let mut ids_per_text_id: FnvHashMap<u32, Vec<u32>> = FnvHashMap::default();
+    for id in ids {
+        let mut text_ids = get_text_ids_for_anchor(persistence, &steps, *id)?;
+        text_ids.sort_unstable();
+        text_ids.dedup();
+        for text_id in text_ids {
+            ids_per_text_id.entry(text_id).or_default().push(*id);
+        }
+    }
+
+    let mut groups: Vec<(String, Vec<u32>)> = ids_per_text_id
+        .into_iter()
+        .map(|(text_id, ids)| (get_text_for_id(persistence, steps.last().unwrap(), text_id), ids))
+        .collect();
+    groups.sort_unstable_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+    apply_top_skip(&mut groups, None, req.top);
+
+    groups
+        .into_iter()
+        .map(|(key, ids)| {
+            let aggregations = req
+                .aggregations
+                .iter()
+                .map(|sub_req| Ok((sub_req.field.to_string(), get_aggregation(persistence, sub_req, &ids)?)))
+                .collect::<Result<_, VelociError>>()?;
+            let stats = req
+                .stats
+                .iter()
+                .map(|stats_req| Ok((stats_req.field.to_string(), get_stats(persistence, stats_req, &ids)?)))
+                .collect::<Result<_, VelociError>>()?;
+            Ok(AggregationBucket {
+                key,
+                count: ids.len(),
+                aggregations,
+                stats,
+            })
+        })
+        .collect()
+}
+
+/// The buckets of all segments with the same key
+#[derive(Default)]
+struct MergedBucket {
+    count: usize,
+    aggregations: FnvHashMap<String, Vec<Vec<AggregationBucket>>>,
+    stats: FnvHashMap<String, Stats>,
+}
+
+/// Merges the buckets of the segments by key, the top limit is applied on every level after merging
+pub(crate) fn merge_aggregation_results(req: &AggregationRequest, results: Vec<Vec<AggregationBucket>>) -> Vec<AggregationBucket> {
+    let mut merged: FnvHashMap<String, MergedBucket> = FnvHashMap::default();
+    for bucket in results.into_iter().flatten() {
+        let merged_bucket = merged.entry(bucket.key).or_default();
+        merged_bucket.count += bucket.count;
+        for (field, buckets) in bucket.aggregations {
+            merged_bucket.aggregations.entry(field).or_default().push(buckets);
+        }
+        for (field, stats) in bucket.stats {
+            merged_bucket.stats.entry(field).or_default().merge(&stats);
+        }
+    }
+
+    let mut buckets: Vec<AggregationBucket> = merged
+        .into_iter()
+        .map(|(key, mut merged_bucket)| AggregationBucket {
+            key,
+            count: merged_bucket.count,
+            aggregations: req
+                .aggregations
+                .iter()
+                .filter_map(|sub_req| {
+                    let results = merged_bucket.aggregations.remove(&sub_req.field)?;
+                    Some((sub_req.field.to_string(), merge_aggregation_results(sub_req, results)))
+                })
+                .collect(),
+            stats: merged_bucket.stats,
+        })
+        .collect();
+    buckets.sort_unstable_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+    apply_top_skip(&mut buckets, None, req.top);
+    buckets
+}
@@ -3,9 +3,17 @@ mod create_fulltext;
 mod fast_lines;
 mod features;
 mod fields_config;
+mod hierarchy;
+mod merge;
 mod path_data;
+mod primary_key;
+mod segment;
 mod token_values_to_tokens;
 mod write_docs;
+pub use fields_config::CreateIndexConfig;
+pub use merge::*;
+pub use primary_key::*;
+pub use segment::*;
 pub use token_values_to_tokens::*;
 
 use self::{fast_lines::FastLinesTrait, features::IndexCreationType, fields_config::FieldsConfig};
@@ -14,13 +22,15 @@ use crate::{
     create::{
         calculate_score::{calculate_and_add_token_score_in_doc, calculate_token_score_for_entry},
         create_fulltext::AllTermsAndDocumentBuilder,
-        fields_config::config_from_string,
+        fields_config::create_index_config_from_string,
+        hierarchy::add_path_prefixes,
         path_data::{prepare_path_data, PathData},
+        primary_key::store_primary_keys,
         write_docs::write_docs,
     },
     error::*,
     indices::{persistence_score::token_to_anchor_score_vint::*, *},
-    metadata::FulltextIndexOptions,
+    metadata::{FulltextIndexOptions, NumericType},
     persistence::{Persistence, *},
     util::{StringAdd, *},
 };
@@ -167,7 +177,7 @@ macro_rules! add {
     };
 }
 
-type DataAndIds = Result<(FnvHashMap<String, PathData>, FnvHashMap<String, PathDataIds>), io::Error>;
+type DataAndIds = Result<(FnvHashMap<String, PathData>, FnvHashMap<String, PathDataIds>, FnvHashMap<String, GeoColumnWriter>), io::Error>;
 
 fn parse_json_and_prepare_indices<I>(stream1: I, persistence: &Persistence, fields_config: &FieldsConfig, term_data: &mut AllTermsAndDocumentBuilder) -> DataAndIds
 where
@@ -177,11 +187,13 @@ where
 
     let mut id_holder = json_converter::IDHolder::new();
     let mut tuples_to_parent_in_path: FnvHashMap<String, PathDataIds> = FnvHashMap::default();
+    let mut geo_data: FnvHashMap<String, GeoColumnWriter> = FnvHashMap::default();
 
     {
         info_time!("build path data");
 
         let mut tokens_ids = Vec::with_capacity(5);
+        let mut position_token_ids = Vec::with_capacity(5);
         let mut tokens_to_anchor_id = Vec::with_capacity(10);
 
         let mut cb_text = |anchor_id: u32, value: &str, path: &str, parent_val_id: u32| -> Result<(), io::Error> {
@@ -212,6 +224,23 @@ where
                     }
                 }
             }
+            if let Some((numeric_type, column)) = data.numeric.as_mut() {
+                let bits = if let Some(format) = data.date_format.as_deref() {
+                    parse_date(value, Some(format)).map(|millis| millis as u64)
+                } else {
+                    numeric_type.parse_to_bits(value)
+                };
+                let bits = bits.ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Expected a {:?} value in numeric field {:?}, but got {:?}", numeric_type, path, value),
+                    )
+                })?;
+                column.add(anchor_id, bits);
+            }
+            if let Some(geo) = data.geo.as_ref() {
+                get_or_insert_prefer_get(&mut geo_data, geo.field(), GeoColumnWriter::default).add(anchor_id, parent_val_id, geo, value)?;
+            }
             add!(data.value_id_to_anchor, parent_val_id, anchor_id);
 
             add!(
@@ -226,6 +255,7 @@ where
                     let mut current_token_pos = 0;
 
                     let text_ids_to_token_ids_already_stored = data.text_id_to_token_ids.as_ref().map(|el| el.contains(text_info.id)).unwrap_or(false);
+                    let positions_already_stored = data.token_to_text_id_positions.as_ref().map(|el| el.contains(text_info.id)).unwrap_or(true);
 
                     let mut prev_token: Option<TokenId> = None;
 
@@ -254,6 +284,9 @@ where
                         // we still would want a phrase boost if we search for <<cool
                         // so we would need maybe two categories of seperators
                         if !is_seperator {
+                            if !positions_already_stored {
+                                position_token_ids.push(token_info.id);
+                            }
                             if let Some(el) = data.phrase_pair_to_anchor.as_mut() {
                                 if let Some(prev_token) = prev_token {
                                     el.add((prev_token, token_info.id), anchor_id)?;
@@ -270,6 +303,13 @@ where
                         }
                     }
 
+                    if !positions_already_stored {
+                        if let Some(el) = data.token_to_text_id_positions.as_mut() {
+                            el.add_positions(text_info.id, &position_token_ids)?;
+                        }
+                        position_token_ids.clear();
+                    }
+
                     if let Some(token_to_anchor_id_score) = data.token_to_anchor_id_score.as_mut() {
                         calculate_and_add_token_score_in_doc(&mut tokens_to_anchor_id, anchor_id, current_token_pos, token_to_anchor_id_score)?;
                     }
@@ -317,7 +357,7 @@ where
 
     // std::mem::swap(&mut create_cache.term_data.id_holder, &mut id_holder);
 
-    Ok((path_data, tuples_to_parent_in_path))
+    Ok((path_data, tuples_to_parent_in_path, geo_data))
 }
 
 /// Only trace im data
@@ -570,6 +610,8 @@ enum IndexVariants {
     MultiValue(IndirectFlushingInOrderVint),
     TokenToAnchorScoreU32(TokenToAnchorScoreVintFlushing<u32>),
     TokenToAnchorScoreU64(TokenToAnchorScoreVintFlushing<u64>),
+    /// Already written numeric column
+    Numeric(NumericType, IndexValuesMetadata),
 }
 
 fn convert_raw_path_data_to_indices(
@@ -630,6 +672,17 @@ fn convert_raw_path_data_to_indices(
             }
 
             let no_sort_and_dedup = false;
+            if let Some(token_to_text_id_positions) = data.token_to_text_id_positions {
+                add_index_flush(
+                    &path_col,
+                    path.add(TOKEN_TO_TEXT_ID_POSITIONS),
+                    token_to_text_id_positions.data,
+                    false,
+                    no_sort_and_dedup,
+                    &mut indices,
+                )?;
+            }
+
             if let Some(text_id_to_token_ids) = data.text_id_to_token_ids {
                 add_index_flush(&path_col, path.add(TEXT_ID_TO_TOKEN_IDS), text_id_to_token_ids.data, false, no_sort_and_dedup, &mut indices)?;
             }
@@ -668,6 +721,29 @@ fn convert_raw_path_data_to_indices(
                 add_index_flush(&path_col, path.add(ANCHOR_TO_TEXT_ID), *anchor_to_text_id, false, no_sort_and_dedup, &mut indices)?;
             }
 
+            if let Some(text_id_to_lower_case_text_id) = data.text_id_to_lower_case_text_id {
+                add_index_flush(
+                    &path_col,
+                    path.add(TEXT_ID_TO_LOWER_CASE_TEXT_ID),
+                    *text_id_to_lower_case_text_id,
+                    true, // every text id has exactly one lower case text id
+                    no_sort_and_dedup,
+                    &mut indices,
+                )?;
+            }
+
+            if let Some((numeric_type, column)) = data.numeric {
+                let numeric_path = path_col.add(NUMERIC_VALUES);
+                column.write_sorted_values(directory, &numeric_path, numeric_type)?;
+                let metadata = column.write(directory, &numeric_path)?;
+                indices.push(IndexData {
+                    path_col: path_col.to_string(),
+                    path: numeric_path,
+                    index: IndexVariants::Numeric(numeric_type, metadata),
+                    index_category: IndexCategory::Numeric,
+                });
+            }
+
             if let Some(buffered_index_data) = data.boost {
                 let boost_path = extract_field_name(path).add(BOOST_VALID_TO_VALUE);
 
@@ -759,7 +835,7 @@ pub fn create_fulltext_index<I, J, K, S: AsRef<str>>(
     stream2: J,
     stream3: K,
     persistence: &mut Persistence,
-    indices_json: &FieldsConfig,
+    config: &CreateIndexConfig,
     load_persistence: bool,
 ) -> Result<(), VelociError>
 where
@@ -767,10 +843,19 @@ where
     J: Iterator<Item = Result<serde_json::Value, serde_json::Error>>,
     K: Iterator<Item = S>,
 {
+    let indices_json = &config.fields_config;
     let mut term_data = AllTermsAndDocumentBuilder::default();
+    let hierarchy_fields = indices_json.get_hierarchy_fields();
+    let stream1 = stream1.map(|doc| doc.and_then(|doc| add_path_prefixes(&hierarchy_fields, doc)));
+    let stream2 = stream2.map(|doc| doc.and_then(|doc| add_path_prefixes(&hierarchy_fields, doc)));
 
+    persistence.metadata.index_config = Some(config.clone());
     let doc_write_res = write_docs(persistence, stream3)?;
-    get_allterms_per_path(stream1, indices_json, &mut term_data)?;
+    let primary_key_field = config.primary_key.as_ref().map(|primary_key| primary_key.field.as_str());
+    get_allterms_per_path(stream1, indices_json, primary_key_field, &mut term_data)?;
+    if let Some(primary_key) = config.primary_key.as_ref() {
+        store_primary_keys(persistence, primary_key, std::mem::take(&mut term_data.primary_keys))?;
+    }
 
     let default_fulltext_options = FulltextIndexOptions::new_with_tokenize();
     {
@@ -787,6 +872,8 @@ where
                     name: path.to_string(),
                     has_fst: true,
                     textindex_metadata,
+                    hierarchy_separator: indices_json.get(path).hierarchy.as_ref().map(|hierarchy| hierarchy.separator.clone()),
+                    is_path_prefixes: hierarchy_fields.iter().any(|(field, _)| get_path_prefixes_field(field) == *path),
                     ..Default::default()
                 };
 
@@ -815,7 +902,7 @@ where
     info_time!("create and (write) fulltext_index");
     //trace!("all_terms {:?}", term_data.terms_in_path);
 
-    let (mut path_data, tuples_to_parent_in_path) = parse_json_and_prepare_indices(stream2, persistence, indices_json, &mut term_data)?;
+    let (mut path_data, tuples_to_parent_in_path, geo_data) = parse_json_and_prepare_indices(stream2, persistence, indices_json, &mut term_data)?;
 
     // std::mem::drop(create_cache);
 
@@ -824,6 +911,17 @@ where
     }
 
     let mut indices = convert_raw_path_data_to_indices(&persistence.directory, path_data, tuples_to_parent_in_path, indices_json)?;
+    for (field, column) in geo_data {
+        let geo_path = field.add(GEO_VALUES);
+        let metadata = column.write(&persistence.directory, &geo_path)?;
+        indices.push(IndexData {
+            path_col: field,
+            path: geo_path,
+            index: IndexVariants::Numeric(NumericType::F64, metadata),
+            index_category: IndexCategory::Geo,
+        });
+    }
+    let get_date_format = |path_col: &str| indices_json.get(path_col).date.as_ref().and_then(|date| date.format.clone());
     info_time!("write indices");
     for index_data in &mut indices {
         let mut index_metadata = IndexMetadata {
@@ -862,6 +960,19 @@ where
                 index_metadata.metadata = store.metadata;
                 index_metadata.data_type = DataType::U64;
             }
+            IndexVariants::Numeric(numeric_type, metadata) => {
+                index_metadata.is_empty = metadata.num_values == 0;
+                index_metadata.metadata = *metadata;
+                index_metadata.data_type = match numeric_type {
+                    NumericType::I64 => DataType::I64,
+                    NumericType::U64 => DataType::U64,
+                    NumericType::F64 => DataType::F64,
+                    NumericType::Date => DataType::Date,
+                };
+                if *numeric_type == NumericType::Date {
+                    index_metadata.date_format = get_date_format(&index_data.path_col);
+                }
+            }
         }
         let entry = persistence.metadata.columns.entry(index_data.path_col.to_string()).or_insert_with(|| FieldInfo {
             has_fst: false,
@@ -911,6 +1022,14 @@ where
                 IndexVariants::TokenToAnchorScoreU64(index) => {
                     persistence.indices.token_to_anchor_score.insert(path, index.into_store()?);
                 }
+                IndexVariants::Numeric(_, _) if index_data.index_category == IndexCategory::Geo => {
+                    let store = GeoColumn::open(&persistence.directory, &path)?;
+                    persistence.indices.geo_values.insert(path, store);
+                }
+                IndexVariants::Numeric(numeric_type, _) => {
+                    let store = NumericColumn::open(&persistence.directory, &path, numeric_type, get_date_format(&index_data.path_col))?;
+                    persistence.indices.numeric_values.insert(path, store);
+                }
             }
         }
     }
@@ -955,9 +1074,9 @@ where
 {
     info_time!("total time create_indices for");
 
-    let mut indices_json: FieldsConfig = config_from_string(indices)?;
-    indices_json.features_to_indices()?;
-    create_fulltext_index(stream1, stream2, stream3, persistence, &indices_json, load_persistence)?;
+    let mut config = create_index_config_from_string(indices)?;
+    config.fields_config.features_to_indices()?;
+    create_fulltext_index(stream1, stream2, stream3, persistence, &config, load_persistence)?;
 
     info_time!("write json and metadata");
 
@@ -23,6 +23,8 @@ pub struct AllTermsAndDocumentBuilder {
     current_offset: u64,
     pub(crate) id_holder: json_converter::IDHolder,
     pub(crate) terms_in_path: FnvHashMap<String, TermDataInPath>,
+    /// (anchor_id, key) of the primary key field, if configured
+    pub(crate) primary_keys: Vec<(u32, String)>,
 }
 
 pub(crate) fn store_full_text_info_and_set_ids(
@@ -117,6 +119,7 @@ pub(crate) fn get_allterms_per_path<I: Iterator<Item = Result<serde_json::Value,
     stream: I,
     // persistence: &mut Persistence,
     fulltext_info_for_path: &FieldsConfig,
+    primary_key_field: Option<&str>,
     data: &mut AllTermsAndDocumentBuilder,
 ) -> Result<(), io::Error> {
     info_time!("get_allterms_per_path");
@@ -126,7 +129,10 @@ pub(crate) fn get_allterms_per_path<I: Iterator<Item = Result<serde_json::Value,
 
     let mut id_holder = json_converter::IDHolder::new();
     {
-        let mut cb_text = |_anchor_id: u32, value: &str, path: &str, _parent_val_id: u32| -> Result<(), io::Error> {
+        let mut cb_text = |anchor_id: u32, value: &str, path: &str, _parent_val_id: u32| -> Result<(), io::Error> {
+            if primary_key_field == Some(path) {
+                data.primary_keys.push((anchor_id, value.to_string()));
+            }
             let options: &FulltextIndexOptions = fulltext_info_for_path.get(path).fulltext.as_ref().unwrap_or(&default_fulltext_options);
 
             let terms_data = get_or_insert_prefer_get(&mut data.terms_in_path, path, || TermDataInPath {
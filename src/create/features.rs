@@ -13,6 +13,7 @@ pub enum Features {
     WhyFound,
     Highlight,
     PhraseBoost,
+    PhraseQuery,
 }
 
 impl Features {
@@ -32,6 +33,7 @@ impl Features {
             Features::WhyFound,
             Features::Highlight,
             Features::PhraseBoost,
+            Features::PhraseQuery,
         ];
 
         all_features.iter().filter(|feature| features.contains(feature)).cloned().collect()
@@ -67,11 +69,12 @@ impl Features {
         add_if_features_not_used(&[Features::All, Features::BoostingFieldData], IndexCreationType::ValueIDToParent, &mut hashset);
 
         add_if_features_not_used(&[Features::All, Features::PhraseBoost], IndexCreationType::PhrasePairToAnchor, &mut hashset);
+        add_if_features_not_used(&[Features::PhraseQuery], IndexCreationType::TokenToTextIDPositions, &mut hashset);
         add_if_features_not_used(&[Features::All, Features::Select, Features::WhyFound], IndexCreationType::TextIDToTokenIds, &mut hashset);
         add_if_features_not_used(&[Features::All, Features::BoostingFieldData], IndexCreationType::TextIDToParent, &mut hashset);
         add_if_features_not_used(&[Features::All, Features::Facets, Features::Select], IndexCreationType::ParentToTextID, &mut hashset); //TODO can be diabled if facets is on non root element
         add_if_features_not_used(
-            &[Features::All, Features::BoostTextLocality, Features::Select, Features::Filters],
+            &[Features::All, Features::BoostTextLocality, Features::Select, Features::Filters, Features::PhraseQuery],
             IndexCreationType::TextIDToAnchor,
             &mut hashset,
         );
@@ -85,6 +88,7 @@ pub enum IndexCreationType {
     TokensToTextID, // Used by boost_text_locality, highlighting(why?), when resolving from a field to boost data (boost but indirectly) TODO: detect when boost is on same level and activate
     TokenToAnchorIDScore, //normal search
     PhrasePairToAnchor, //phrase boost
+    TokenToTextIDPositions, // exact phrase queries
     TextIDToTokenIds, // highlight document(why_found, when select), select
     TextIDToParent, // queries with boost indices on fields (slow search path)
     ParentToTextID, // facets on root, facets on sublevel with no special direct index, select
@@ -1,19 +1,47 @@
 use super::features::{Features, IndexCreationType};
-use crate::{error::VelociError, metadata::FulltextIndexOptions};
+use crate::{
+    error::VelociError,
+    metadata::{FulltextIndexOptions, NumericType, PrimaryKeyConfig},
+    util::get_path_prefixes_field,
+};
 
 use fnv::{FnvHashMap, FnvHashSet};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// `CreateIndexConfig` is the complete config for index creation.
+///
+/// The config can be provided in two formats, a plain `FieldsConfig` or a `CreateIndexConfig` with the `fields_config` property.
+/// See `create_index_config_from_string`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct CreateIndexConfig {
-    fields_config: FnvHashMap<String, FieldConfig>,
+    #[serde(default)]
+    pub fields_config: FieldsConfig,
     #[serde(default)]
     /// This can be used e.g. for documents, when only why found or snippets are used
     do_not_store_document: bool,
+    /// A field which uniquely identifies the document, e.g. `{"field": "ent_seq", "on_duplicate": "last_write_wins"}`
+    #[serde(default)]
+    pub primary_key: Option<PrimaryKeyConfig>,
+}
+
+impl From<FieldsConfig> for CreateIndexConfig {
+    fn from(fields_config: FieldsConfig) -> Self {
+        CreateIndexConfig {
+            fields_config,
+            ..Default::default()
+        }
+    }
+}
+
+impl CreateIndexConfig {
+    /// Creates the tokenizers, which are not serialized
+    pub(crate) fn create_tokenizers(&mut self) {
+        create_tokenizers(&mut self.fields_config);
+    }
 }
 
 const ALL_FIELD_CONFIG: &str = "*GLOBAL*";
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct FieldsConfig(FnvHashMap<String, FieldConfig>);
 impl FieldsConfig {
     pub fn get(&self, path: &str) -> &FieldConfig {
@@ -26,11 +54,45 @@ impl FieldsConfig {
         }
     }
 
+    /// Returns the fields with the `hierarchy` option
+    pub(crate) fn get_hierarchy_fields(&self) -> Vec<(String, HierarchyOptions)> {
+        self.0
+            .iter()
+            .filter_map(|(field, config)| config.hierarchy.as_ref().map(|hierarchy| (field.to_string(), hierarchy.clone())))
+            .collect()
+    }
+
+    /// Adds the config for the path prefixes of the fields with the `hierarchy` option. They are facets and can be used in filters.
+    fn add_path_prefixes_fields(&mut self) -> Result<(), VelociError> {
+        for (field, hierarchy) in self.get_hierarchy_fields() {
+            if field.contains('.') {
+                return Err(VelociError::InvalidConfig(format!(
+                    "hierarchy is only supported on the root level, but {:?} is nested",
+                    field
+                )));
+            }
+            if hierarchy.separator.is_empty() {
+                return Err(VelociError::InvalidConfig(format!("the hierarchy separator of {:?} is empty", field)));
+            }
+            self.0.entry(get_path_prefixes_field(&field)).or_insert_with(|| FieldConfig {
+                facet: true,
+                fulltext: Some(FulltextIndexOptions {
+                    tokenize: false,
+                    ..Default::default()
+                }),
+                features: Some([Features::Search, Features::Filters, Features::Facets].iter().cloned().collect()),
+                ..Default::default()
+            });
+        }
+        Ok(())
+    }
+
     pub fn features_to_indices(&mut self) -> Result<(), VelociError> {
         if !self.0.contains_key(ALL_FIELD_CONFIG) {
             let default_field_config = FieldConfig::default();
             self.0.insert(ALL_FIELD_CONFIG.to_string(), default_field_config);
         }
+        self.add_path_prefixes_fields()?;
         for (key, val) in self.0.iter_mut() {
             if val.features.is_some() && val.disabled_features.is_some() {
                 return Err(VelociError::InvalidConfig(format!(
@@ -60,6 +122,16 @@ pub struct FieldConfig {
     pub features: Option<FnvHashSet<Features>>,
     pub disabled_features: Option<FnvHashSet<Features>>,
     pub boost: Option<BoostIndexOptions>,
+    /// Stores the values of the field additionally as numbers in a columnar store, e.g. `numeric = "f64"`
+    pub numeric: Option<NumericType>,
+    /// Parses the values of the field as dates and stores them as timestamps in a columnar store, e.g. `date = { format = "%Y%m%d" }`
+    pub date: Option<DateOptions>,
+    /// Stores the values of the field as geo points in a columnar store. Accepts `"lat,lon"` strings or `{"lat": .., "lon": ..}` objects
+    #[serde(default)]
+    pub geo_point: bool,
+    /// Indexes additionally every path prefix of the values for hierarchical facets, e.g. `Books`, `Books/Fiction` and `Books/Fiction/Crime` for `Books/Fiction/Crime`.
+    /// Only for fields on the root level, e.g. `hierarchy = { separator = "/" }`
+    pub hierarchy: Option<HierarchyOptions>,
 }
 
 impl Default for FieldConfig {
@@ -71,6 +143,10 @@ impl Default for FieldConfig {
             fulltext: Some(FulltextIndexOptions::new_with_tokenize()),
             disabled_indices: None,
             boost: None,
+            numeric: None,
+            date: None,
+            geo_point: false,
+            hierarchy: None,
         }
     }
 }
@@ -86,6 +162,23 @@ pub struct BoostIndexOptions {
     boost_type: BoostType,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DateOptions {
+    /// The chrono format of the dates, e.g. `%Y%m%d`. Default is ISO-8601
+    pub format: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HierarchyOptions {
+    /// The separator between the levels of a path, default is `/`
+    #[serde(default = "default_hierarchy_separator")]
+    pub separator: String,
+}
+
+fn default_hierarchy_separator() -> String {
+    "/".to_string()
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 enum BoostType {
     #[serde(rename = "f32")]
@@ -99,6 +192,30 @@ pub fn config_from_string(indices: &str) -> Result<FieldsConfig, VelociError> {
         let map: FnvHashMap<String, FieldConfig> = toml::from_str(indices)?;
         FieldsConfig(map)
     };
+    create_tokenizers(&mut configs);
+    Ok(configs)
+}
+
+/// Parses a `CreateIndexConfig` in json or toml. If there is no `fields_config` property, the config is parsed as `FieldsConfig`.
+pub fn create_index_config_from_string(indices: &str) -> Result<CreateIndexConfig, VelociError> {
+    let mut config: CreateIndexConfig = if indices.trim().starts_with('{') {
+        let value: serde_json::Value = serde_json::from_str(indices)?;
+        if value.get("fields_config").is_none() {
+            return Ok(config_from_string(indices)?.into());
+        }
+        serde_json::from_value(value)?
+    } else {
+        let table: toml::Table = toml::from_str(indices)?;
+        if table.get("fields_config").is_none() {
+            return Ok(config_from_string(indices)?.into());
+        }
+        toml::Value::Table(table).try_into()?
+    };
+    create_tokenizers(&mut config.fields_config);
+    Ok(config)
+}
+
+fn create_tokenizers(configs: &mut FieldsConfig) {
     for value in &mut configs.0.values_mut() {
         if let Some(fulltext) = &mut value.fulltext {
             fulltext.create_tokenizer();
@@ -106,7 +223,6 @@ pub fn config_from_string(indices: &str) -> Result<FieldsConfig, VelociError> {
             // }
         }
     }
-    Ok(configs)
 }
 
 #[test]
@@ -172,3 +288,30 @@ fn test_field_config_from_toml() {
 
     config_from_string(indices).unwrap();
 }
+
+#[test]
+fn test_create_index_config_from_toml() {
+    let indices = r#"
+        [primary_key]
+            field = "ent_seq"
+            on_duplicate = "last_write_wins"
+        [fields_config."*GLOBAL*"]
+            features = ["All"]
+        [fields_config.tags]
+            facet = true
+    "#;
+
+    let config = create_index_config_from_string(indices).unwrap();
+    assert_eq!(
+        config.primary_key,
+        Some(PrimaryKeyConfig {
+            field: "ent_seq".to_string(),
+            on_duplicate: crate::metadata::OnDuplicateKey::LastWriteWins
+        })
+    );
+    assert!(config.fields_config.get("tags").facet);
+
+    let config = create_index_config_from_string(r#"{"tags": {"facet": true}}"#).unwrap();
+    assert_eq!(config.primary_key, None);
+    assert!(config.fields_config.get("tags").facet);
+}
@@ -0,0 +1,59 @@
+use super::fields_config::HierarchyOptions;
+use crate::util::get_path_prefixes_field;
+use serde_json::Value;
+
+/// Adds the path prefixes of the fields with the `hierarchy` option to the document, see `util::get_path_prefixes_field`.
+///
+/// The stored document is not changed, only the indexed one. Documents which already contain the path prefixes field are rejected.
+pub(crate) fn add_path_prefixes(hierarchy_fields: &[(String, HierarchyOptions)], mut doc: Value) -> Result<Value, serde_json::Error> {
+    for (field, hierarchy) in hierarchy_fields {
+        let prefixes_field = get_path_prefixes_field(field);
+        let prefixes_field = prefixes_field.trim_end_matches("[]");
+        if doc.get(prefixes_field).is_some() {
+            return Err(serde::de::Error::custom(format!(
+                "the field {:?} is reserved for the path prefixes of {:?}",
+                prefixes_field, field
+            )));
+        }
+        let prefixes = match doc.get(field.strip_suffix("[]").unwrap_or(field)) {
+            Some(Value::String(text)) => get_path_prefixes(std::iter::once(text.as_str()), &hierarchy.separator),
+            Some(Value::Array(values)) => get_path_prefixes(values.iter().filter_map(|value| value.as_str()), &hierarchy.separator),
+            _ => continue,
+        };
+        if let Some(doc) = doc.as_object_mut() {
+            doc.insert(prefixes_field.to_string(), Value::from(prefixes));
+        }
+    }
+    Ok(doc)
+}
+
+/// Returns every path prefix of the texts, e.g. `Books` and `Books/Fiction` for `Books/Fiction`. Empty levels are ignored.
+fn get_path_prefixes<'a>(texts: impl Iterator<Item = &'a str>, separator: &str) -> Vec<String> {
+    let mut prefixes = vec![];
+    for text in texts {
+        let mut prefix = String::new();
+        for level in text.split(separator).map(|level| level.trim()).filter(|level| !level.is_empty()) {
+            if !prefix.is_empty() {
+                prefix.push_str(separator);
+            }
+            prefix.push_str(level);
+            prefixes.push(prefix.clone());
+        }
+    }
+    prefixes.sort_unstable();
+    prefixes.dedup();
+    prefixes
+}
+
+#[test]
+fn test_get_path_prefixes() {
+    assert_eq!(
+        get_path_prefixes(["Books/Fiction/Crime"].into_iter(), "/"),
+        vec!["Books", "Books/Fiction", "Books/Fiction/Crime"]
+    );
+    assert_eq!(
+        get_path_prefixes(["Books/Fiction", "Books/ Poetry /", "Music"].into_iter(), "/"),
+        vec!["Books", "Books/Fiction", "Books/Poetry", "Music"]
+    );
+    assert_eq!(get_path_prefixes(["Books > Fiction"].into_iter(), " > "), vec!["Books", "Books > Fiction"]);
+}
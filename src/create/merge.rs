@@ -0,0 +1,320 @@
+use std::{ops::Range, path::Path};
+
+use crate::{
+    create::{create_fulltext_index, segment::get_free_segment_name},
+    directory::Directory,
+    error::VelociError,
+    indices::metadata::IndexCategory,
+    metadata::{FieldInfo, OnDuplicateKey, SegmentInfo},
+    persistence::{Persistence, DELETED_DOCS, PRIMARY_KEY, SORTED_VALUES, TEXTINDEX},
+    util::{Ext, SetExt, StringAdd},
+};
+use doc_store::DocLoader;
+use fixedbitset::FixedBitSet;
+use fnv::FnvHashMap;
+
+/// `MergePolicy` decides which segments should be merged. The initial segment is a merge candidate like the appended segments.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct MergePolicy {
+    /// When there are more segments (including the initial segment) than `max_segments`, the adjacent segments with the fewest documents are merged.
+    /// Only segments created with the same index config are merged
+    pub max_segments: usize,
+    /// A segment with a higher ratio of deleted documents is rewritten, to remove the deleted documents physically
+    pub max_deleted_ratio: f32,
+}
+
+impl Default for MergePolicy {
+    fn default() -> MergePolicy {
+        MergePolicy {
+            max_segments: 8,
+            max_deleted_ratio: 0.3,
+        }
+    }
+}
+
+impl MergePolicy {
+    /// Returns the range of segments (index in the order of `Persistence::get_segments`, 0 is the initial segment) which should be merged
+    pub fn find_merge(&self, persistence: &Persistence) -> Option<Range<usize>> {
+        let segments: Vec<&Persistence> = persistence.get_segments().map(|(_, segment)| segment).collect();
+        let max_segments = self.max_segments.max(1);
+        if segments.len() > max_segments {
+            let window_size = segments.len() - max_segments + 1;
+            let merge = (0..=segments.len() - window_size)
+                .filter(|start| has_same_index_config(&segments[*start..*start + window_size]))
+                .min_by_key(|start| segments[*start..*start + window_size].iter().map(|segment| segment.metadata.num_docs).sum::<u64>())
+                .and_then(|start| extend_to_not_deleted_doc(&segments, start..start + window_size))
+                .filter(|merge| has_same_index_config(&segments[merge.clone()]));
+            if merge.is_some() {
+                return merge;
+            }
+        }
+
+        (0..segments.len())
+            .filter(|pos| {
+                let segment = segments[*pos];
+                segment.metadata.num_docs > 0 && get_num_deleted_docs(segment) as f32 / segment.metadata.num_docs as f32 > self.max_deleted_ratio
+            })
+            .find_map(|pos| extend_to_not_deleted_doc(&segments, pos..pos + 1).filter(|merge| has_same_index_config(&segments[merge.clone()])))
+    }
+}
+
+/// The initial segment can't be empty, so a merge of the initial segment is extended until it contains a not deleted document.
+/// Returns None if all following documents are deleted.
+fn extend_to_not_deleted_doc(segments: &[&Persistence], mut merge: Range<usize>) -> Option<Range<usize>> {
+    if merge.start != 0 {
+        return Some(merge);
+    }
+    while segments[merge.clone()].iter().all(|segment| get_num_deleted_docs(segment) == segment.metadata.num_docs) {
+        if merge.end == segments.len() {
+            return None;
+        }
+        merge.end += 1;
+    }
+    Some(merge)
+}
+
+/// The indices of segments are only compatible, if they were created with the same index config
+fn has_same_index_config(segments: &[&Persistence]) -> bool {
+    let configs: Vec<_> = segments.iter().map(|segment| serde_json::to_value(&segment.metadata.index_config).ok()).collect();
+    configs.windows(2).all(|pair| pair[0] == pair[1])
+}
+
+fn get_num_deleted_docs(segment: &Persistence) -> u64 {
+    segment.deleted_docs.as_ref().map(|deleted_docs| deleted_docs.count_ones(..)).unwrap_or(0) as u64
+}
+
+/// Returns the names of all segments in the order of `Persistence::get_segments`. The initial segment is named by the prefix of its files, which is empty until it is merged.
+fn get_segment_names(persistence: &Persistence) -> Vec<String> {
+    std::iter::once(persistence.metadata.initial_segment.clone().unwrap_or_default())
+        .chain(persistence.metadata.segments.iter().map(|info| info.name.to_string()))
+        .collect()
+}
+
+/// A merged segment, which is not yet visible to searches. See `prepare_merge` and `commit_merge`.
+#[derive(Debug)]
+pub struct PreparedMerge {
+    /// The merged segment and its name, None if all documents of the merged segments are deleted
+    segment: Option<(String, Persistence)>,
+    merged_segments: Vec<String>,
+    /// The deleted documents of the merged segments, when the merge was prepared
+    deleted_docs: Vec<Option<FixedBitSet>>,
+    /// old anchor id in a merged segment -> new anchor id in the merged segment, per merged segment
+    id_mapping: Vec<Vec<Option<u32>>>,
+}
+
+/// Merges the segments in the range (index in the order of `Persistence::get_segments`) into one segment, deleted documents are removed.
+///
+/// The indices are rebuilt with the config stored in the metadata of the merged segments, which has to be the same for all of them.
+pub fn merge_segments(persistence: &mut Persistence, segments: Range<usize>) -> Result<(), VelociError> {
+    let merge = prepare_merge(persistence, segments)?;
+    commit_merge(persistence, merge)
+}
+
+/// Merges segments according to the merge policy, until no more merges are required. Returns true if segments were merged.
+pub fn merge_by_policy(persistence: &mut Persistence, policy: &MergePolicy) -> Result<bool, VelociError> {
+    let mut merged = false;
+    while let Some(segments) = policy.find_merge(persistence) {
+        merge_segments(persistence, segments)?;
+        merged = true;
+    }
+    Ok(merged)
+}
+
+/// Creates the merged segment, which contains all not deleted documents of the segments in the range.
+///
+/// This is the expensive part of the merge and only requires read access, so searches can continue meanwhile.
+/// The anchor ids are remapped, the fst and term ids per field are rebuilt and all indices are rewritten from the stored documents,
+/// with the index config of the merged segments. Segments with different index configs can't be merged.
+/// A merge including the initial segment fails, if all documents in the range are deleted, since the initial segment can't be empty.
+pub fn prepare_merge(persistence: &Persistence, segments: Range<usize>) -> Result<PreparedMerge, VelociError> {
+    info_time!("prepare merge of segments {:?}", segments);
+    let num_segments = persistence.segments.len() + 1;
+    if segments.is_empty() || segments.end > num_segments {
+        return Err(VelociError::InvalidRequest {
+            message: format!("invalid segment range {:?}, there are {} segments including the initial segment", segments, num_segments),
+        });
+    }
+    let merged: Vec<&Persistence> = persistence.get_segments().map(|(_, segment)| segment).skip(segments.start).take(segments.len()).collect();
+    if !has_same_index_config(&merged) {
+        return Err(VelociError::InvalidRequest {
+            message: format!("the segments {:?} were created with different index configs and can't be merged", segments),
+        });
+    }
+
+    let mut docs: Vec<String> = vec![];
+    let mut id_mapping = vec![];
+    for segment in &merged {
+        let doc_store_data = segment.get_file_bytes("data")?;
+        let doc_store = DocLoader::open(doc_store_data.as_ref());
+        let mut segment_id_mapping = Vec::with_capacity(segment.metadata.num_docs as usize);
+        for id in 0..segment.metadata.num_docs as u32 {
+            if segment.is_deleted(id) {
+                segment_id_mapping.push(None);
+            } else {
+                segment_id_mapping.push(Some(docs.len() as u32));
+                docs.push(doc_store.get_doc(id)?);
+            }
+        }
+        id_mapping.push(segment_id_mapping);
+    }
+
+    let merged_segments = get_segment_names(persistence)[segments.clone()].to_vec();
+    let deleted_docs = merged.iter().map(|segment| segment.deleted_docs.clone()).collect();
+    if docs.is_empty() {
+        if segments.start == 0 {
+            return Err(VelociError::InvalidRequest {
+                message: format!("all documents in the segments {:?} are deleted, but the initial segment can't be empty", segments),
+            });
+        }
+        return Ok(PreparedMerge {
+            segment: None,
+            merged_segments,
+            deleted_docs,
+            id_mapping,
+        });
+    }
+
+    let config = merged[0]
+        .metadata
+        .index_config
+        .clone()
+        .ok_or_else(|| VelociError::InvalidConfig(format!("no index config is stored in the metadata of the segments {:?}", segments)))?;
+    let mut merge_config = config.clone();
+    if merge_config.primary_key.is_none() {
+        merge_config.primary_key = persistence.metadata.primary_key.clone();
+    }
+    if let Some(primary_key) = merge_config.primary_key.as_mut() {
+        // documents with the same key in different segments are allowed, the newer one wins
+        primary_key.on_duplicate = OnDuplicateKey::LastWriteWins;
+    }
+
+    let name = get_free_segment_name(persistence)?;
+    let mut segment = persistence.create_segment(&name)?;
+    let stream1 = docs.iter().map(|doc| serde_json::from_str(doc));
+    let stream2 = docs.iter().map(|doc| serde_json::from_str(doc));
+    create_fulltext_index(stream1, stream2, docs.iter(), &mut segment, &merge_config, true)?;
+    segment.metadata.index_config = Some(config);
+    segment.write_metadata()?;
+
+    Ok(PreparedMerge {
+        segment: Some((name, segment)),
+        merged_segments,
+        deleted_docs,
+        id_mapping,
+    })
+}
+
+/// Replaces the merged segments with the merged segment and removes their files.
+///
+/// Documents deleted after `prepare_merge` are deleted in the merged segment too.
+/// If the initial segment is merged, the merged segment becomes the new initial segment.
+pub fn commit_merge(persistence: &mut Persistence, mut merge: PreparedMerge) -> Result<(), VelociError> {
+    info_time!("commit merge");
+    let names = get_segment_names(persistence);
+    let start = names
+        .iter()
+        .position(|name| Some(name) == merge.merged_segments.first())
+        .filter(|start| names.iter().skip(*start).take(merge.merged_segments.len()).eq(merge.merged_segments.iter()))
+        .ok_or_else(|| VelociError::InvalidRequest {
+            message: format!("segments {:?} changed since the merge was prepared", merge.merged_segments),
+        })?;
+    let segments = start..start + merge.merged_segments.len();
+
+    let mut deleted_since_prepare = vec![];
+    let merged = persistence.get_segments().map(|(_, segment)| segment).skip(start);
+    for ((segment, deleted_on_prepare), id_mapping) in merged.zip(&merge.deleted_docs).zip(&merge.id_mapping) {
+        if let Some(deleted_docs) = segment.deleted_docs.as_ref() {
+            for id in deleted_docs.ones() {
+                let was_deleted = deleted_on_prepare.as_ref().map(|deleted| deleted.contains(id)).unwrap_or(false);
+                if let (false, Some(new_id)) = (was_deleted, id_mapping[id]) {
+                    deleted_since_prepare.push(new_id);
+                }
+            }
+        }
+    }
+    if let Some((_, segment)) = merge.segment.as_mut().filter(|_| !deleted_since_prepare.is_empty()) {
+        segment.add_deleted_docs(&deleted_since_prepare)?;
+    }
+
+    if segments.start == 0 {
+        let (name, segment) = merge.segment.expect("a merge of the initial segment is never empty");
+        let old_directory = std::mem::replace(&mut persistence.directory, segment.directory);
+        let old_columns = std::mem::replace(&mut persistence.metadata.columns, segment.metadata.columns);
+        persistence.metadata.num_docs = segment.metadata.num_docs;
+        persistence.metadata.bytes_indexed = segment.metadata.bytes_indexed;
+        persistence.metadata.index_config = segment.metadata.index_config;
+        persistence.metadata.initial_segment = Some(name);
+        persistence.indices = segment.indices;
+        persistence.deleted_docs = segment.deleted_docs;
+        persistence.metadata.segments.drain(..segments.end - 1);
+        let old_segments: Vec<Persistence> = persistence.segments.drain(..segments.end - 1).collect();
+        persistence.write_metadata()?;
+        persistence.root_directory.sync_directory()?;
+
+        // the metadata of the initial segment is in the root metaData.json
+        if let Err(err) = persistence.directory.delete(Path::new("metaData.json")) {
+            warn!("could not delete metaData.json of merged segment {:?}", err);
+        }
+        delete_segment_files(old_directory.as_ref(), &old_columns, false);
+        for old_segment in old_segments {
+            delete_segment_files(old_segment.directory.as_ref(), &old_segment.metadata.columns, true);
+        }
+        return Ok(());
+    }
+
+    let (merged_info, merged_segment): (Option<SegmentInfo>, Option<Persistence>) = match merge.segment {
+        Some((name, segment)) => (
+            Some(SegmentInfo {
+                name,
+                num_docs: segment.metadata.num_docs,
+            }),
+            Some(segment),
+        ),
+        None => (None, None),
+    };
+    let appended = segments.start - 1..segments.end - 1;
+    persistence.metadata.segments.splice(appended.clone(), merged_info);
+    let old_segments: Vec<Persistence> = persistence.segments.splice(appended, merged_segment).collect();
+    persistence.write_metadata()?;
+    persistence.root_directory.sync_directory()?;
+
+    for old_segment in old_segments {
+        delete_segment_files(old_segment.directory.as_ref(), &old_segment.metadata.columns, true);
+    }
+    Ok(())
+}
+
+/// Removes all files of a segment, which is not referenced anymore. Errors are ignored, since the files are unused.
+///
+/// The metaData.json of a replaced initial segment is not deleted, it is the metadata of the whole persistence or already gone.
+fn delete_segment_files(directory: &dyn Directory, columns: &FnvHashMap<String, FieldInfo>, delete_metadata: bool) {
+    let mut files = vec!["data".to_string(), DELETED_DOCS.to_string(), PRIMARY_KEY.to_string() + ".fst"];
+    for (column_name, column) in columns {
+        if column.has_fst {
+            files.push(column_name.add(TEXTINDEX).add(".fst"));
+        }
+        for index in &column.indices {
+            let path = Path::new(&index.path);
+            files.push(index.path.to_string());
+            files.push(path.set_ext(Ext::Indirect).to_string_lossy().to_string());
+            files.push(path.set_ext(Ext::Data).to_string_lossy().to_string());
+            if index.index_category == IndexCategory::Numeric {
+                files.push(index.path.add(SORTED_VALUES));
+            }
+        }
+    }
+    if delete_metadata {
+        // metaData.json is deleted last, the segment name is only reused after it is gone
+        files.push("metaData.json".to_string());
+    }
+
+    for file in files {
+        let path = Path::new(&file);
+        if directory.exists(path).unwrap_or(false) {
+            if let Err(err) = directory.delete(path) {
+                warn!("could not delete {:?} of merged segment {:?}", file, err);
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     create::{fields_config::FieldsConfig, *},
-    metadata::FulltextIndexOptions,
+    indices::{GeoPart, NumericColumnWriter},
+    metadata::{FulltextIndexOptions, NumericType},
     persistence::Persistence,
 };
 use buffered_index_writer::BufferedIndexWriter;
@@ -11,6 +12,8 @@ pub(crate) struct PathData {
     pub(crate) token_to_anchor_id_score: Option<Box<BufferedIndexWriter<u32, (u32, u32)>>>,
     pub(crate) phrase_pair_to_anchor: Option<Box<BufferedIndexWriter<(TokenId, TokenId), ValueId>>>, // phrase_pair
     pub(crate) text_id_to_token_ids: Option<Box<BufferedTextIdToTokenIdsData>>,
+    /// Token positions per text id, for exact phrase queries
+    pub(crate) token_to_text_id_positions: Option<Box<BufferedTokenToTextIdPositionsData>>,
     pub(crate) text_id_to_parent: Option<Box<BufferedIndexWriter>>,
 
     /// Used to recreate objects, keep oder
@@ -19,7 +22,13 @@ pub(crate) struct PathData {
     pub(crate) value_id_to_anchor: Option<Box<BufferedIndexWriter>>,
     pub(crate) text_id_to_anchor: Option<Box<BufferedIndexWriter>>,
     pub(crate) anchor_to_text_id: Option<Box<BufferedIndexWriter>>,
+    /// Only created for facet fields with texts which differ only by case
+    pub(crate) text_id_to_lower_case_text_id: Option<Box<BufferedIndexWriter>>,
     pub(crate) boost: Option<Box<BufferedIndexWriter>>,
+    pub(crate) numeric: Option<(NumericType, NumericColumnWriter)>,
+    /// Custom format for a date field
+    pub(crate) date_format: Option<String>,
+    pub(crate) geo: Option<GeoPart>,
     pub(crate) fulltext_options: FulltextIndexOptions,
     pub(crate) is_anchor_identity_column: bool,
     #[allow(dead_code)]
@@ -54,6 +63,67 @@ impl BufferedTextIdToTokenIdsData {
     }
 }
 
+/// Stores for every token the text ids and the positions of the token in the text, as flattened pairs `[text_id, position, ..]`.
+///
+/// Every text is stored only once, positions count only tokens which are not seperators.
+#[derive(Debug)]
+pub(crate) struct BufferedTokenToTextIdPositionsData {
+    text_id_flag: FixedBitSet,
+    pub(crate) data: BufferedIndexWriter,
+}
+
+impl BufferedTokenToTextIdPositionsData {
+    #[inline]
+    pub(crate) fn contains(&self, text_id: u32) -> bool {
+        self.text_id_flag.contains(text_id as usize)
+    }
+
+    /// Adds the token ids of a text, in the order they appear in the text
+    pub(crate) fn add_positions(&mut self, text_id: u32, token_ids: &[u32]) -> Result<(), io::Error> {
+        if self.text_id_flag.len() <= text_id as usize {
+            self.text_id_flag.grow(text_id as usize + 1);
+        }
+        self.text_id_flag.insert(text_id as usize);
+        for (position, token_id) in token_ids.iter().enumerate() {
+            self.data.add_all(*token_id, &[text_id, position as u32])?;
+        }
+        Ok(())
+    }
+}
+
+fn get_geo_part(fields_config: &FieldsConfig, path: &str) -> Option<GeoPart> {
+    if fields_config.get(path).geo_point {
+        return Some(GeoPart::Point(path.to_string()));
+    }
+    if let Some(field) = path.strip_suffix(".lat").filter(|field| fields_config.get(field).geo_point) {
+        return Some(GeoPart::Lat(field.to_string()));
+    }
+    if let Some(field) = path.strip_suffix(".lon").filter(|field| fields_config.get(field).geo_point) {
+        return Some(GeoPart::Lon(field.to_string()));
+    }
+    None
+}
+
+/// Maps every text id to the smallest text id with the same lower case text. The text ids are already set, see `store_full_text_info_and_set_ids`.
+fn get_text_id_to_lower_case_text_id(persistence: &Persistence, term_data: &TermDataInPath) -> Option<Box<BufferedIndexWriter>> {
+    let mut lower_case_to_text_id: FnvHashMap<String, u32> = FnvHashMap::default();
+    for (term, info) in term_data.terms.iter() {
+        let text_id = lower_case_to_text_id.entry(term.to_lowercase()).or_insert(info.id);
+        *text_id = (*text_id).min(info.id);
+    }
+    if lower_case_to_text_id.len() == term_data.terms.len() {
+        return None;
+    }
+    let mut text_id_to_lower_case_text_id: Vec<(u32, u32)> = term_data.terms.iter().map(|(term, info)| (info.id, lower_case_to_text_id[&term.to_lowercase()])).collect();
+    text_id_to_lower_case_text_id.sort_unstable();
+
+    let mut store = Box::new(BufferedIndexWriter::new_for_sorted_id_insertion(persistence.directory.box_clone()));
+    for (text_id, lower_case_text_id) in text_id_to_lower_case_text_id {
+        store.add(text_id, lower_case_text_id).expect("could not add lower case text id");
+    }
+    Some(store)
+}
+
 pub(crate) fn prepare_path_data(persistence: &Persistence, fields_config: &FieldsConfig, path: &str, term_data: TermDataInPath) -> PathData {
     let field_config = fields_config.get(path);
     let boost_info_data = if field_config.boost.is_some() {
@@ -75,6 +145,12 @@ pub(crate) fn prepare_path_data(persistence: &Persistence, fields_config: &Field
         None
     };
 
+    let text_id_to_lower_case_text_id = if field_config.is_index_enabled(IndexCreationType::ParentToTextID) {
+        get_text_id_to_lower_case_text_id(persistence, &term_data)
+    } else {
+        None
+    };
+
     let get_buffered_if_enabled = |val: IndexCreationType| -> Option<Box<BufferedIndexWriter>> {
         if field_config.is_index_enabled(val) {
             Some(Box::new(BufferedIndexWriter::new_unstable_sorted(persistence.directory.box_clone())))
@@ -99,6 +175,14 @@ pub(crate) fn prepare_path_data(persistence: &Persistence, fields_config: &Field
     } else {
         None
     };
+    let token_to_text_id_positions = if field_config.is_index_enabled(IndexCreationType::TokenToTextIDPositions) {
+        Some(Box::new(BufferedTokenToTextIdPositionsData {
+            text_id_flag: FixedBitSet::default(),
+            data: BufferedIndexWriter::new_stable_sorted(persistence.directory.box_clone()), // Stable sort, text ids and positions are stored as pairs
+        }))
+    } else {
+        None
+    };
     let parent_to_text_id = if field_config.is_index_enabled(IndexCreationType::ParentToTextID) {
         Some(Box::new(BufferedIndexWriter::new_for_sorted_id_insertion(persistence.directory.box_clone())))
     } else {
@@ -116,12 +200,19 @@ pub(crate) fn prepare_path_data(persistence: &Persistence, fields_config: &Field
     let skip_tokenizing = if !fulltext_options.tokenize {
         fulltext_options.tokenize
     } else {
-        tokens_to_text_id.is_none() && token_to_anchor_id_score.is_none() && phrase_pair_to_anchor.is_none()
+        tokens_to_text_id.is_none() && token_to_anchor_id_score.is_none() && phrase_pair_to_anchor.is_none() && token_to_text_id_positions.is_none()
     };
 
     PathData {
         anchor_to_text_id,
+        text_id_to_lower_case_text_id,
         boost: boost_info_data,
+        numeric: field_config
+            .numeric
+            .or_else(|| field_config.date.as_ref().map(|_| NumericType::Date))
+            .map(|numeric_type| (numeric_type, NumericColumnWriter::default())),
+        date_format: field_config.date.as_ref().and_then(|date| date.format.clone()),
+        geo: get_geo_part(fields_config, path),
         value_id_to_anchor,
         // parent_id is monotonically increasing, hint buffered index writer, it's already sorted
         parent_to_text_id,
@@ -131,6 +222,7 @@ pub(crate) fn prepare_path_data(persistence: &Persistence, fields_config: &Field
         text_id_to_anchor,
         phrase_pair_to_anchor,
         text_id_to_token_ids,
+        token_to_text_id_positions,
         fulltext_options,
         skip_tokenizing,
         is_anchor_identity_column: persistence.metadata.columns.get(path).map(|el| el.is_anchor_identity_column).unwrap_or(false),
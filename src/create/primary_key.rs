@@ -0,0 +1,99 @@
+use crate::{
+    create::{add_segment_from_streams, fast_lines::FastLinesTrait},
+    error::VelociError,
+    metadata::{OnDuplicateKey, PrimaryKeyConfig},
+    persistence::{Persistence, PRIMARY_KEY},
+};
+use fst::{MapBuilder, Streamer};
+use std::{
+    fs::File,
+    io::{self, BufRead},
+};
+
+/// Stores the primary key to anchor id mapping as fst. Duplicate keys are handled according to `PrimaryKeyConfig::on_duplicate`.
+pub(crate) fn store_primary_keys(persistence: &mut Persistence, config: &PrimaryKeyConfig, mut keys: Vec<(u32, String)>) -> Result<(), VelociError> {
+    info_time!("store primary keys");
+    if config.field.contains("[]") {
+        return Err(VelociError::InvalidConfig(format!(
+            "primary key field {:?} needs to be a single value field, but is an array",
+            config.field
+        )));
+    }
+
+    // stable sort, so the last written document is the last in a group of equal keys
+    keys.sort_by(|a, b| a.1.cmp(&b.1));
+    let mut overwritten_anchors = vec![];
+    let mut unique_keys: Vec<(u32, String)> = Vec::with_capacity(keys.len());
+    for (anchor_id, key) in keys {
+        if let Some(last) = unique_keys.last_mut().filter(|last| last.1 == key) {
+            match config.on_duplicate {
+                OnDuplicateKey::Reject => return Err(VelociError::DuplicatePrimaryKey { key }),
+                OnDuplicateKey::LastWriteWins => {
+                    overwritten_anchors.push(last.0);
+                    last.0 = anchor_id;
+                    continue;
+                }
+            }
+        }
+        unique_keys.push((anchor_id, key));
+    }
+
+    let mut build = MapBuilder::memory();
+    for (anchor_id, key) in &unique_keys {
+        build.insert(key, u64::from(*anchor_id))?;
+    }
+    persistence.write_data(&(PRIMARY_KEY.to_string() + ".fst"), &build.into_inner()?)?;
+    persistence.metadata.primary_key = Some(config.clone());
+    persistence.indices.fst.insert(PRIMARY_KEY.to_string(), persistence.load_fst(PRIMARY_KEY)?);
+
+    if !overwritten_anchors.is_empty() {
+        persistence.add_deleted_docs(&overwritten_anchors)?;
+    }
+    Ok(())
+}
+
+/// Inserts or replaces the documents in `data_str`, identified by their primary key.
+///
+/// The documents are added as a new segment, existing documents with the same key are deleted.
+pub fn upsert_documents_from_str(persistence: &mut Persistence, data_str: &str, indices: &str) -> Result<(), VelociError> {
+    let stream1 = data_str.lines().map(serde_json::from_str);
+    let stream2 = data_str.lines().map(serde_json::from_str);
+
+    upsert_documents_from_streams(persistence, stream1, stream2, data_str.lines(), indices)
+}
+
+/// Inserts or replaces the documents in the line delimited json file, identified by their primary key.
+pub fn upsert_documents_from_file(persistence: &mut Persistence, data_path: &str, indices: &str) -> Result<(), VelociError> {
+    let stream1 = io::BufReader::new(File::open(data_path)?).fast_lines();
+    let stream2 = io::BufReader::new(File::open(data_path)?).fast_lines();
+    let stream3 = io::BufReader::new(File::open(data_path)?).lines().map(|line| line.unwrap());
+
+    upsert_documents_from_streams(persistence, stream1, stream2, stream3, indices)
+}
+
+pub fn upsert_documents_from_streams<I, J, K, S: AsRef<str>>(persistence: &mut Persistence, stream1: I, stream2: J, stream3: K, indices: &str) -> Result<(), VelociError>
+where
+    I: Iterator<Item = Result<serde_json::Value, serde_json::Error>>,
+    J: Iterator<Item = Result<serde_json::Value, serde_json::Error>>,
+    K: Iterator<Item = S>,
+{
+    if persistence.metadata.primary_key.is_none() {
+        return Err(VelociError::InvalidConfig("upsert requires a primary key in the index config".to_string()));
+    }
+    add_segment_from_streams(persistence, stream1, stream2, stream3, indices)?;
+
+    let new_segment = persistence.segments.last().expect("segment was just added");
+    let mut replaced_anchors = vec![];
+    if let Some(map) = new_segment.indices.fst.get(PRIMARY_KEY) {
+        let num_old_segments = persistence.segments.len();
+        let mut stream = map.stream();
+        while let Some((key, _)) = stream.next() {
+            let key = std::str::from_utf8(key)?;
+            if let Some(anchor_id) = persistence.get_anchor_by_key_in_segments(key, num_old_segments) {
+                replaced_anchors.push(anchor_id);
+            }
+        }
+    }
+    persistence.delete_documents(&replaced_anchors)?;
+    Ok(())
+}
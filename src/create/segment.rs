@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use crate::{
+    create::{create_fulltext_index, create_index_config_from_string, fast_lines::FastLinesTrait},
+    error::VelociError,
+    metadata::SegmentInfo,
+    persistence::Persistence,
+};
+use std::{
+    fs::File,
+    io::{self, BufRead},
+};
+
+/// Appends the documents in `data_str` as a new segment to an existing persistence.
+///
+/// The new documents get the anchor ids after all existing documents and are searchable after the call.
+pub fn add_segment_from_str(persistence: &mut Persistence, data_str: &str, indices: &str) -> Result<(), VelociError> {
+    let stream1 = data_str.lines().map(serde_json::from_str);
+    let stream2 = data_str.lines().map(serde_json::from_str);
+
+    add_segment_from_streams(persistence, stream1, stream2, data_str.lines(), indices)
+}
+
+/// Appends the documents in the line delimited json file as a new segment to an existing persistence.
+pub fn add_segment_from_file(persistence: &mut Persistence, data_path: &str, indices: &str) -> Result<(), VelociError> {
+    let stream1 = io::BufReader::new(File::open(data_path)?).fast_lines();
+    let stream2 = io::BufReader::new(File::open(data_path)?).fast_lines();
+    let stream3 = io::BufReader::new(File::open(data_path)?).lines().map(|line| line.unwrap());
+
+    add_segment_from_streams(persistence, stream1, stream2, stream3, indices)
+}
+
+pub fn add_segment_from_streams<I, J, K, S: AsRef<str>>(persistence: &mut Persistence, stream1: I, stream2: J, stream3: K, indices: &str) -> Result<(), VelociError>
+where
+    I: Iterator<Item = Result<serde_json::Value, serde_json::Error>>,
+    J: Iterator<Item = Result<serde_json::Value, serde_json::Error>>,
+    K: Iterator<Item = S>,
+{
+    info_time!("add segment");
+    let name = get_free_segment_name(persistence)?;
+    let mut segment = persistence.create_segment(&name)?;
+
+    let mut config = create_index_config_from_string(indices)?;
+    config.fields_config.features_to_indices()?;
+    if config.primary_key.is_none() {
+        config.primary_key = persistence.metadata.primary_key.clone();
+    }
+    create_fulltext_index(stream1, stream2, stream3, &mut segment, &config, true)?;
+
+    persistence.metadata.segments.push(SegmentInfo {
+        name,
+        num_docs: segment.metadata.num_docs,
+    });
+    persistence.segments.push(segment);
+    persistence.write_metadata()?;
+    Ok(())
+}
+
+pub(crate) fn get_free_segment_name(persistence: &Persistence) -> Result<String, VelociError> {
+    let mut segment_id = persistence.metadata.segments.len() + 1;
+    loop {
+        let name = format!("segment_{}", segment_id);
+        let segment = persistence.create_segment(&name)?;
+        let is_initial_segment = persistence.metadata.initial_segment.as_ref() == Some(&name);
+        if !is_initial_segment && !segment.directory.exists(Path::new("metaData.json"))? {
+            return Ok(name);
+        }
+        segment_id += 1;
+    }
+}
@@ -64,6 +64,7 @@ pub fn add_token_values_to_tokens(persistence: &mut Persistence, data_str: &str,
         metadata: store.metadata,
         index_cardinality: IndexCardinality::SingleValue,
         data_type: DataType::U32,
+        date_format: None,
     };
 
     let entry = persistence.metadata.columns.entry(config.path).or_insert_with(|| FieldInfo {
@@ -2,6 +2,7 @@ use std::{io, path::Path};
 
 pub use directory::Directory;
 pub use directory::MmapDirectory;
+pub use directory::PrefixDirectory;
 pub use directory::RamDirectory;
 use ownedbytes::OwnedBytes;
 
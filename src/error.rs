@@ -30,6 +30,8 @@ pub enum VelociError {
     Io(io::Error),
     #[error("Invalid Config: '{:?}'", _0)]
     InvalidConfig(String),
+    #[error("Duplicate primary key {key:?}")]
+    DuplicatePrimaryKey { key: String },
     #[error("Missing text_id {:?} in index {}, therefore could not load text", text_value_id, field_name)]
     MissingTextId { text_value_id: u32, field_name: String },
     #[error("field does not exist {} (fst not found)", _0)]
@@ -40,6 +42,8 @@ pub enum VelociError {
     PlanExecutionSendFailed,
     #[error("Plan Execution Failed, filter channel was closed or empty ")]
     PlanExecutionRecvFailedFilter,
+    #[error("QueryParseError: {0}")]
+    QueryParseError(query_parser::error::ParseError),
 }
 
 impl From<io::Error> for VelociError {
@@ -72,3 +76,8 @@ impl From<toml::de::Error> for VelociError {
         VelociError::TomlError(error)
     }
 }
+impl From<query_parser::error::ParseError> for VelociError {
+    fn from(error: query_parser::error::ParseError) -> VelociError {
+        VelociError::QueryParseError(error)
+    }
+}
@@ -2,6 +2,8 @@
 
 use crate::{
     error::VelociError,
+    indices::{DateInterval, NumericColumn, NumericValue},
+    metadata::NumericType,
     persistence::*,
     search::{sort::check_apply_top_n_sort, *},
     util::{self, StringAdd},
@@ -11,11 +13,6 @@ use itertools::Itertools;
 use num::{self, ToPrimitive};
 use std::{self, cmp::Ordering};
 
-fn get_top_facet_group<T: IndexIdToParentData>(hits: &FnvHashMap<T, usize>, top: Option<usize>) -> Vec<(T, u32)> {
-    let groups: Vec<(T, u32)> = hits.iter().map(|ref tupl| (*tupl.0, tupl.1.to_u32().unwrap())).collect();
-    sort_and_apply_top_skip_group(groups, top)
-}
-
 fn sort_and_apply_top_skip_group<T: IndexIdToParentData>(mut groups: Vec<(T, u32)>, top: Option<usize>) -> Vec<(T, u32)> {
     groups.sort_unstable_by(|a, b| b.1.cmp(&a.1));
     apply_top_skip(&mut groups, None, top);
@@ -26,16 +23,86 @@ fn get_groups_with_text(persistence: &Persistence, groups: &[(u32, u32)], field:
     groups.iter().map(|el| (get_text_for_id(persistence, field, el.0), el.1 as usize)).collect()
 }
 
-// TODO Check ignorecase, check duplicates in facet data
-// For ignorecase, we probably need a term_ids -> lower case term id mapping index - read all texts annd aggregate may be too slow
-pub fn get_facet(persistence: &Persistence, req: &FacetRequest, ids: &[u32]) -> Result<Vec<(String, usize)>, VelociError> {
+// TODO check duplicates in facet data
+pub fn get_facet(persistence: &Persistence, req: &FacetRequest, ids: &[u32]) -> Result<FacetResult, VelociError> {
     info_time!("facets in field {:?}", req.field);
     trace!("get_facet for ids {:?}", ids);
     let steps = util::get_steps_to_anchor(&req.field);
     info!("facet on {:?}", steps);
 
-    // one step facet special case
-    if steps.len() == 1 || persistence.has_index(&(steps.last().unwrap().add(ANCHOR_TO_TEXT_ID))) {
+    let live_ids: Vec<u32>;
+    let ids = if persistence.has_deleted_docs() {
+        live_ids = ids.iter().filter(|id| !persistence.is_deleted(**id)).cloned().collect();
+        &live_ids
+    } else {
+        ids
+    };
+
+    let num_bucket_options = [req.date_histogram.is_some(), req.ranges.is_some(), req.histogram.is_some(), req.hierarchy.is_some()]
+        .iter()
+        .filter(|is_set| **is_set)
+        .count();
+    if num_bucket_options > 1 {
+        return Err(VelociError::InvalidRequest {
+            message: format!("facet on {:?} can only have one of date_histogram, ranges, histogram or hierarchy", req.field),
+        });
+    }
+    if let Some(interval) = req.date_histogram {
+        return Ok(FacetResult::Terms(get_date_histogram(persistence, &req.field, interval, ids)?));
+    }
+    if let Some(ranges) = req.ranges.as_ref() {
+        return Ok(FacetResult::Buckets(get_range_facet(persistence, &req.field, ranges, ids)?));
+    }
+    if let Some(histogram) = req.histogram.as_ref() {
+        return Ok(FacetResult::Buckets(get_histogram_facet(persistence, &req.field, histogram.interval, ids)?));
+    }
+    if let Some(hierarchy) = req.hierarchy.as_ref() {
+        return Ok(FacetResult::Terms(get_hierarchy_facet(persistence, req, hierarchy, ids)?));
+    }
+    get_term_facet(persistence, req, ids, &steps).map(FacetResult::Terms)
+}
+
+/// Counts the hits per child of the parent path, on the path prefixes of the field (see `util::get_path_prefixes_field`)
+fn get_hierarchy_facet(persistence: &Persistence, req: &FacetRequest, hierarchy: &HierarchyFacet, ids: &[u32]) -> Result<Vec<(String, usize)>, VelociError> {
+    let separator = match persistence.metadata.columns.get(&req.field) {
+        Some(field_info) => field_info.hierarchy_separator.as_ref().ok_or_else(|| VelociError::InvalidRequest {
+            message: format!("hierarchy facet on {:?} requires a field with the hierarchy option", req.field),
+        })?,
+        None => return Ok(vec![]),
+    };
+    let mut parent_prefix = hierarchy.parent.as_ref().map(|parent| parent.to_string() + separator).unwrap_or_default();
+    if req.ignore_case {
+        parent_prefix = parent_prefix.to_lowercase();
+    }
+
+    let prefixes_req = FacetRequest {
+        field: util::get_path_prefixes_field(&req.field),
+        top: None,
+        order: FacetOrder::Alphabetical,
+        include: Some(FacetInclude::Prefix(parent_prefix.to_string())),
+        ignore_case: req.ignore_case,
+        ..Default::default()
+    };
+    let mut groups = get_term_facet(persistence, &prefixes_req, ids, &util::get_steps_to_anchor(&prefixes_req.field))?;
+    groups.retain(|(path, _)| path.strip_prefix(parent_prefix.as_str()).is_some_and(|child| !child.contains(separator.as_str())));
+    if let Some(include) = req.include.as_ref() {
+        let include = IncludeFilter::new(req, include)?;
+        groups.retain(|group| include.matches(&group.0));
+    }
+    sort_and_filter_term_groups(req, &mut groups);
+    Ok(groups)
+}
+
+/// Term facets sorted by count without a filter on the texts can select the top groups by their text id, before the texts are loaded
+fn is_top_by_count(req: &FacetRequest) -> bool {
+    req.order == FacetOrder::Count && req.include.is_none() && !req.ignore_case
+}
+
+fn get_term_facet(persistence: &Persistence, req: &FacetRequest, ids: &[u32], steps: &[String]) -> Result<Vec<(String, usize)>, VelociError> {
+    let groups = if req.ignore_case {
+        get_lower_case_groups(persistence, ids, steps)?
+    } else if steps.len() == 1 || persistence.has_index(&(steps.last().unwrap().add(ANCHOR_TO_TEXT_ID))) {
+        // one step facet special case
         let path = if steps.len() == 1 {
             steps.first().unwrap().add(PARENT_TO_VALUE_ID)
         } else {
@@ -44,34 +111,347 @@ pub fn get_facet(persistence: &Persistence, req: &FacetRequest, ids: &[u32]) ->
         let kv_store = persistence.get_valueid_to_parent(path)?;
         let hits = {
             debug_time!("facet count_values_for_ids {:?}", req.field);
-            kv_store.count_values_for_ids(ids, req.top.map(|el| el as u32))
+            let top = if is_top_by_count(req) { req.top.map(|el| el as u32) } else { None };
+            kv_store.count_values_for_ids(ids, top)
         };
+        hits.iter().map(|tupl| (*tupl.0, tupl.1.to_u32().unwrap())).collect()
+    } else {
+        let mut next_level_ids = join_anchor_to_leaf(persistence, ids, steps)?;
 
-        debug_time!("facet collect and get texts {:?}", req.field);
-
-        let groups = get_top_facet_group(&hits, req.top);
+        debug_time!("facet group by field {:?}", req.field);
+        next_level_ids.sort_unstable();
+        let mut groups = vec![];
+        for (key, group) in &next_level_ids.into_iter().group_by(|el| *el) {
+            groups.push((key, group.count() as u32));
+        }
+        groups
+    };
 
-        let groups_with_text = get_groups_with_text(persistence, &groups, steps.last().unwrap());
+    debug_time!("facet collect and get texts {:?}", req.field);
+    let field = steps.last().unwrap();
+    if is_top_by_count(req) {
+        let mut groups = sort_and_apply_top_skip_group(groups, req.top);
+        if let Some(min_count) = req.min_count {
+            groups.retain(|group| group.1 as usize >= min_count);
+        }
+        let groups_with_text = get_groups_with_text(persistence, &groups, field);
         debug!("{:?}", groups_with_text);
         return Ok(groups_with_text);
     }
 
-    let mut next_level_ids = join_anchor_to_leaf(persistence, ids, &steps)?;
-
-    let mut groups = vec![];
-    {
-        debug_time!("facet group by field {:?}", req.field);
-        next_level_ids.sort_unstable();
-        for (key, group) in &next_level_ids.into_iter().group_by(|el| *el) {
-            groups.push((key, group.count() as u32));
+    let mut groups_with_text = get_groups_with_text(persistence, &groups, field);
+    if req.ignore_case {
+        for group in groups_with_text.iter_mut() {
+            group.0 = group.0.to_lowercase();
         }
-        groups = sort_and_apply_top_skip_group(groups, req.top);
     }
-    let groups_with_text = get_groups_with_text(persistence, &groups, steps.last().unwrap());
+    if let Some(include) = req.include.as_ref() {
+        let include = IncludeFilter::new(req, include)?;
+        groups_with_text.retain(|group| include.matches(&group.0));
+    }
+    sort_and_filter_term_groups(req, &mut groups_with_text);
     debug!("{:?}", groups_with_text);
     Ok(groups_with_text)
 }
 
+/// Counts the hits per lower case text id, a hit is counted once per lower case text. Without a `TEXT_ID_TO_LOWER_CASE_TEXT_ID` index, the texts of the field differ not only by case.
+fn get_lower_case_groups(persistence: &Persistence, ids: &[u32], steps: &[String]) -> Result<Vec<(u32, u32)>, VelociError> {
+    let lower_case_path = steps.last().unwrap().add(TEXT_ID_TO_LOWER_CASE_TEXT_ID);
+    let lower_case_store = if persistence.has_index(&lower_case_path) {
+        Some(persistence.get_valueid_to_parent(&lower_case_path)?)
+    } else {
+        None
+    };
+    let mut counts: FnvHashMap<u32, u32> = FnvHashMap::default();
+    for id in ids {
+        let mut text_ids = get_text_ids_for_anchor(persistence, steps, *id)?;
+        if let Some(lower_case_store) = lower_case_store {
+            for text_id in text_ids.iter_mut() {
+                *text_id = lower_case_store.get_value(u64::from(*text_id)).unwrap_or(*text_id);
+            }
+        }
+        text_ids.sort_unstable();
+        text_ids.dedup();
+        for text_id in text_ids {
+            *counts.entry(text_id).or_insert(0) += 1;
+        }
+    }
+    Ok(counts.into_iter().collect())
+}
+
+/// The compiled `include` of a facet request
+enum IncludeFilter<'a> {
+    Prefix(&'a str),
+    Regex(regex::Regex),
+}
+
+impl<'a> IncludeFilter<'a> {
+    fn new(req: &FacetRequest, include: &'a FacetInclude) -> Result<IncludeFilter<'a>, VelociError> {
+        match include {
+            FacetInclude::Prefix(prefix) => Ok(IncludeFilter::Prefix(prefix)),
+            FacetInclude::Regex(regex) => regex::Regex::new(&format!("^(?:{})$", regex))
+                .map(IncludeFilter::Regex)
+                .map_err(|err| VelociError::InvalidRequest {
+                    message: format!("facet on {:?} has an invalid include regex: {}", req.field, err),
+                }),
+        }
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            IncludeFilter::Prefix(prefix) => text.starts_with(prefix),
+            IncludeFilter::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+/// Removes the groups with less than `min_count` hits, sorts them by the `order` of the request and applies `top`
+fn sort_and_filter_term_groups(req: &FacetRequest, groups: &mut Vec<(String, usize)>) {
+    if let Some(min_count) = req.min_count {
+        groups.retain(|group| group.1 >= min_count);
+    }
+    match req.order {
+        FacetOrder::Count => groups.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0))),
+        FacetOrder::Alphabetical => groups.sort_unstable(),
+        FacetOrder::Numeric => groups.sort_by(|a, b| {
+            let (a_number, b_number) = (a.0.parse::<f64>().ok(), b.0.parse::<f64>().ok());
+            match (a_number, b_number) {
+                (Some(a_number), Some(b_number)) => a_number.partial_cmp(&b_number).unwrap_or(Ordering::Equal),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => a.0.cmp(&b.0),
+            }
+        }),
+    }
+    apply_top_skip(groups, None, req.top);
+}
+
+/// Counts the hits per date bucket, a hit is counted once per bucket. The buckets are sorted chronologically.
+///
+/// A segment without values for the field has no column, then there are no buckets.
+fn get_date_histogram(persistence: &Persistence, field: &str, interval: DateInterval, ids: &[u32]) -> Result<Vec<(String, usize)>, VelociError> {
+    let column = match persistence.indices.numeric_values.get(&field.add(NUMERIC_VALUES)) {
+        Some(column) if column.numeric_type == NumericType::Date => column,
+        Some(_) => {
+            return Err(VelociError::InvalidRequest {
+                message: format!("date_histogram requires a date field, but {:?} is numeric", field),
+            })
+        }
+        None => return Ok(vec![]),
+    };
+
+    let mut counts: FnvHashMap<String, usize> = FnvHashMap::default();
+    let mut keys = vec![];
+    for id in ids {
+        keys.clear();
+        keys.extend(column.get_values(*id).filter_map(|value| match value {
+            NumericValue::I64(millis) => interval.bucket_key(millis),
+            _ => None,
+        }));
+        keys.sort_unstable();
+        keys.dedup();
+        for key in keys.drain(..) {
+            *counts.entry(key).or_insert(0) += 1;
+        }
+    }
+    let mut groups: Vec<(String, usize)> = counts.into_iter().collect();
+    groups.sort_unstable();
+    Ok(groups)
+}
+
+/// The numeric values of a field per anchor id, for range and histogram facets and stats
+pub(crate) enum NumericValuesPerAnchor<'a> {
+    Column(&'a NumericColumn),
+    /// Boost values are stored per value id of the parent, so for `[]` paths the anchor is joined to the parents first
+    Boost {
+        persistence: &'a Persistence,
+        store: &'a dyn IndexIdToParent<Output = u32>,
+        steps_to_parent: Vec<String>,
+    },
+}
+
+impl NumericValuesPerAnchor<'_> {
+    /// Returns `None` if the segment has no values for the field
+    pub(crate) fn open<'a>(persistence: &'a Persistence, field: &str) -> Result<Option<NumericValuesPerAnchor<'a>>, VelociError> {
+        if let Some(column) = persistence.indices.numeric_values.get(&field.add(NUMERIC_VALUES)) {
+            return Ok(Some(NumericValuesPerAnchor::Column(column)));
+        }
+        if let Some(store) = persistence.indices.boost_valueid_to_value.get(&field.add(BOOST_VALID_TO_VALUE)) {
+            let mut steps_to_parent = util::get_steps_to_anchor(field);
+            steps_to_parent.pop(); // the last step is the textindex of the field
+            return Ok(Some(NumericValuesPerAnchor::Boost {
+                persistence,
+                store: store.as_ref(),
+                steps_to_parent,
+            }));
+        }
+        if persistence.indices.fst.contains_key(&field.add(TEXTINDEX)) {
+            return Err(VelociError::InvalidRequest {
+                message: format!("{:?} is a text field, but a numeric or boost field is required", field),
+            });
+        }
+        Ok(None)
+    }
+
+    pub(crate) fn get_values(&self, id: u32, values: &mut Vec<f64>) -> Result<(), VelociError> {
+        values.clear();
+        match self {
+            NumericValuesPerAnchor::Column(column) => values.extend(column.get_values(id).map(|value| value.to_f64())),
+            NumericValuesPerAnchor::Boost {
+                persistence,
+                store,
+                steps_to_parent,
+            } => {
+                let to_value = |bits: u32| f64::from(f32::from_bits(bits));
+                if steps_to_parent.is_empty() {
+                    values.extend(store.get_value(u64::from(id)).map(to_value));
+                } else {
+                    let parent_ids = join_anchor_to_leaf(persistence, &[id], steps_to_parent)?;
+                    values.extend(parent_ids.iter().filter_map(|parent_id| store.get_value(u64::from(*parent_id))).map(to_value));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Counts the hits per range, a hit is counted once per range. Every range is returned, also without hits.
+fn get_range_facet(persistence: &Persistence, field: &str, ranges: &[FacetRange], ids: &[u32]) -> Result<Vec<FacetBucket>, VelociError> {
+    let mut buckets: Vec<FacetBucket> = ranges
+        .iter()
+        .map(|range| FacetBucket {
+            key: range.key.clone().unwrap_or_else(|| get_range_key(range.from, range.to)),
+            from: range.from,
+            to: range.to,
+            count: 0,
+        })
+        .collect();
+    let values_per_id = match NumericValuesPerAnchor::open(persistence, field)? {
+        Some(values_per_id) => values_per_id,
+        None => return Ok(buckets),
+    };
+    let mut values = vec![];
+    for id in ids {
+        values_per_id.get_values(*id, &mut values)?;
+        for bucket in buckets.iter_mut() {
+            if values.iter().any(|value| bucket.contains(*value)) {
+                bucket.count += 1;
+            }
+        }
+    }
+    Ok(buckets)
+}
+
+fn get_range_key(from: Option<f64>, to: Option<f64>) -> String {
+    let bound_to_string = |bound: Option<f64>| bound.map(|bound| bound.to_string()).unwrap_or_else(|| "*".to_string());
+    format!("{}-{}", bound_to_string(from), bound_to_string(to))
+}
+
+/// Counts the hits per interval, a hit is counted once per interval. The buckets are sorted by their start.
+fn get_histogram_facet(persistence: &Persistence, field: &str, interval: f64, ids: &[u32]) -> Result<Vec<FacetBucket>, VelociError> {
+    if !interval.is_finite() || interval <= 0.0 {
+        return Err(VelociError::InvalidRequest {
+            message: format!("histogram facet on {:?} requires a positive interval, got {}", field, interval),
+        });
+    }
+    let values_per_id = match NumericValuesPerAnchor::open(persistence, field)? {
+        Some(values_per_id) => values_per_id,
+        None => return Ok(vec![]),
+    };
+
+    let mut counts: FnvHashMap<i64, usize> = FnvHashMap::default();
+    let mut values = vec![];
+    let mut bucket_ids = vec![];
+    for id in ids {
+        values_per_id.get_values(*id, &mut values)?;
+        bucket_ids.clear();
+        bucket_ids.extend(values.iter().map(|value| (value / interval).floor() as i64));
+        bucket_ids.sort_unstable();
+        bucket_ids.dedup();
+        for bucket_id in bucket_ids.drain(..) {
+            *counts.entry(bucket_id).or_insert(0) += 1;
+        }
+    }
+    let mut buckets: Vec<(i64, usize)> = counts.into_iter().collect();
+    buckets.sort_unstable();
+    Ok(buckets
+        .into_iter()
+        .map(|(bucket_id, count)| {
+            let from = bucket_id as f64 * interval;
+            FacetBucket {
+                key: from.to_string(),
+                from: Some(from),
+                to: Some(from + interval),
+                count,
+            }
+        })
+        .collect())
+}
+
+/// Computes the stats of the values of a numeric or boost field over the ids
+pub fn get_stats(persistence: &Persistence, req: &StatsRequest, ids: &[u32]) -> Result<Stats, VelociError> {
+    info_time!("stats in field {:?}", req.field);
+    let mut stats = Stats::default();
+    let values_per_id = match NumericValuesPerAnchor::open(persistence, &req.field)? {
+        Some(values_per_id) => values_per_id,
+        None => return Ok(stats),
+    };
+    let mut values = vec![];
+    for id in ids {
+        values_per_id.get_values(*id, &mut values)?;
+        for value in &values {
+            stats.add(*value);
+        }
+    }
+    Ok(stats)
+}
+
+/// Merges the facet results of the segments of a facet request
+pub(crate) fn merge_facet_results(req: &FacetRequest, results: Vec<FacetResult>) -> FacetResult {
+    if req.ranges.is_some() || req.histogram.is_some() {
+        let mut merged: Vec<FacetBucket> = vec![];
+        for bucket in results.into_iter().flat_map(|result| result.as_buckets().cloned().unwrap_or_default()) {
+            match merged.iter_mut().find(|merged_bucket| merged_bucket.key == bucket.key) {
+                Some(merged_bucket) => merged_bucket.count += bucket.count,
+                None => merged.push(bucket),
+            }
+        }
+        if req.histogram.is_some() {
+            merged.sort_by(|a, b| a.from.partial_cmp(&b.from).unwrap_or(Ordering::Equal));
+        }
+        return FacetResult::Buckets(merged);
+    }
+
+    let mut counts: FnvHashMap<String, usize> = FnvHashMap::default();
+    for (text, count) in results.into_iter().flat_map(|result| result.as_terms().cloned().unwrap_or_default()) {
+        *counts.entry(text).or_insert(0) += count;
+    }
+    let mut groups: Vec<(String, usize)> = counts.into_iter().collect();
+    if req.date_histogram.is_some() {
+        groups.sort_unstable();
+    } else {
+        sort_and_filter_term_groups(req, &mut groups);
+    }
+    FacetResult::Terms(groups)
+}
+
+/// Returns the text ids of the field (given by its `steps`, see `util::get_steps_to_anchor`) for an anchor
+pub(crate) fn get_text_ids_for_anchor(persistence: &Persistence, steps: &[String], anchor_id: u32) -> Result<Vec<u32>, VelociError> {
+    let last_step = match steps.last() {
+        Some(last_step) => last_step,
+        None => return Ok(vec![]),
+    };
+    if steps.len() == 1 || persistence.has_index(&last_step.add(ANCHOR_TO_TEXT_ID)) {
+        let path = if steps.len() == 1 {
+            last_step.add(PARENT_TO_VALUE_ID)
+        } else {
+            last_step.add(ANCHOR_TO_TEXT_ID)
+        };
+        return Ok(persistence.get_valueid_to_parent(path)?.get_values(u64::from(anchor_id)).unwrap_or_default());
+    }
+    join_anchor_to_leaf(persistence, &[anchor_id], steps)
+}
+
 pub(crate) fn join_anchor_to_leaf(persistence: &Persistence, ids: &[u32], steps: &[String]) -> Result<Vec<u32>, VelociError> {
     let mut next_level_ids = { join_for_n_to_m(persistence, ids, &(steps.first().unwrap().add(PARENT_TO_VALUE_ID)))? };
     for step in steps.iter().skip(1) {
@@ -0,0 +1,72 @@
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+/// Parses a date to milliseconds since the unix epoch.
+///
+/// Without a `format` ISO-8601 dates are accepted, e.g. `2020-01-31`, `2020-01-31T12:00:00` or `2020-01-31T12:00:00+01:00`.
+/// Dates without timezone are interpreted as UTC.
+/// A custom `format` uses the chrono syntax, e.g. `%Y%m%d`.
+pub fn parse_date(text: &str, format: Option<&str>) -> Option<i64> {
+    let text = text.trim();
+    if let Some(format) = format {
+        return DateTime::parse_from_str(text, format)
+            .map(|date| date.timestamp_millis())
+            .or_else(|_| NaiveDateTime::parse_from_str(text, format).map(|date| date.and_utc().timestamp_millis()))
+            .or_else(|_| NaiveDate::parse_from_str(text, format).map(naive_date_to_millis))
+            .ok();
+    }
+
+    DateTime::parse_from_rfc3339(text)
+        .map(|date| date.timestamp_millis())
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f").map(|date| date.and_utc().timestamp_millis()))
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f").map(|date| date.and_utc().timestamp_millis()))
+        .or_else(|_| NaiveDate::parse_from_str(text, "%Y-%m-%d").map(naive_date_to_millis))
+        .ok()
+}
+
+fn naive_date_to_millis(date: NaiveDate) -> i64 {
+    date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp_millis()
+}
+
+/// The bucket size of a date histogram
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum DateInterval {
+    Day,
+    Month,
+    Year,
+}
+
+impl DateInterval {
+    /// Returns the key of the bucket for a timestamp in milliseconds, e.g. `2020-01` for `Month`.
+    ///
+    /// The keys sort lexicographically in chronological order.
+    pub fn bucket_key(self, millis: i64) -> Option<String> {
+        let date = Utc.timestamp_millis_opt(millis).single()?;
+        let key = match self {
+            DateInterval::Day => format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day()),
+            DateInterval::Month => format!("{:04}-{:02}", date.year(), date.month()),
+            DateInterval::Year => format!("{:04}", date.year()),
+        };
+        Some(key)
+    }
+}
+
+#[test]
+fn test_parse_date() {
+    assert_eq!(parse_date("1970-01-02", None), Some(86_400_000));
+    assert_eq!(parse_date("1970-01-01T00:00:01", None), Some(1_000));
+    assert_eq!(parse_date("1970-01-01 00:00:00.5", None), Some(500));
+    assert_eq!(parse_date("1970-01-01T01:00:00+01:00", None), Some(0));
+    assert_eq!(parse_date("19700102", Some("%Y%m%d")), Some(86_400_000));
+    assert_eq!(parse_date("02.01.1970 00:00", Some("%d.%m.%Y %H:%M")), Some(86_400_000));
+    assert_eq!(parse_date("19700102", None), None);
+    assert_eq!(parse_date("unknown", None), None);
+}
+
+#[test]
+fn test_bucket_key() {
+    let millis = parse_date("2020-02-29T23:59:59", None).unwrap();
+    assert_eq!(DateInterval::Day.bucket_key(millis), Some("2020-02-29".to_string()));
+    assert_eq!(DateInterval::Month.bucket_key(millis), Some("2020-02".to_string()));
+    assert_eq!(DateInterval::Year.bucket_key(millis), Some("2020".to_string()));
+}
@@ -0,0 +1,173 @@
+use crate::{
+    directory::Directory,
+    indices::{metadata::IndexValuesMetadata, NumericColumn, NumericColumnWriter, NumericValue},
+    metadata::NumericType,
+};
+use fnv::FnvHashMap;
+use itertools::Itertools;
+use std::io;
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// A point on earth in degrees
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl GeoPoint {
+    /// Creates a point, if latitude and longitude are in their valid ranges
+    pub fn new(lat: f64, lon: f64) -> Option<GeoPoint> {
+        if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) {
+            Some(GeoPoint { lat, lon })
+        } else {
+            None
+        }
+    }
+
+    /// Parses a point in the format `"lat,lon"`, e.g. `"52.52,13.40"`
+    pub fn parse(text: &str) -> Option<GeoPoint> {
+        let (lat, lon) = text.split_once(',')?;
+        GeoPoint::new(lat.trim().parse().ok()?, lon.trim().parse().ok()?)
+    }
+
+    /// The great-circle distance in kilometers (haversine formula)
+    pub fn distance_km(&self, other: &GeoPoint) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.lon - self.lon).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+    }
+}
+
+/// Which part of a geo point is stored in a path.
+///
+/// A geo point field accepts `"lat,lon"` strings or `{"lat": .., "lon": ..}` objects, in the latter case the values are in the subpaths `.lat` and `.lon`.
+#[derive(Debug, Clone)]
+pub(crate) enum GeoPart {
+    Point(String),
+    Lat(String),
+    Lon(String),
+}
+
+impl GeoPart {
+    pub(crate) fn field(&self) -> &str {
+        match self {
+            GeoPart::Point(field) | GeoPart::Lat(field) | GeoPart::Lon(field) => field,
+        }
+    }
+}
+
+/// Collects the geo points per anchor id while indexing. The anchor ids need to be added in ascending order.
+#[derive(Debug, Default)]
+pub(crate) struct GeoColumnWriter {
+    /// lat and lon are stored alternating
+    column: NumericColumnWriter,
+    /// incomplete points of objects by their value id
+    pending: FnvHashMap<u32, (Option<f64>, Option<f64>)>,
+}
+
+impl GeoColumnWriter {
+    pub(crate) fn add(&mut self, anchor_id: u32, value_id: u32, part: &GeoPart, value: &str) -> Result<(), io::Error> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("Expected a geo point in field {:?}, but got {:?}", part.field(), value));
+        match part {
+            GeoPart::Point(_) => {
+                let point = GeoPoint::parse(value).ok_or_else(invalid)?;
+                self.add_point(anchor_id, point);
+            }
+            GeoPart::Lat(_) | GeoPart::Lon(_) => {
+                let coordinate = value.parse::<f64>().map_err(|_| invalid())?;
+                let pending = self.pending.entry(value_id).or_default();
+                if let GeoPart::Lat(_) = part {
+                    pending.0 = Some(coordinate);
+                } else {
+                    pending.1 = Some(coordinate);
+                }
+                if let (Some(lat), Some(lon)) = *pending {
+                    self.pending.remove(&value_id);
+                    let point = GeoPoint::new(lat, lon).ok_or_else(invalid)?;
+                    self.add_point(anchor_id, point);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add_point(&mut self, anchor_id: u32, point: GeoPoint) {
+        self.column.add(anchor_id, point.lat.to_bits());
+        self.column.add(anchor_id, point.lon.to_bits());
+    }
+
+    /// Writes the column as `path.indirect` and `path.data`, objects without lat or lon are ignored
+    pub(crate) fn write(self, directory: &Box<dyn Directory>, path: &str) -> Result<IndexValuesMetadata, io::Error> {
+        self.column.write(directory, path)
+    }
+}
+
+/// Columnar store of the geo points per anchor id, a document can have multiple points.
+#[derive(Debug)]
+pub struct GeoColumn {
+    column: NumericColumn,
+}
+
+impl GeoColumn {
+    pub(crate) fn open(directory: &Box<dyn Directory>, path: &str) -> Result<GeoColumn, io::Error> {
+        Ok(GeoColumn {
+            column: NumericColumn::open(directory, path, NumericType::F64, None)?,
+        })
+    }
+
+    /// Number of anchor ids covered by the column
+    pub fn num_ids(&self) -> u32 {
+        self.column.num_ids()
+    }
+
+    /// Returns the points of the document
+    pub fn get_points(&self, anchor_id: u32) -> impl Iterator<Item = GeoPoint> + '_ {
+        self.column.get_values(anchor_id).tuples().map(|(lat, lon)| match (lat, lon) {
+            (NumericValue::F64(lat), NumericValue::F64(lon)) => GeoPoint { lat, lon },
+            _ => unreachable!("geo points are stored as f64"),
+        })
+    }
+
+    /// Returns the distance of the nearest point of the document in kilometers
+    pub fn get_min_distance_km(&self, anchor_id: u32, point: &GeoPoint) -> Option<f64> {
+        self.get_points(anchor_id).map(|other| point.distance_km(&other)).min_by(|a, b| a.total_cmp(b))
+    }
+
+    /// Returns the distance of the farthest point of the document in kilometers
+    pub fn get_max_distance_km(&self, anchor_id: u32, point: &GeoPoint) -> Option<f64> {
+        self.get_points(anchor_id).map(|other| point.distance_km(&other)).max_by(|a, b| a.total_cmp(b))
+    }
+}
+
+#[test]
+fn test_geo_point() {
+    assert_eq!(GeoPoint::parse("52.52, 13.405"), Some(GeoPoint { lat: 52.52, lon: 13.405 }));
+    assert_eq!(GeoPoint::parse("91,0"), None);
+    assert_eq!(GeoPoint::parse("52.52"), None);
+
+    let berlin = GeoPoint::new(52.52, 13.405).unwrap();
+    let paris = GeoPoint::new(48.8566, 2.3522).unwrap();
+    let distance = berlin.distance_km(&paris);
+    assert!((distance - 877.5).abs() < 1.0, "{}", distance);
+    assert_eq!(berlin.distance_km(&berlin), 0.0);
+}
+
+#[test]
+fn test_geo_column() {
+    let directory: Box<dyn Directory> = Box::new(crate::directory::RamDirectory::create());
+    let mut writer = GeoColumnWriter::default();
+    writer.add(0, 0, &GeoPart::Point("location".to_string()), "52.52,13.405").unwrap();
+    writer.add(2, 5, &GeoPart::Lon("location".to_string()), "2.3522").unwrap();
+    writer.add(2, 5, &GeoPart::Lat("location".to_string()), "48.8566").unwrap();
+    assert!(writer.add(3, 6, &GeoPart::Lat("location".to_string()), "north").is_err());
+    writer.write(&directory, "location.geo_values").unwrap();
+
+    let column = GeoColumn::open(&directory, "location.geo_values").unwrap();
+    assert_eq!(column.get_points(0).collect::<Vec<_>>(), vec![GeoPoint { lat: 52.52, lon: 13.405 }]);
+    assert_eq!(column.get_points(1).count(), 0);
+    assert_eq!(column.get_points(2).collect::<Vec<_>>(), vec![GeoPoint { lat: 48.8566, lon: 2.3522 }]);
+}
@@ -23,6 +23,8 @@ pub enum IndexCategory {
     KeyValue,
     AnchorScore,
     Phrase,
+    Numeric,
+    Geo,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
@@ -34,6 +36,9 @@ pub struct IndexMetadata {
     pub is_empty: bool,
     pub metadata: IndexValuesMetadata,
     pub data_type: DataType,
+    /// The chrono format of the values of a `DataType::Date` index, see `DateOptions`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
@@ -41,6 +46,10 @@ pub enum DataType {
     #[default]
     U32,
     U64,
+    I64,
+    F64,
+    /// i64 milliseconds since the unix epoch
+    Date,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
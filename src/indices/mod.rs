@@ -4,13 +4,19 @@ use std::path::Path;
 pub mod direct;
 #[macro_use]
 pub mod indirect;
+pub mod date;
+pub mod geo;
 pub mod metadata;
+pub mod numeric;
 pub mod persistence_data_binary_search;
 pub mod persistence_score;
 
+pub use date::*;
 pub(crate) use direct::*;
+pub use geo::*;
 pub(crate) use indirect::*;
 pub(crate) use metadata::*;
+pub use numeric::*;
 pub(crate) use persistence_data_binary_search::*;
 pub(crate) use persistence_score::*;
 
@@ -0,0 +1,257 @@
+use crate::{
+    directory::{load_data_pair, Directory},
+    indices::{date::parse_date, metadata::IndexValuesMetadata},
+    metadata::NumericType,
+    persistence::{vec_to_bytes, SORTED_VALUES},
+    util::*,
+};
+use ownedbytes::OwnedBytes;
+use std::{cmp::Ordering, io, path::Path};
+
+/// A typed numeric value, read from a `NumericColumn`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum NumericValue {
+    U64(u64),
+    I64(i64),
+    F64(f64),
+}
+
+impl NumericValue {
+    pub fn to_f64(self) -> f64 {
+        match self {
+            NumericValue::I64(val) => val as f64,
+            NumericValue::U64(val) => val as f64,
+            NumericValue::F64(val) => val,
+        }
+    }
+
+    /// Compares the values numerically, values of different types are compared as f64
+    pub fn cmp_numeric(&self, other: &NumericValue) -> Ordering {
+        match (self, other) {
+            (NumericValue::I64(val), NumericValue::I64(other)) => val.cmp(other),
+            (NumericValue::U64(val), NumericValue::U64(other)) => val.cmp(other),
+            _ => self.to_f64().total_cmp(&other.to_f64()),
+        }
+    }
+}
+
+impl From<NumericValue> for serde_json::Value {
+    fn from(val: NumericValue) -> serde_json::Value {
+        match val {
+            NumericValue::I64(val) => val.into(),
+            NumericValue::U64(val) => val.into(),
+            NumericValue::F64(val) => val.into(),
+        }
+    }
+}
+
+impl NumericType {
+    /// Parses the text representation of a value to its u64 bits, which are stored in the `NumericColumn`
+    pub fn parse_to_bits(self, text: &str) -> Option<u64> {
+        match self {
+            NumericType::I64 => text.parse::<i64>().ok().map(|val| val as u64),
+            NumericType::U64 => text.parse::<u64>().ok(),
+            NumericType::F64 => text.parse::<f64>().ok().filter(|val| !val.is_nan()).map(f64::to_bits),
+            NumericType::Date => parse_date(text, None).map(|millis| millis as u64),
+        }
+    }
+
+    pub fn parse(self, text: &str) -> Option<NumericValue> {
+        self.parse_to_bits(text).map(|bits| self.from_bits(bits))
+    }
+
+    #[inline]
+    pub fn from_bits(self, bits: u64) -> NumericValue {
+        match self {
+            NumericType::I64 | NumericType::Date => NumericValue::I64(bits as i64),
+            NumericType::U64 => NumericValue::U64(bits),
+            NumericType::F64 => NumericValue::F64(f64::from_bits(bits)),
+        }
+    }
+}
+
+/// Collects the numeric values per anchor id while indexing. The anchor ids need to be added in ascending order.
+#[derive(Debug, Default)]
+pub(crate) struct NumericColumnWriter {
+    /// start position in `values` per anchor id
+    offsets: Vec<u32>,
+    values: Vec<u64>,
+}
+
+impl NumericColumnWriter {
+    #[inline]
+    pub(crate) fn add(&mut self, anchor_id: u32, bits: u64) {
+        while self.offsets.len() <= anchor_id as usize {
+            self.offsets.push(self.values.len() as u32);
+        }
+        self.values.push(bits);
+    }
+
+    /// Writes the positions of the values sorted by value as `path.sorted_values`, which is used for range queries
+    pub(crate) fn write_sorted_values(&self, directory: &Box<dyn Directory>, path: &str, numeric_type: NumericType) -> Result<(), io::Error> {
+        let mut sorted_values: Vec<u32> = (0..self.values.len() as u32).collect();
+        sorted_values.sort_by(|pos, other| {
+            let value = numeric_type.from_bits(self.values[*pos as usize]);
+            value.cmp_numeric(&numeric_type.from_bits(self.values[*other as usize]))
+        });
+        directory.write(Path::new(&path.add(SORTED_VALUES)), &vec_to_bytes(&sorted_values))?;
+        Ok(())
+    }
+
+    /// Writes the column as `path.indirect` and `path.data`
+    pub(crate) fn write(mut self, directory: &Box<dyn Directory>, path: &str) -> Result<IndexValuesMetadata, io::Error> {
+        let num_ids = self.offsets.len() as u32;
+        self.offsets.push(self.values.len() as u32);
+        let path = Path::new(path);
+        directory.write(&path.set_ext(Ext::Indirect), &vec_to_bytes(&self.offsets))?;
+        directory.write(&path.set_ext(Ext::Data), &vec_to_bytes(&self.values))?;
+        Ok(IndexValuesMetadata {
+            num_values: self.values.len() as u64,
+            num_ids,
+            ..Default::default()
+        })
+    }
+}
+
+/// Columnar store for the values of a numeric field per anchor id, a document can have multiple values for a field.
+///
+/// The values are stored with their u64 bits, the `NumericType` defines how they are interpreted.
+#[derive(Debug)]
+pub struct NumericColumn {
+    pub numeric_type: NumericType,
+    /// The format of the date values, when they were indexed with a custom format
+    pub date_format: Option<String>,
+    offsets: OwnedBytes,
+    values: OwnedBytes,
+    /// The positions in `values` sorted by value, None for indices created without it
+    sorted_values: Option<OwnedBytes>,
+}
+
+impl NumericColumn {
+    pub(crate) fn open(directory: &Box<dyn Directory>, path: &str, numeric_type: NumericType, date_format: Option<String>) -> Result<NumericColumn, io::Error> {
+        let (offsets, values) = load_data_pair(directory, Path::new(path))?;
+        let sorted_values_path = path.add(SORTED_VALUES);
+        let sorted_values = if directory.exists(Path::new(&sorted_values_path))? {
+            Some(directory.get_file_bytes(Path::new(&sorted_values_path))?)
+        } else {
+            None
+        };
+        Ok(NumericColumn {
+            numeric_type,
+            date_format: date_format.filter(|_| numeric_type == NumericType::Date),
+            offsets,
+            values,
+            sorted_values,
+        })
+    }
+
+    /// Parses the text representation of a value. Dates are parsed with the format of the field, or as ISO-8601.
+    pub fn parse(&self, text: &str) -> Option<NumericValue> {
+        self.date_format
+            .as_deref()
+            .and_then(|format| parse_date(text, Some(format)))
+            .map(NumericValue::I64)
+            .or_else(|| self.numeric_type.parse(text))
+    }
+
+    /// Number of anchor ids covered by the column
+    pub fn num_ids(&self) -> u32 {
+        (self.offsets.len() / 4).saturating_sub(1) as u32
+    }
+
+    /// Returns the values of the document
+    pub fn get_values(&self, anchor_id: u32) -> impl Iterator<Item = NumericValue> + '_ {
+        let range = if anchor_id < self.num_ids() {
+            let pos = anchor_id as usize * 4;
+            get_u32_from_bytes(&self.offsets, pos) as usize..get_u32_from_bytes(&self.offsets, pos + 4) as usize
+        } else {
+            0..0
+        };
+        range.map(move |index| self.numeric_type.from_bits(get_u64_from_bytes(&self.values, index * 8)))
+    }
+
+    /// Returns the first value of the document
+    pub fn get_value(&self, anchor_id: u32) -> Option<NumericValue> {
+        self.get_values(anchor_id).next()
+    }
+
+    /// Returns the sorted anchor ids with a value, which is neither below the lower bound nor above the upper bound.
+    ///
+    /// With the sorted values only the values in the range are visited, the bounds are found by binary search.
+    /// Indices without sorted values are scanned completely.
+    pub fn get_anchors_in_range(&self, is_below: impl Fn(&NumericValue) -> bool, is_above: impl Fn(&NumericValue) -> bool) -> Vec<u32> {
+        let sorted_values = match self.sorted_values.as_ref() {
+            Some(sorted_values) => sorted_values,
+            None => {
+                return (0..self.num_ids())
+                    .filter(|anchor_id| self.get_values(*anchor_id).any(|val| !is_below(&val) && !is_above(&val)))
+                    .collect();
+            }
+        };
+        let value_at = |index: usize| {
+            let pos = get_u32_from_bytes(sorted_values, index * 4) as usize;
+            self.numeric_type.from_bits(get_u64_from_bytes(&self.values, pos * 8))
+        };
+        let num_values = sorted_values.len() / 4;
+        let start = partition_point(num_values, |index| is_below(&value_at(index)));
+        let end = start + partition_point(num_values - start, |index| !is_above(&value_at(start + index)));
+
+        let mut anchor_ids: Vec<u32> = (start..end).map(|index| self.get_anchor_for_value(get_u32_from_bytes(sorted_values, index * 4))).collect();
+        anchor_ids.sort_unstable();
+        anchor_ids.dedup();
+        anchor_ids
+    }
+
+    /// Returns the anchor id of the value at the position in `values`
+    fn get_anchor_for_value(&self, pos: u32) -> u32 {
+        // the last anchor id, whose values start at or before the position
+        partition_point(self.num_ids() as usize + 1, |anchor_id| get_u32_from_bytes(&self.offsets, anchor_id * 4) <= pos) as u32 - 1
+    }
+}
+
+/// Returns the number of leading indices in `0..len`, for which the predicate is true. The predicate needs to be partitioned.
+fn partition_point(len: usize, pred: impl Fn(usize) -> bool) -> usize {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if pred(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+#[test]
+fn test_numeric_column() {
+    let directory: Box<dyn Directory> = Box::new(crate::directory::RamDirectory::create());
+    let mut writer = NumericColumnWriter::default();
+    writer.add(0, NumericType::I64.parse_to_bits("-5").unwrap());
+    writer.add(2, NumericType::I64.parse_to_bits("10").unwrap());
+    writer.add(2, NumericType::I64.parse_to_bits("11").unwrap());
+    writer.write_sorted_values(&directory, "price.numeric_values", NumericType::I64).unwrap();
+    let metadata = writer.write(&directory, "price.numeric_values").unwrap();
+    assert_eq!(metadata.num_ids, 3);
+    assert_eq!(metadata.num_values, 3);
+
+    let column = NumericColumn::open(&directory, "price.numeric_values", NumericType::I64, None).unwrap();
+    assert_eq!(column.get_value(0), Some(NumericValue::I64(-5)));
+    assert_eq!(column.get_value(1), None);
+    assert_eq!(column.get_values(2).collect::<Vec<_>>(), vec![NumericValue::I64(10), NumericValue::I64(11)]);
+    assert_eq!(column.get_value(3), None);
+
+    let is_below = |bound: i64| move |val: &NumericValue| val.cmp_numeric(&NumericValue::I64(bound)) == Ordering::Less;
+    let is_above = |bound: i64| move |val: &NumericValue| val.cmp_numeric(&NumericValue::I64(bound)) == Ordering::Greater;
+    assert_eq!(column.get_anchors_in_range(is_below(-10), is_above(20)), vec![0, 2]);
+    assert_eq!(column.get_anchors_in_range(is_below(10), is_above(11)), vec![2]);
+    assert_eq!(column.get_anchors_in_range(is_below(-5), is_above(9)), vec![0]);
+    assert_eq!(column.get_anchors_in_range(is_below(12), is_above(20)), Vec::<u32>::new());
+
+    // indices created without sorted values are scanned
+    directory.delete(Path::new("price.numeric_values.sorted_values")).unwrap();
+    let column = NumericColumn::open(&directory, "price.numeric_values", NumericType::I64, None).unwrap();
+    assert_eq!(column.get_anchors_in_range(is_below(-10), is_above(20)), vec![0, 2]);
+    assert_eq!(column.get_anchors_in_range(is_below(10), is_above(11)), vec![2]);
+}
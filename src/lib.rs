@@ -29,6 +29,7 @@ pub mod util;
 #[macro_use]
 pub mod type_info;
 
+pub mod aggregation;
 #[cfg(feature = "create")]
 pub mod create;
 
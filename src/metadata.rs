@@ -1,4 +1,5 @@
 use crate::{
+    create::CreateIndexConfig,
     directory::Directory,
     error::VelociError,
     indices::metadata::*,
@@ -12,6 +13,60 @@ pub struct PeristenceMetaData {
     pub num_docs: u64,
     pub bytes_indexed: u64,
     pub columns: FnvHashMap<String, FieldInfo>,
+    /// Segments appended after the initial index creation, in insertion order.
+    /// The anchor ids of a segment start after the anchor ids of all previous segments.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<SegmentInfo>,
+    /// The field which uniquely identifies a document, if configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary_key: Option<PrimaryKeyConfig>,
+    /// The config the indices were created with, used to rebuild them when segments are merged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_config: Option<CreateIndexConfig>,
+    /// Set when the initial segment has been replaced by a merged segment. Its files are then prefixed with this name,
+    /// while metaData.json stays in the root of the directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_segment: Option<String>,
+}
+
+/// 'PrimaryKeyConfig' defines a field, which is used as an external id for the documents.
+/// The field needs to be a single value on the root level of the document, e.g. `ent_seq`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PrimaryKeyConfig {
+    pub field: String,
+    #[serde(default)]
+    pub on_duplicate: OnDuplicateKey,
+}
+
+/// How duplicate primary keys are handled while creating an index
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OnDuplicateKey {
+    /// Index creation fails with `VelociError::DuplicatePrimaryKey`
+    #[default]
+    Reject,
+    /// The last document with the key is kept, previous documents are marked as deleted
+    LastWriteWins,
+}
+
+/// The type of a numeric field, the values are stored in a `NumericColumn` per anchor id
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NumericType {
+    I64,
+    U64,
+    F64,
+    /// Milliseconds since the unix epoch, set by the `date` option of a field
+    #[serde(skip)]
+    Date,
+}
+
+/// 'SegmentInfo' describes a segment, which is stored with its own set of indices in the same directory.
+/// All files of a segment are prefixed with its name.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SegmentInfo {
+    pub name: String,
+    pub num_docs: u64,
 }
 
 impl PeristenceMetaData {
@@ -22,6 +77,9 @@ impl PeristenceMetaData {
         for val in obj.columns.values_mut() {
             val.textindex_metadata.options.create_tokenizer(); //  TODO reuse default tokenizer
         }
+        if let Some(index_config) = obj.index_config.as_mut() {
+            index_config.create_tokenizers();
+        }
         Ok(obj)
     }
 
@@ -40,6 +98,12 @@ pub struct FieldInfo {
     /// special case when text_id equals document id
     pub is_anchor_identity_column: bool,
     pub has_fst: bool,
+    /// The separator of a field with the `hierarchy` option, its path prefixes are in the field `util::get_path_prefixes_field`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hierarchy_separator: Option<String>,
+    /// The field holds the generated path prefixes of a field with the `hierarchy` option, it is not searched if no fields are given
+    #[serde(default)]
+    pub is_path_prefixes: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
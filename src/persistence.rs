@@ -1,6 +1,6 @@
 pub use crate::metadata::*;
 use crate::{
-    directory::{load_data_pair, Directory, MmapDirectory, RamDirectory},
+    directory::{load_data_pair, Directory, MmapDirectory, PrefixDirectory, RamDirectory},
     error::VelociError,
     indices::*,
     search::*,
@@ -8,6 +8,7 @@ use crate::{
     util::*,
 };
 use colored::*;
+use fixedbitset::FixedBitSet;
 use fnv::FnvHashMap;
 use fst::Map;
 use ownedbytes::OwnedBytes;
@@ -24,29 +25,46 @@ pub const TOKENS_TO_TEXT_ID: &str = ".tokens_to_text_id";
 pub const TEXT_ID_TO_TOKEN_IDS: &str = ".text_id_to_token_ids";
 pub const TO_ANCHOR_ID_SCORE: &str = ".to_anchor_id_score";
 pub const PHRASE_PAIR_TO_ANCHOR: &str = ".phrase_pair_to_anchor";
+/// Maps a token id to the pairs of text id and position of the token in the text, for exact phrase queries
+pub const TOKEN_TO_TEXT_ID_POSITIONS: &str = ".token_to_text_id_positions";
 pub const VALUE_ID_TO_PARENT: &str = ".value_id_to_parent";
 pub const PARENT_TO_VALUE_ID: &str = ".parent_to_value_id";
 pub const TEXT_ID_TO_ANCHOR: &str = ".text_id_to_anchor";
 // pub const PARENT_TO_TEXT_ID: &str = ".parent_to_text_id";
 pub const ANCHOR_TO_TEXT_ID: &str = ".anchor_to_text_id";
+/// Maps a text id to the smallest text id with the same lower case text, for case insensitive facets
+pub const TEXT_ID_TO_LOWER_CASE_TEXT_ID: &str = ".text_id_to_lower_case_text_id";
 pub const BOOST_VALID_TO_VALUE: &str = ".boost_valid_to_value";
 pub const VALUE_ID_TO_ANCHOR: &str = ".value_id_to_anchor";
 pub const TOKEN_VALUES: &str = ".token_values";
+pub const NUMERIC_VALUES: &str = ".numeric_values";
+pub const GEO_VALUES: &str = ".geo_values";
 
 pub const TEXTINDEX: &str = ".textindex";
 
+/// Bitset of deleted anchor ids, stored per segment
+pub const DELETED_DOCS: &str = "deleted_docs";
+/// FST from primary key to anchor id, stored per segment
+pub const PRIMARY_KEY: &str = "primary_key";
+/// Positions of the values of a `NumericColumn`, sorted by value
+pub const SORTED_VALUES: &str = ".sorted_values";
+
 pub static INDEX_FILE_ENDINGS: &[&str] = &[
     TOKENS_TO_TEXT_ID,
     TEXT_ID_TO_TOKEN_IDS,
     TO_ANCHOR_ID_SCORE,
     PHRASE_PAIR_TO_ANCHOR,
+    TOKEN_TO_TEXT_ID_POSITIONS,
     VALUE_ID_TO_PARENT,
     PARENT_TO_VALUE_ID,
     TEXT_ID_TO_ANCHOR,
     ANCHOR_TO_TEXT_ID,
+    TEXT_ID_TO_LOWER_CASE_TEXT_ID,
     BOOST_VALID_TO_VALUE,
     VALUE_ID_TO_ANCHOR,
     TOKEN_VALUES,
+    NUMERIC_VALUES,
+    GEO_VALUES,
 ];
 
 #[derive(Debug, Default)]
@@ -55,16 +73,25 @@ pub struct PersistenceIndices {
     pub token_to_anchor_score: HashMap<String, Box<dyn TokenToAnchorScore>>,
     pub phrase_pair_to_anchor: HashMap<String, Box<dyn PhrasePairToAnchor<Input = (u32, u32)>>>,
     pub boost_valueid_to_value: HashMap<String, Box<dyn IndexIdToParent<Output = u32>>>,
+    pub numeric_values: HashMap<String, NumericColumn>,
+    pub geo_values: HashMap<String, GeoColumn>,
     // index_64: HashMap<String, Box<IndexIdToParent<Output = u64>>>,
     pub fst: HashMap<String, Map<OwnedBytes>>,
 }
 
 pub struct Persistence {
     pub directory: Box<dyn Directory>, // folder
+    /// The directory of metaData.json and the appended segments. Equal to `directory`, unless the initial segment
+    /// has been replaced by a merged segment, see `PeristenceMetaData::initial_segment`
+    pub root_directory: Box<dyn Directory>,
     pub metadata: PeristenceMetaData,
     pub indices: PersistenceIndices,
     pub lru_cache: HashMap<String, LruCache<RequestSearchPart, SearchResult>>,
     pub term_boost_cache: RwLock<LruCache<Vec<RequestSearchPart>, Vec<SearchFieldResult>>>,
+    /// Segments which have been appended to this persistence, see `PeristenceMetaData::segments`
+    pub segments: Vec<Persistence>,
+    /// Deleted anchor ids of this segment
+    pub deleted_docs: Option<FixedBitSet>,
 }
 
 impl fmt::Debug for Persistence {
@@ -232,6 +259,9 @@ impl Persistence {
                         match el.data_type {
                             DataType::U32 => Box::new(TokenToAnchorScoreVint::<u32>::from_data(indirect_data, data)?),
                             DataType::U64 => Box::new(TokenToAnchorScoreVint::<u64>::from_data(indirect_data, data)?),
+                            DataType::I64 | DataType::F64 | DataType::Date => {
+                                return Err(VelociError::StringError(format!("unsupported data type for anchor score index {}", el.path)))
+                            }
                         }
                     };
                     self.indices.token_to_anchor_score.insert(el.path.to_string(), store);
@@ -251,6 +281,21 @@ impl Persistence {
                         }
                     }
                 }
+                IndexCategory::Numeric => {
+                    let numeric_type = match el.data_type {
+                        DataType::I64 => NumericType::I64,
+                        DataType::U64 => NumericType::U64,
+                        DataType::F64 => NumericType::F64,
+                        DataType::Date => NumericType::Date,
+                        DataType::U32 => return Err(VelociError::StringError(format!("unsupported data type for numeric index {}", el.path))),
+                    };
+                    let store = NumericColumn::open(&self.directory, &el.path, numeric_type, el.date_format.clone())?;
+                    self.indices.numeric_values.insert(el.path.to_string(), store);
+                }
+                IndexCategory::Geo => {
+                    let store = GeoColumn::open(&self.directory, &el.path)?;
+                    self.indices.geo_values.insert(el.path.to_string(), store);
+                }
                 IndexCategory::KeyValue => {
                     info_time!("loaded key_value_store {:?}", &el.path);
 
@@ -287,6 +332,10 @@ impl Persistence {
         }
 
         self.load_all_fst()?;
+        if self.metadata.primary_key.is_some() {
+            let map = self.load_fst(PRIMARY_KEY)?;
+            self.indices.fst.insert(PRIMARY_KEY.to_string(), map);
+        }
         Ok(())
     }
 
@@ -313,6 +362,26 @@ impl Persistence {
         self.indices.boost_valueid_to_value.get(path).map(|el| el.as_ref()).ok_or_else(|| path_not_found(path))
     }
 
+    /// Returns the numeric values per anchor id of a field with the `numeric` option
+    pub fn get_numeric_column(&self, field: &str) -> Result<&NumericColumn, VelociError> {
+        let path = field.add(NUMERIC_VALUES);
+        self.indices.numeric_values.get(&path).ok_or_else(|| path_not_found(&path))
+    }
+
+    pub fn has_numeric_column(&self, field: &str) -> bool {
+        self.indices.numeric_values.contains_key(&field.add(NUMERIC_VALUES))
+    }
+
+    /// Returns the geo points per anchor id of a field with the `geo_point` option
+    pub fn get_geo_column(&self, field: &str) -> Result<&GeoColumn, VelociError> {
+        let path = field.add(GEO_VALUES);
+        self.indices.geo_values.get(&path).ok_or_else(|| path_not_found(&path))
+    }
+
+    pub fn has_geo_column(&self, field: &str) -> bool {
+        self.indices.geo_values.contains_key(&field.add(GEO_VALUES))
+    }
+
     pub fn has_index(&self, path: &str) -> bool {
         self.indices.key_value_stores.contains_key(path)
     }
@@ -347,8 +416,140 @@ impl Persistence {
             .ok_or_else(|| path_not_found(path.as_ref()))
     }
 
+    /// Returns the number of documents over all segments
     pub fn get_number_of_documents(&self) -> u64 {
-        self.metadata.num_docs
+        self.metadata.num_docs + self.segments.iter().map(|segment| segment.get_number_of_documents()).sum::<u64>()
+    }
+
+    /// Iterates over all segments, including the initial one, with the offset of their anchor ids.
+    pub fn get_segments(&self) -> impl Iterator<Item = (u32, &Persistence)> {
+        let mut offset = 0;
+        std::iter::once(self).chain(self.segments.iter()).map(move |segment| {
+            let segment_offset = offset;
+            offset += segment.metadata.num_docs as u32;
+            (segment_offset, segment)
+        })
+    }
+
+    /// Returns the segment containing the global anchor id and the anchor id local to this segment.
+    pub fn get_segment_for_id(&self, id: u32) -> (&Persistence, u32) {
+        let (segment_index, segment_id) = self.get_segment_index_for_id(id);
+        let segment = if segment_index == 0 { self } else { &self.segments[segment_index - 1] };
+        (segment, segment_id)
+    }
+
+    /// Returns the index of the segment (in the order of `get_segments`) containing the global anchor id and the anchor id local to this segment.
+    pub fn get_segment_index_for_id(&self, id: u32) -> (usize, u32) {
+        let mut segment_index_and_id = (0, id);
+        for (segment_index, (offset, _)) in self.get_segments().enumerate() {
+            if id < offset {
+                break;
+            }
+            segment_index_and_id = (segment_index, id - offset);
+        }
+        segment_index_and_id
+    }
+
+    /// Returns true if the anchor id, local to this segment, has been deleted
+    #[inline]
+    pub fn is_deleted(&self, id: u32) -> bool {
+        self.deleted_docs.as_ref().map(|deleted_docs| deleted_docs.contains(id as usize)).unwrap_or(false)
+    }
+
+    /// Returns true if there are deleted documents in this segment
+    pub fn has_deleted_docs(&self) -> bool {
+        self.deleted_docs.is_some()
+    }
+
+    /// Returns the number of deleted documents over all segments
+    pub fn get_number_of_deleted_documents(&self) -> u64 {
+        self.get_segments()
+            .map(|(_, segment)| segment.deleted_docs.as_ref().map(|deleted_docs| deleted_docs.count_ones(..) as u64).unwrap_or(0))
+            .sum()
+    }
+
+    /// Marks the documents as deleted. The anchor ids are global over all segments.
+    ///
+    /// Deleted documents are not removed from the indices, but are skipped in search, facets, suggest and when reading documents.
+    pub fn delete_documents(&mut self, ids: &[u32]) -> Result<(), VelociError> {
+        let num_docs = self.get_number_of_documents();
+        if let Some(invalid_id) = ids.iter().find(|id| u64::from(**id) >= num_docs) {
+            return Err(VelociError::InvalidRequest {
+                message: format!("can't delete document {}, there are only {} documents", invalid_id, num_docs),
+            });
+        }
+        let mut ids_per_segment: Vec<Vec<u32>> = vec![vec![]; self.segments.len() + 1];
+        for id in ids {
+            let (segment_index, segment_id) = self.get_segment_index_for_id(*id);
+            ids_per_segment[segment_index].push(segment_id);
+        }
+        for (segment_index, segment_ids) in ids_per_segment.into_iter().enumerate().filter(|(_, ids)| !ids.is_empty()) {
+            let segment = if segment_index == 0 { &mut *self } else { &mut self.segments[segment_index - 1] };
+            segment.add_deleted_docs(&segment_ids)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn add_deleted_docs(&mut self, ids: &[u32]) -> Result<(), VelociError> {
+        let num_docs = self.metadata.num_docs as usize;
+        let deleted_docs = self.deleted_docs.get_or_insert_with(|| FixedBitSet::with_capacity(num_docs));
+        for id in ids {
+            deleted_docs.insert(*id as usize);
+        }
+        let bytes = vec_to_bytes(deleted_docs.as_slice());
+        self.write_data(DELETED_DOCS, &bytes)?;
+        Ok(())
+    }
+
+    fn load_deleted_docs(&mut self) -> Result<(), VelociError> {
+        if !self.directory.exists(Path::new(DELETED_DOCS))? {
+            return Ok(());
+        }
+        let bytes = self.get_file_bytes(DELETED_DOCS)?;
+        let blocks: Vec<u32> = bytes.as_slice().chunks_exact(4).map(|block| get_u32_from_bytes(block, 0)).collect();
+        self.deleted_docs = Some(FixedBitSet::with_capacity_and_blocks(self.metadata.num_docs as usize, blocks));
+        Ok(())
+    }
+
+    /// Returns the global anchor id for the primary key, if there is a not deleted document with the key.
+    pub fn get_anchor_by_key(&self, key: &str) -> Option<u32> {
+        self.get_anchor_by_key_in_segments(key, self.segments.len() + 1)
+    }
+
+    /// Returns the global anchor ids for the primary keys
+    pub fn get_anchors_by_keys<S: AsRef<str>>(&self, keys: &[S]) -> Vec<Option<u32>> {
+        keys.iter().map(|key| self.get_anchor_by_key(key.as_ref())).collect()
+    }
+
+    /// Lookup of the primary key in the first `num_segments` segments, newer segments are checked first.
+    pub(crate) fn get_anchor_by_key_in_segments(&self, key: &str, num_segments: usize) -> Option<u32> {
+        let segments: Vec<_> = self.get_segments().take(num_segments).collect();
+        segments.into_iter().rev().find_map(|(offset, segment)| {
+            let map = segment.indices.fst.get(PRIMARY_KEY)?;
+            let segment_id = map.get(key)? as u32;
+            if segment.is_deleted(segment_id) {
+                None
+            } else {
+                Some(offset + segment_id)
+            }
+        })
+    }
+
+    /// Deletes the documents with the primary keys and returns the number of deleted documents.
+    pub fn delete_documents_by_keys<S: AsRef<str>>(&mut self, keys: &[S]) -> Result<u64, VelociError> {
+        let ids: Vec<u32> = self.get_anchors_by_keys(keys).into_iter().flatten().collect();
+        self.delete_documents(&ids)?;
+        Ok(ids.len() as u64)
+    }
+
+    /// Opens the segment with the name, the files of the segment are prefixed with its name.
+    pub fn open_segment(&self, name: &str) -> Result<Persistence, VelociError> {
+        Self::open(Box::new(PrefixDirectory::new(&get_segment_prefix(name), self.root_directory.box_clone())))
+    }
+
+    /// Creates a new empty segment persistence with the name, which can be used to index data.
+    pub fn create_segment(&self, name: &str) -> Result<Persistence, io::Error> {
+        Self::create(Box::new(PrefixDirectory::new(&get_segment_prefix(name), self.root_directory.box_clone())))
     }
 
     pub fn get_bytes_indexed(&self) -> u64 {
@@ -361,7 +562,8 @@ impl Persistence {
     }
 
     pub fn write_metadata(&self) -> Result<(), VelociError> {
-        self.write_data("metaData.json", serde_json::to_string_pretty(&self.metadata)?.as_bytes())?;
+        self.root_directory
+            .write(Path::new("metaData.json"), serde_json::to_string_pretty(&self.metadata)?.as_bytes())?;
         Ok(())
     }
 
@@ -370,11 +572,14 @@ impl Persistence {
     pub fn create(directory: Box<dyn Directory>) -> Result<Self, io::Error> {
         let metadata = PeristenceMetaData { ..Default::default() };
         Ok(Persistence {
+            root_directory: directory.box_clone(),
             directory,
             metadata,
             lru_cache: HashMap::default(),
             term_boost_cache: RwLock::new(LruCache::with_expiry_duration_and_capacity(Duration::new(3600, 0), 10)),
             indices: PersistenceIndices::default(),
+            segments: vec![],
+            deleted_docs: None,
         })
     }
 
@@ -397,14 +602,29 @@ impl Persistence {
 
     pub fn open(directory: Box<dyn Directory>) -> Result<Self, VelociError> {
         let metadata = PeristenceMetaData::new(&directory)?;
+        let root_directory = directory.box_clone();
+        let directory: Box<dyn Directory> = match metadata.initial_segment.as_ref() {
+            Some(name) => Box::new(PrefixDirectory::new(&get_segment_prefix(name), directory)),
+            None => directory,
+        };
         let mut pers = Persistence {
+            root_directory,
             directory,
             metadata,
             lru_cache: HashMap::default(),
             term_boost_cache: RwLock::new(LruCache::with_expiry_duration_and_capacity(Duration::new(3600, 0), 10)),
             indices: PersistenceIndices::default(),
+            segments: vec![],
+            deleted_docs: None,
         };
         pers.load_indices()?;
+        pers.load_deleted_docs()?;
+        pers.segments = pers
+            .metadata
+            .segments
+            .iter()
+            .map(|segment| pers.open_segment(&segment.name))
+            .collect::<Result<Vec<_>, _>>()?;
         pers.print_heap_sizes();
         Ok(pers)
     }
@@ -451,6 +671,10 @@ impl Persistence {
     }
 }
 
+fn get_segment_prefix(name: &str) -> String {
+    name.to_string() + "."
+}
+
 fn path_not_found(path: &str) -> VelociError {
     let error = format!("Did not found path in indices {}", path);
     error!("{:?}", error);
@@ -72,8 +72,8 @@ fn get_all_field_request_parts_and_propagate_settings_to_search_req<'a>(header_r
     merge_explain(request.get_options_mut(), header_request.explain);
 
     match request {
-        SearchRequest::And(SearchTree { queries, options: _ }) | SearchRequest::Or(SearchTree { queries, options: _ }) => {
-            for el in queries {
+        SearchRequest::And(SearchTree { queries, must_not, should, .. }) | SearchRequest::Or(SearchTree { queries, must_not, should, .. }) => {
+            for el in queries.iter_mut().chain(must_not.iter_mut()).chain(should.iter_mut()) {
                 get_all_field_request_parts_and_propagate_settings_to_search_req(header_request, el, map);
             }
         }
@@ -81,6 +81,11 @@ fn get_all_field_request_parts_and_propagate_settings_to_search_req<'a>(header_r
             merge_explain(&mut search.options, header_request.explain);
             map.push(search);
         }
+        // phrase, proximity and range queries resolve their terms themselves, so they are not part of the field search cache
+        SearchRequest::Phrase(_) => {}
+        SearchRequest::Proximity(_) => {}
+        SearchRequest::Range(_) => {}
+        SearchRequest::GeoDistance(_) => {}
     }
 }
 
@@ -283,8 +288,39 @@ fn plan_creator_2(
 ) -> PlanStepId {
     // request.explain |= request_header.explain;
 
+    if let SearchRequest::Or(tree) | SearchRequest::And(tree) = request {
+        if !tree.must_not.is_empty() {
+            return plan_creator_must_not(
+                is_filter,
+                is_filter_channel,
+                filter_channel_step,
+                request_header,
+                request,
+                boost,
+                plan,
+                parent_step_dependecy,
+                depends_on_step,
+                field_search_cache,
+            );
+        }
+        if !tree.should.is_empty() {
+            return plan_creator_should(
+                is_filter,
+                is_filter_channel,
+                filter_channel_step,
+                request_header,
+                request,
+                boost,
+                plan,
+                parent_step_dependecy,
+                depends_on_step,
+                field_search_cache,
+            );
+        }
+    }
+
     match request {
-        SearchRequest::Or(SearchTree { queries, options: _ }) => {
+        SearchRequest::Or(SearchTree { queries, .. }) => {
             let mut channel = PlanStepDataChannels::default();
             if let Some(step_id) = filter_channel_step {
                 plan.get_step_channel(step_id).filter_channel.as_mut().unwrap().num_receivers += 1;
@@ -326,7 +362,7 @@ fn plan_creator_2(
 
             step_id
         }
-        SearchRequest::And(SearchTree { queries, options: _ }) => {
+        SearchRequest::And(SearchTree { queries, .. }) => {
             let mut channel = PlanStepDataChannels::default();
             if let Some(step_id) = filter_channel_step {
                 plan.get_step_channel(step_id).filter_channel.as_mut().unwrap().num_receivers += 1;
@@ -383,7 +419,194 @@ fn plan_creator_2(
                 field_search_cache,
             )
         }
+        SearchRequest::Phrase(part) => plan_creator_anchor_step(is_filter_channel, filter_channel_step, plan, parent_step_dependecy, depends_on_step, |channel| {
+            Box::new(PlanStepPhraseToAnchor { request: part.clone(), channel })
+        }),
+        SearchRequest::Proximity(part) => plan_creator_anchor_step(is_filter_channel, filter_channel_step, plan, parent_step_dependecy, depends_on_step, |channel| {
+            Box::new(PlanStepProximityToAnchor { request: part.clone(), channel })
+        }),
+        SearchRequest::Range(part) => plan_creator_anchor_step(is_filter_channel, filter_channel_step, plan, parent_step_dependecy, depends_on_step, |channel| {
+            Box::new(PlanStepRangeToAnchor { request: part.clone(), channel })
+        }),
+        SearchRequest::GeoDistance(part) => plan_creator_anchor_step(is_filter_channel, filter_channel_step, plan, parent_step_dependecy, depends_on_step, |channel| {
+            Box::new(PlanStepGeoDistanceToAnchor { request: part.clone(), channel })
+        }),
+    }
+}
+
+/// Plans the tree without its must_not subqueries as usual and removes the hits of the must_not subqueries from its result
+fn plan_creator_must_not(
+    is_filter: bool,
+    is_filter_channel: bool,
+    filter_channel_step: Option<usize>,
+    request_header: &Request,
+    request: &SearchRequest,
+    boost: Vec<RequestBoostPart>,
+    plan: &mut Plan,
+    parent_step_dependecy: Option<usize>,
+    depends_on_step: Option<usize>,
+    field_search_cache: &mut FieldRequestCache,
+) -> PlanStepId {
+    let mut channel = PlanStepDataChannels::default();
+    if is_filter_channel {
+        channel.filter_channel = Some(FilterChannel::default());
+    }
+    let step_id = plan.add_step(Box::new(Difference { channel }));
+
+    let mut request = request.clone();
+    let must_not = match &mut request {
+        SearchRequest::Or(tree) | SearchRequest::And(tree) => std::mem::take(&mut tree.must_not),
+        _ => unreachable!(),
+    };
+    let mut result_channels_from_prev_steps = vec![];
+    let positive_step_id = plan_creator_2(
+        is_filter,
+        false,
+        filter_channel_step,
+        request_header,
+        &request,
+        boost.clone(),
+        plan,
+        Some(step_id),
+        depends_on_step,
+        field_search_cache,
+    );
+    result_channels_from_prev_steps.push(plan.get_step_channel(positive_step_id).receiver_for_next_step.clone());
+    for x in &must_not {
+        let boost = merge_vec(&boost, &x.get_boost());
+        let step_id = plan_creator_2(
+            is_filter,
+            false,
+            filter_channel_step,
+            request_header,
+            x,
+            boost,
+            plan,
+            Some(step_id),
+            depends_on_step,
+            field_search_cache,
+        );
+        result_channels_from_prev_steps.push(plan.get_step_channel(step_id).receiver_for_next_step.clone());
+    }
+    plan.get_step_channel(step_id).input_prev_steps = result_channels_from_prev_steps;
+
+    if let Some(parent_step_dependecy) = parent_step_dependecy {
+        plan.add_dependency(parent_step_dependecy, step_id);
+    }
+    if let Some(depends_on_step) = depends_on_step {
+        plan.add_dependency(step_id, depends_on_step);
+    }
+    step_id
+}
+
+/// Plans the tree without its should subqueries as usual and adds the scores of the should subqueries to its hits.
+///
+/// Filters only need the ids, so the should subqueries are not planned for them.
+fn plan_creator_should(
+    is_filter: bool,
+    is_filter_channel: bool,
+    filter_channel_step: Option<usize>,
+    request_header: &Request,
+    request: &SearchRequest,
+    boost: Vec<RequestBoostPart>,
+    plan: &mut Plan,
+    parent_step_dependecy: Option<usize>,
+    depends_on_step: Option<usize>,
+    field_search_cache: &mut FieldRequestCache,
+) -> PlanStepId {
+    let mut request = request.clone();
+    let should = match &mut request {
+        SearchRequest::Or(tree) | SearchRequest::And(tree) => std::mem::take(&mut tree.should),
+        _ => unreachable!(),
+    };
+    if is_filter {
+        return plan_creator_2(
+            is_filter,
+            is_filter_channel,
+            filter_channel_step,
+            request_header,
+            &request,
+            boost,
+            plan,
+            parent_step_dependecy,
+            depends_on_step,
+            field_search_cache,
+        );
+    }
+
+    let mut channel = PlanStepDataChannels::default();
+    if is_filter_channel {
+        channel.filter_channel = Some(FilterChannel::default());
+    }
+    let step_id = plan.add_step(Box::new(AddOptionalScores { channel }));
+
+    let mut result_channels_from_prev_steps = vec![];
+    let required_step_id = plan_creator_2(
+        is_filter,
+        false,
+        filter_channel_step,
+        request_header,
+        &request,
+        boost.clone(),
+        plan,
+        Some(step_id),
+        depends_on_step,
+        field_search_cache,
+    );
+    result_channels_from_prev_steps.push(plan.get_step_channel(required_step_id).receiver_for_next_step.clone());
+    for x in &should {
+        let boost = merge_vec(&boost, &x.get_boost());
+        let step_id = plan_creator_2(
+            is_filter,
+            false,
+            filter_channel_step,
+            request_header,
+            x,
+            boost,
+            plan,
+            Some(step_id),
+            depends_on_step,
+            field_search_cache,
+        );
+        result_channels_from_prev_steps.push(plan.get_step_channel(step_id).receiver_for_next_step.clone());
+    }
+    plan.get_step_channel(step_id).input_prev_steps = result_channels_from_prev_steps;
+
+    if let Some(parent_step_dependecy) = parent_step_dependecy {
+        plan.add_dependency(parent_step_dependecy, step_id);
+    }
+    if let Some(depends_on_step) = depends_on_step {
+        plan.add_dependency(step_id, depends_on_step);
+    }
+    step_id
+}
+
+/// Adds a step, which emits anchors directly without a term search, like range or geo distance queries
+fn plan_creator_anchor_step(
+    is_filter_channel: bool,
+    filter_channel_step: Option<usize>,
+    plan: &mut Plan,
+    parent_step_dependecy: Option<usize>,
+    depends_on_step: Option<usize>,
+    create_step: impl FnOnce(PlanStepDataChannels) -> Box<dyn PlanStepTrait>,
+) -> PlanStepId {
+    let mut channel = PlanStepDataChannels::open_channel(1, vec![]);
+    if let Some(step_id) = filter_channel_step {
+        plan.get_step_channel(step_id).filter_channel.as_mut().unwrap().num_receivers += 1;
+        channel.filter_receiver = Some(plan.get_step_channel(step_id).filter_channel.as_mut().unwrap().filter_receiver.clone());
+    }
+    if is_filter_channel {
+        channel.filter_channel = Some(FilterChannel::default());
+    }
+    let step_id = plan.add_step(create_step(channel));
+
+    if let Some(parent_step_dependecy) = parent_step_dependecy {
+        plan.add_dependency(parent_step_dependecy, step_id);
+    }
+    if let Some(depends_on_step) = depends_on_step {
+        plan.add_dependency(step_id, depends_on_step);
     }
+    step_id
 }
 
 fn plan_creator_search_part(
@@ -59,6 +59,26 @@ pub(crate) struct PlanStepPhrasePairToAnchorId {
     pub(crate) channel: PlanStepDataChannels,
 }
 #[derive(Clone, Debug)]
+pub(crate) struct PlanStepRangeToAnchor {
+    pub(crate) request: RequestRangePart,
+    pub(crate) channel: PlanStepDataChannels,
+}
+#[derive(Clone, Debug)]
+pub(crate) struct PlanStepPhraseToAnchor {
+    pub(crate) request: RequestPhrasePart,
+    pub(crate) channel: PlanStepDataChannels,
+}
+#[derive(Clone, Debug)]
+pub(crate) struct PlanStepProximityToAnchor {
+    pub(crate) request: RequestProximityPart,
+    pub(crate) channel: PlanStepDataChannels,
+}
+#[derive(Clone, Debug)]
+pub(crate) struct PlanStepGeoDistanceToAnchor {
+    pub(crate) request: RequestGeoDistancePart,
+    pub(crate) channel: PlanStepDataChannels,
+}
+#[derive(Clone, Debug)]
 pub(crate) struct Union {
     pub(crate) ids_only: bool,
     pub(crate) channel: PlanStepDataChannels,
@@ -68,6 +88,16 @@ pub(crate) struct Intersect {
     pub(crate) ids_only: bool,
     pub(crate) channel: PlanStepDataChannels,
 }
+/// Removes the hits of the first input, which are in any of the other inputs
+#[derive(Clone, Debug)]
+pub(crate) struct Difference {
+    pub(crate) channel: PlanStepDataChannels,
+}
+/// Adds the scores of the other inputs to the hits of the first input
+#[derive(Clone, Debug)]
+pub(crate) struct AddOptionalScores {
+    pub(crate) channel: PlanStepDataChannels,
+}
 #[derive(Clone, Debug)]
 pub(crate) struct IntersectScoresWithIds {
     pub(crate) channel: PlanStepDataChannels,
@@ -115,6 +145,30 @@ impl std::fmt::Display for PlanStepPhrasePairToAnchorId {
         Ok(())
     }
 }
+impl std::fmt::Display for PlanStepRangeToAnchor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "range {}", self.request)?;
+        Ok(())
+    }
+}
+impl std::fmt::Display for PlanStepPhraseToAnchor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "phrase {}", self.request)?;
+        Ok(())
+    }
+}
+impl std::fmt::Display for PlanStepProximityToAnchor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "proximity {}", self.request)?;
+        Ok(())
+    }
+}
+impl std::fmt::Display for PlanStepGeoDistanceToAnchor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "geo distance {}", self.request)?;
+        Ok(())
+    }
+}
 impl std::fmt::Display for Union {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Union")?;
@@ -127,6 +181,18 @@ impl std::fmt::Display for Intersect {
         Ok(())
     }
 }
+impl std::fmt::Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Difference")?;
+        Ok(())
+    }
+}
+impl std::fmt::Display for AddOptionalScores {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "AddOptionalScores")?;
+        Ok(())
+    }
+}
 impl std::fmt::Display for IntersectScoresWithIds {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "IntersectScoresWithIds")?;
@@ -293,6 +359,78 @@ impl PlanStepTrait for PlanStepPhrasePairToAnchorId {
     }
 }
 
+impl PlanStepTrait for PlanStepPhraseToAnchor {
+    fn get_channel(&mut self) -> &mut PlanStepDataChannels {
+        &mut self.channel
+    }
+
+    fn execute_step(self: Box<Self>, persistence: &Persistence) -> Result<(), VelociError> {
+        let filter_res = if let Some(ref filter_receiver) = self.channel.filter_receiver {
+            Some(filter_receiver.recv().map_err(|_| VelociError::PlanExecutionRecvFailedFilter)?)
+        } else {
+            None
+        };
+        let field_result = phrase::get_anchors_for_phrase(persistence, &self.request, &filter_res)?;
+        send_result_to_channel(field_result, &self.channel)?;
+        drop_channel(self.channel);
+        Ok(())
+    }
+}
+
+impl PlanStepTrait for PlanStepProximityToAnchor {
+    fn get_channel(&mut self) -> &mut PlanStepDataChannels {
+        &mut self.channel
+    }
+
+    fn execute_step(self: Box<Self>, persistence: &Persistence) -> Result<(), VelociError> {
+        let filter_res = if let Some(ref filter_receiver) = self.channel.filter_receiver {
+            Some(filter_receiver.recv().map_err(|_| VelociError::PlanExecutionRecvFailedFilter)?)
+        } else {
+            None
+        };
+        let field_result = phrase::get_anchors_for_proximity(persistence, &self.request, &filter_res)?;
+        send_result_to_channel(field_result, &self.channel)?;
+        drop_channel(self.channel);
+        Ok(())
+    }
+}
+
+impl PlanStepTrait for PlanStepRangeToAnchor {
+    fn get_channel(&mut self) -> &mut PlanStepDataChannels {
+        &mut self.channel
+    }
+
+    fn execute_step(self: Box<Self>, persistence: &Persistence) -> Result<(), VelociError> {
+        let filter_res = if let Some(ref filter_receiver) = self.channel.filter_receiver {
+            Some(filter_receiver.recv().map_err(|_| VelociError::PlanExecutionRecvFailedFilter)?)
+        } else {
+            None
+        };
+        let field_result = range::get_anchors_in_range(persistence, &self.request, &filter_res)?;
+        send_result_to_channel(field_result, &self.channel)?;
+        drop_channel(self.channel);
+        Ok(())
+    }
+}
+
+impl PlanStepTrait for PlanStepGeoDistanceToAnchor {
+    fn get_channel(&mut self) -> &mut PlanStepDataChannels {
+        &mut self.channel
+    }
+
+    fn execute_step(self: Box<Self>, persistence: &Persistence) -> Result<(), VelociError> {
+        let filter_res = if let Some(ref filter_receiver) = self.channel.filter_receiver {
+            Some(filter_receiver.recv().map_err(|_| VelociError::PlanExecutionRecvFailedFilter)?)
+        } else {
+            None
+        };
+        let field_result = geo_distance::get_anchors_in_distance(persistence, &self.request, &filter_res)?;
+        send_result_to_channel(field_result, &self.channel)?;
+        drop_channel(self.channel);
+        Ok(())
+    }
+}
+
 impl PlanStepTrait for Union {
     fn get_channel(&mut self) -> &mut PlanStepDataChannels {
         &mut self.channel
@@ -327,6 +465,32 @@ impl PlanStepTrait for Intersect {
         Ok(())
     }
 }
+impl PlanStepTrait for Difference {
+    fn get_channel(&mut self) -> &mut PlanStepDataChannels {
+        &mut self.channel
+    }
+
+    fn execute_step(self: Box<Self>, _persistence: &Persistence) -> Result<(), VelociError> {
+        let res = self.channel.input_prev_steps[0].recv().map_err(|_| VelociError::PlanExecutionRecvFailed)?;
+        let excluded = get_data(&self.channel.input_prev_steps[1..])?;
+        send_result_to_channel(difference_hits(res, excluded), &self.channel)?;
+        drop(self.channel.sender_to_next_steps);
+        Ok(())
+    }
+}
+impl PlanStepTrait for AddOptionalScores {
+    fn get_channel(&mut self) -> &mut PlanStepDataChannels {
+        &mut self.channel
+    }
+
+    fn execute_step(self: Box<Self>, _persistence: &Persistence) -> Result<(), VelociError> {
+        let res = self.channel.input_prev_steps[0].recv().map_err(|_| VelociError::PlanExecutionRecvFailed)?;
+        let optional = get_data(&self.channel.input_prev_steps[1..])?;
+        send_result_to_channel(add_optional_hits_score(res, optional), &self.channel)?;
+        drop(self.channel.sender_to_next_steps);
+        Ok(())
+    }
+}
 impl PlanStepTrait for IntersectScoresWithIds {
     fn get_channel(&mut self) -> &mut PlanStepDataChannels {
         &mut self.channel
@@ -1,5 +1,5 @@
 mod query_parser_to_veloci_request;
-use crate::persistence::TEXTINDEX;
+use crate::persistence::{TEXTINDEX, TOKEN_TO_TEXT_ID_POSITIONS};
 use query_parser_to_veloci_request::*;
 use std::{
     collections::{HashMap, HashSet},
@@ -25,6 +25,27 @@ pub struct QueryParserOptions {
     /// This setting will disable defining a levensthtein distance after a searchterm
     /// e.g. "searchterm~2"
     pub no_levensthein: bool,
+    /// This setting will disable parsing of negations
+    /// e.g. "NOT searchterm", "-searchterm" or "myfield:-searchterm"
+    pub no_negation: bool,
+    /// This setting will disable parsing of required clauses
+    /// e.g. "+searchterm" or "myfield:+searchterm"
+    pub no_required: bool,
+    /// This setting will disable parsing of proximity queries, a distance after quotes is then a levenshtein distance
+    /// e.g. "apple NEAR/5 pie" or "\"search engine\"~3"
+    pub no_proximity: bool,
+    /// This setting will disable parsing of wildcards, `*` and `?` are then part of the searchterm
+    /// e.g. "haus*" or "te?m"
+    pub no_wildcard: bool,
+    /// This setting will disable boosts, `^` is then part of the searchterm
+    /// e.g. "searchterm^2" or "(nice cool)^0.5"
+    pub no_boost: bool,
+    /// This setting will disable parsing of ranges and comparisons on attributes, they are then searchterms
+    /// e.g. "year:[2000 TO 2010]" or "price:<20"
+    pub no_range: bool,
+    /// Unbalanced parentheses and quotes are part of the searchterm instead of an error.
+    /// Queries which are still invalid are searched as OR connected words, e.g. "fancy AND" or "()".
+    pub lenient: bool,
     // pub no_quotes: bool
 }
 
@@ -34,6 +55,13 @@ impl From<QueryParserOptions> for query_parser::Options {
             no_attributes: options.no_attributes,
             no_parentheses: options.no_parentheses,
             no_levensthein: options.no_levensthein,
+            no_negation: options.no_negation,
+            no_required: options.no_required,
+            no_proximity: options.no_proximity,
+            no_wildcard: options.no_wildcard,
+            no_boost: options.no_boost,
+            no_range: options.no_range,
+            lenient: options.lenient,
         }
     }
 }
@@ -98,6 +126,19 @@ fn get_default_levenshtein(term: &str, levenshtein_auto_limit: usize, wildcard:
     }
 }
 
+/// get all fields with a positional index in any segment, quoted terms are exact phrase queries on them
+fn get_phrase_field_names(persistence: &Persistence, fields: &[String]) -> Vec<String> {
+    fields
+        .iter()
+        .filter(|path| {
+            persistence
+                .get_segments()
+                .any(|(_, segment)| segment.has_index(&path.add(TEXTINDEX).add(TOKEN_TO_TEXT_ID_POSITIONS)))
+        })
+        .cloned()
+        .collect()
+}
+
 /// get all fields, while applying the fields whitelist if applicable
 fn get_all_search_field_names(persistence: &Persistence, fields: &Option<Vec<String>>) -> Result<Vec<String>, VelociError> {
     let res: Vec<_> = persistence
@@ -108,6 +149,10 @@ fn get_all_search_field_names(persistence: &Persistence, fields: &Option<Vec<Str
             if let Some(ref filter) = *fields {
                 return filter.contains(path);
             }
+            if persistence.metadata.columns.get(path).map(|col| col.is_path_prefixes).unwrap_or(false) {
+                // the path prefixes duplicate the texts of the hierarchy field
+                return false;
+            }
             let path: String = path.add(TEXTINDEX);
             if !persistence.has_token_to_anchor(path) {
                 // Index creation for fields may be disabled and therefore be unavailable
@@ -178,13 +223,14 @@ pub fn search_query(persistence: &Persistence, mut opt: SearchQueryGeneratorPara
 
     let all_fields = persistence.metadata.get_all_fields();
     let all_search_fields = get_all_search_field_names(persistence, &opt.fields)?; // all fields with applied field_filter
+    let phrase_fields = get_phrase_field_names(persistence, &all_fields);
 
     let parser_options: QueryParserOptions = opt.parser_options.unwrap_or_default();
-    let query_ast = query_parser::parse_with_opt(&opt.search_term, parser_options.into()).unwrap();
+    let query_ast = query_parser::parse_with_opt(&opt.search_term, parser_options.into())?;
 
     let mut request = Request::default();
 
-    request.search_req = Some(ast_to_search_request(&query_ast, &all_search_fields, &opt)?);
+    request.search_req = Some(ast_to_search_request(&query_ast, &all_search_fields, &phrase_fields, &opt)?);
     if let Some(el) = request.search_req.as_mut() {
         el.simplify()
     }
@@ -196,7 +242,11 @@ pub fn search_query(persistence: &Persistence, mut opt: SearchQueryGeneratorPara
             .into_iter()
             .map(|field| {
                 check_field(&field, &all_fields)?;
-                Ok(FacetRequest { field, top: facetlimit })
+                Ok(FacetRequest {
+                    field,
+                    top: facetlimit,
+                    ..Default::default()
+                })
             })
             .collect::<Result<Vec<FacetRequest>, VelociError>>()
     });
@@ -227,8 +277,8 @@ pub fn search_query(persistence: &Persistence, mut opt: SearchQueryGeneratorPara
     if let Some(filters) = opt.filter.as_ref() {
         let mut params = SearchQueryGeneratorParameters::default();
         params.levenshtein = Some(0);
-        let query_ast = query_parser::parse_with_opt(filters, opt.filter_parser_options.unwrap_or_default().into()).unwrap();
-        let mut filter_request_ast = ast_to_search_request(&query_ast, &all_fields, &params)?;
+        let query_ast = query_parser::parse_with_opt(filters, opt.filter_parser_options.unwrap_or_default().into())?;
+        let mut filter_request_ast = ast_to_search_request(&query_ast, &all_fields, &phrase_fields, &params)?;
         filter_request_ast.simplify();
         request.filter = Some(Box::new(filter_request_ast));
     }
@@ -1,6 +1,6 @@
 use crate::{
     query_generator::*,
-    search::request::search_request::{SearchRequest, SearchTree},
+    search::request::search_request::{RangeValue, RequestPhrasePart, RequestProximityPart, RequestRangePart, SearchRequest, SearchTree},
 };
 
 use crate::error::VelociError;
@@ -8,10 +8,16 @@ use query_parser::{
     self,
     ast::{Operator, UserAST},
 };
-pub(crate) fn ast_to_search_request(query_ast: &UserAST, all_fields: &[String], opt: &SearchQueryGeneratorParameters) -> Result<SearchRequest, VelociError> {
+/// `phrase_fields` are the fields with a positional index, quoted terms on them are exact phrase queries.
+pub(crate) fn ast_to_search_request(
+    query_ast: &UserAST,
+    all_fields: &[String],
+    phrase_fields: &[String],
+    opt: &SearchQueryGeneratorParameters,
+) -> Result<SearchRequest, VelociError> {
     filter_stopwords(query_ast, opt);
     let query_ast = expand_fields_in_query_ast(query_ast, all_fields)?;
-    Ok(query_ast_to_request(&query_ast, opt, None))
+    Ok(query_ast_to_request(&query_ast, opt, phrase_fields, None))
 }
 
 /// Converts the SearchQueryGeneratorParameters into an SearchRequest ast
@@ -20,42 +26,97 @@ pub(crate) fn ast_to_search_request(query_ast: &UserAST, all_fields: &[String],
 /// foo* will match all tokens starting with foo
 /// foo*bar will match all tokens starting with foo and ending with bar
 /// *foo* will match all tokens containing foo
-fn query_ast_to_request(ast: &UserAST, opt: &SearchQueryGeneratorParameters, field_name: Option<&str>) -> SearchRequest {
+///
+/// Negations are collected into `must_not` of the enclosing And/Or, e.g. "a -b c" removes the hits of b from a OR c.
+/// A query with only negations matches nothing.
+///
+/// Required clauses, e.g. "+a b -c", gate the matches. The other clauses connected with OR are then optional and only add to the score.
+///
+/// Quoted terms without levenshtein distance and wildcard, e.g. "red apple", are exact phrase queries on fields in `phrase_fields`.
+/// Proximities, e.g. "apple NEAR/5 pie", are proximity queries on these fields. On other fields all terms are required,
+/// a quoted term with distance is searched with the distance as levenshtein distance.
+///
+/// Ranges, e.g. "year:[2000 TO 2010]" or "price:<20", are range queries, the bounds are parsed according to the field.
+///
+/// Boosts, e.g. "term^2" or "(a b)^0.5", are multiplied onto the boost of all queries in the subtree.
+fn query_ast_to_request(ast: &UserAST, opt: &SearchQueryGeneratorParameters, phrase_fields: &[String], field_name: Option<&str>) -> SearchRequest {
     match ast {
-        UserAST::BinaryClause(ast1, op, ast2) => {
-            let queries = [ast1, ast2].iter().map(|ast| query_ast_to_request(ast, opt, field_name)).collect();
-            match op {
-                Operator::And => SearchRequest::And(SearchTree {
-                    queries,
-                    options: Default::default(),
-                }),
-                Operator::Or => SearchRequest::Or(SearchTree {
-                    queries,
-                    options: Default::default(),
-                }),
+        UserAST::BinaryClause(_ast1, op, _ast2) => clauses_to_request(ast, *op, opt, phrase_fields, field_name),
+        UserAST::Not(_) | UserAST::Required(_) => clauses_to_request(ast, Operator::Or, opt, phrase_fields, field_name),
+        UserAST::Attributed(attr, ast) => query_ast_to_request(ast, opt, phrase_fields, Some(attr)),
+        UserAST::Range(range) => {
+            let field_name: &str = field_name.as_ref().unwrap();
+            let bound = |value: &Option<String>, inclusive: bool| value.as_ref().filter(|_| inclusive).map(|value| RangeValue::Text(value.to_string()));
+            SearchRequest::Range(RequestRangePart {
+                path: field_name.to_string(),
+                gt: bound(&range.lower, !range.lower_inclusive),
+                gte: bound(&range.lower, range.lower_inclusive),
+                lt: bound(&range.upper, !range.upper_inclusive),
+                lte: bound(&range.upper, range.upper_inclusive),
+                boost: opt.boost_fields.as_ref().and_then(|boost| boost.get(field_name).map(|el| OrderedFloat(*el))),
+                ..Default::default()
+            })
+        }
+        UserAST::Boosted(ast, boost) => {
+            let mut request = query_ast_to_request(ast, opt, phrase_fields, field_name);
+            apply_boost(&mut request, *boost);
+            request
+        }
+        UserAST::Near(filters, distance) => {
+            let field_name: &str = field_name.as_ref().unwrap();
+            if phrase_fields.iter().any(|phrase_field| phrase_field == field_name) {
+                return SearchRequest::Proximity(RequestProximityPart {
+                    path: field_name.to_string(),
+                    terms: filters.iter().map(|filter| filter.phrase.to_string()).collect(),
+                    distance: *distance,
+                    ignore_case: opt.ignore_case,
+                    boost: opt.boost_fields.as_ref().and_then(|boost| boost.get(field_name).map(|el| OrderedFloat(*el))),
+                    ..Default::default()
+                });
+            }
+            if let [filter] = filters.as_slice() {
+                let mut filter = filter.clone();
+                filter.levenshtein = Some((*distance).min(u32::from(u8::MAX)) as u8);
+                return query_ast_to_request(&UserAST::Leaf(Box::new(filter)), opt, phrase_fields, Some(field_name));
             }
+            let queries = filters
+                .iter()
+                .map(|filter| query_ast_to_request(&UserAST::Leaf(Box::new(filter.clone())), opt, phrase_fields, Some(field_name)))
+                .collect();
+            SearchRequest::And(SearchTree { queries, ..Default::default() })
         }
-        UserAST::Attributed(attr, ast) => query_ast_to_request(ast, opt, Some(attr)),
         UserAST::Leaf(filter) => {
             let field_name: &str = field_name.as_ref().unwrap();
+            let boost = opt.boost_fields.as_ref().and_then(|boost| boost.get(field_name).map(|el| OrderedFloat(*el)));
+            let is_phrase = filter.quoted && filter.levenshtein.is_none() && !filter.phrase.contains('*');
+            if is_phrase && phrase_fields.iter().any(|phrase_field| phrase_field == field_name) {
+                return SearchRequest::Phrase(RequestPhrasePart {
+                    path: field_name.to_string(),
+                    phrase: filter.phrase.to_string(),
+                    ignore_case: opt.ignore_case,
+                    boost,
+                    ..Default::default()
+                });
+            }
             let mut term = filter.phrase.to_string();
 
             let mut levenshtein_distance = None;
+            // stars in quotes are still patterns, e.g. "*myfun(param1: Type1)*", but `?` is part of the term
+            let is_pattern = filter.wildcard || (filter.quoted && term.contains('*'));
             // One star at the end means it's a starts_with query, which can be combined with
             // levensthein
-            let starts_with = term.ends_with('*') && term.chars().filter(|&c| c == '*').count() == 1;
+            let starts_with = is_pattern && term.ends_with('*') && term.matches('*').count() == 1 && (filter.quoted || !term.contains('?'));
             if starts_with {
                 term.pop();
             }
 
-            // regex is currently enabled, when there is a star, except if there is only one star at the the end, e.g. fooba*
-            // Then it uses a combination of fuzzy + starts_with
-            // This enables fuzzy search with patterns, currently there is no fuzzy_search for regex
-            let is_regex = term.contains('*');
+            // Other wildcards are compiled to an automaton on the fst, currently there is no fuzzy_search for wildcards
+            let is_wildcard = filter.wildcard && term.contains(['*', '?']);
+            let is_regex = !filter.wildcard && filter.quoted && term.contains('*');
             if is_regex {
                 use itertools::Itertools;
                 term = term.split('*').map(regex::escape).join(".*");
-            } else {
+            } else if !is_wildcard {
                 levenshtein_distance = if let Some(levenshtein) = filter.levenshtein {
                     Some(u32::from(levenshtein))
                 } else {
@@ -64,12 +125,13 @@ fn query_ast_to_request(ast: &UserAST, opt: &SearchQueryGeneratorParameters, fie
             }
 
             let part = RequestSearchPart {
-                boost: opt.boost_fields.as_ref().and_then(|boost| boost.get(field_name).map(|el| OrderedFloat(*el))),
+                boost,
                 levenshtein_distance,
                 path: field_name.to_string(),
                 terms: vec![term],
                 starts_with,
                 is_regex,
+                is_wildcard,
                 ignore_case: opt.ignore_case,
                 ..Default::default()
             };
@@ -78,6 +140,69 @@ fn query_ast_to_request(ast: &UserAST, opt: &SearchQueryGeneratorParameters, fie
     }
 }
 
+/// Multiplies the boost onto the boost of all queries in the tree, negated queries don't contribute to the score
+fn apply_boost(request: &mut SearchRequest, boost: f32) {
+    let boost_val = match request {
+        SearchRequest::Or(tree) | SearchRequest::And(tree) => {
+            for query in tree.queries.iter_mut().chain(tree.should.iter_mut()) {
+                apply_boost(query, boost);
+            }
+            return;
+        }
+        SearchRequest::Search(part) => &mut part.boost,
+        SearchRequest::Phrase(part) => &mut part.boost,
+        SearchRequest::Proximity(part) => &mut part.boost,
+        SearchRequest::Range(part) => &mut part.boost,
+        SearchRequest::GeoDistance(part) => &mut part.boost,
+    };
+    *boost_val = Some(OrderedFloat(boost_val.map(|boost_val| boost_val.into_inner()).unwrap_or(1.0) * boost));
+}
+
+/// Converts the clauses connected with the same operator to an And/Or.
+///
+/// With required clauses the request is an And over the required clauses, the other clauses of an OR move to `should`.
+fn clauses_to_request(ast: &UserAST, op: Operator, opt: &SearchQueryGeneratorParameters, phrase_fields: &[String], field_name: Option<&str>) -> SearchRequest {
+    let mut tree = SearchTree::default();
+    let mut required = vec![];
+    collect_clauses(ast, op, opt, phrase_fields, field_name, &mut tree, &mut required);
+    match op {
+        Operator::And => {
+            tree.queries.extend(required);
+            SearchRequest::And(tree)
+        }
+        Operator::Or if required.is_empty() => SearchRequest::Or(tree),
+        Operator::Or => SearchRequest::And(SearchTree {
+            should: std::mem::replace(&mut tree.queries, required),
+            ..tree
+        }),
+    }
+}
+
+/// Collects the clauses connected with the same operator into `queries`, the negated ones into `must_not` and the required ones into `required`
+fn collect_clauses(
+    ast: &UserAST,
+    op: Operator,
+    opt: &SearchQueryGeneratorParameters,
+    phrase_fields: &[String],
+    field_name: Option<&str>,
+    tree: &mut SearchTree,
+    required: &mut Vec<SearchRequest>,
+) {
+    match ast {
+        UserAST::BinaryClause(ast1, sub_op, ast2) if *sub_op == op => {
+            collect_clauses(ast1, op, opt, phrase_fields, field_name, tree, required);
+            collect_clauses(ast2, op, opt, phrase_fields, field_name, tree, required);
+        }
+        UserAST::Not(ast) => tree.must_not.push(query_ast_to_request(ast, opt, phrase_fields, field_name)),
+        UserAST::Required(ast) => required.push(query_ast_to_request(ast, opt, phrase_fields, field_name)),
+        // field:-value or field:+value
+        UserAST::Attributed(attr, modified) if matches!(**modified, UserAST::Not(_) | UserAST::Required(_)) => {
+            collect_clauses(modified, op, opt, phrase_fields, Some(attr), tree, required)
+        }
+        _ => tree.queries.push(query_ast_to_request(ast, opt, phrase_fields, field_name)),
+    }
+}
+
 fn expand_fields_in_query_ast(ast: &UserAST, all_fields: &[String]) -> Result<UserAST, VelociError> {
     match ast {
         UserAST::BinaryClause(ast1, op, ast2) => Ok(UserAST::BinaryClause(
@@ -85,7 +210,10 @@ fn expand_fields_in_query_ast(ast: &UserAST, all_fields: &[String]) -> Result<Us
             *op,
             expand_fields_in_query_ast(ast2, all_fields)?.into(),
         )),
-        UserAST::Leaf(_) => {
+        UserAST::Not(ast) => Ok(UserAST::Not(expand_fields_in_query_ast(ast, all_fields)?.into())),
+        UserAST::Required(ast) => Ok(UserAST::Required(expand_fields_in_query_ast(ast, all_fields)?.into())),
+        UserAST::Boosted(ast, boost) => Ok(UserAST::Boosted(expand_fields_in_query_ast(ast, all_fields)?.into(), *boost)),
+        UserAST::Near(..) | UserAST::Leaf(_) | UserAST::Range(_) => {
             let mut field_iter = all_fields.iter();
             let mut curr_ast = field_iter
                 .next()
@@ -150,7 +278,7 @@ mod bench {
         ];
         b.iter(|| {
             let query_ast = query_parser::parse("die drei fragezeigen und das unicorn").unwrap();
-            ast_to_search_request(&query_ast, &fields, &SearchQueryGeneratorParameters::default()).unwrap()
+            ast_to_search_request(&query_ast, &fields, &[], &SearchQueryGeneratorParameters::default()).unwrap()
         })
     }
 
@@ -206,6 +334,8 @@ fn test_field_expand() {
     let ast = UserAST::Leaf(Box::new(UserFilter {
         phrase: "Fred".to_string(),
         levenshtein: None,
+        quoted: false,
+        wildcard: false,
     }));
     let expanded_ast = expand_fields_in_query_ast(&ast, &fields).unwrap();
     assert_eq!(format!("{:?}", expanded_ast), "(Author[].name:\"Fred\" OR Title:\"Fred\")");
@@ -215,9 +345,170 @@ fn test_field_expand() {
         UserAST::Leaf(Box::new(UserFilter {
             phrase: "Fred".to_string(),
             levenshtein: None,
+            quoted: false,
+            wildcard: false,
         }))
         .into(),
     );
     let expanded_ast = expand_fields_in_query_ast(&ast, &fields).unwrap();
     assert_eq!(format!("{:?}", expanded_ast), "Title:\"Fred\"");
 }
+
+#[test]
+fn test_negation_to_must_not() {
+    let fields = vec!["Title".to_string(), "Author".to_string()];
+    let query_ast = query_parser::parse("fred -john wilma").unwrap();
+    match ast_to_search_request(&query_ast, &fields, &[], &SearchQueryGeneratorParameters::default()).unwrap() {
+        SearchRequest::Or(tree) => {
+            assert_eq!(tree.queries.len(), 4);
+            assert_eq!(tree.must_not.len(), 1);
+        }
+        _ => panic!("expected or"),
+    }
+
+    let query_ast = query_parser::parse("fred AND Title:-john").unwrap();
+    match ast_to_search_request(&query_ast, &fields, &[], &SearchQueryGeneratorParameters::default()).unwrap() {
+        SearchRequest::And(tree) => {
+            assert_eq!(tree.queries.len(), 1);
+            assert_eq!(tree.must_not.len(), 1);
+            assert_eq!(tree.must_not[0].as_request_search_part().path, "Title");
+            assert_eq!(tree.must_not[0].as_request_search_part().terms, vec!["john".to_string()]);
+        }
+        _ => panic!("expected and"),
+    }
+}
+
+#[test]
+fn test_required_to_should() {
+    let fields = vec!["Title".to_string(), "Author".to_string()];
+    let query_ast = query_parser::parse("+Title:fred wilma -john").unwrap();
+    match ast_to_search_request(&query_ast, &fields, &[], &SearchQueryGeneratorParameters::default()).unwrap() {
+        SearchRequest::And(tree) => {
+            assert_eq!(tree.queries.len(), 1);
+            assert_eq!(tree.queries[0].as_request_search_part().path, "Title");
+            assert_eq!(tree.should.len(), 2);
+            assert_eq!(tree.must_not.len(), 1);
+        }
+        _ => panic!("expected and"),
+    }
+
+    let query_ast = query_parser::parse("fred AND Title:+john").unwrap();
+    match ast_to_search_request(&query_ast, &fields, &[], &SearchQueryGeneratorParameters::default()).unwrap() {
+        SearchRequest::And(tree) => {
+            assert_eq!(tree.queries.len(), 2);
+            assert!(tree.should.is_empty());
+        }
+        _ => panic!("expected and"),
+    }
+}
+
+#[test]
+fn test_near_to_proximity() {
+    let fields = vec!["Title".to_string(), "Author".to_string()];
+    let phrase_fields = vec!["Title".to_string()];
+    let query_ast = query_parser::parse("fred NEAR/3 wilma").unwrap();
+    match ast_to_search_request(&query_ast, &fields, &phrase_fields, &SearchQueryGeneratorParameters::default()).unwrap() {
+        SearchRequest::Or(tree) => {
+            assert!(matches!(&tree.queries[0], SearchRequest::And(tree) if tree.queries.len() == 2));
+            assert!(matches!(&tree.queries[1], SearchRequest::Proximity(part) if part.terms == vec!["fred", "wilma"] && part.distance == 3));
+        }
+        _ => panic!("expected or"),
+    }
+
+    let query_ast = query_parser::parse("Author:\"fred feuerstein\"~1").unwrap();
+    match ast_to_search_request(&query_ast, &fields, &phrase_fields, &SearchQueryGeneratorParameters::default()).unwrap() {
+        SearchRequest::Search(part) => assert_eq!(part.levenshtein_distance, Some(1)),
+        _ => panic!("expected search"),
+    }
+}
+
+#[test]
+fn test_quoted_to_phrase() {
+    let fields = vec!["Title".to_string(), "Author".to_string()];
+    let phrase_fields = vec!["Title".to_string()];
+    let query_ast = query_parser::parse("\"fred feuerstein\"").unwrap();
+    match ast_to_search_request(&query_ast, &fields, &phrase_fields, &SearchQueryGeneratorParameters::default()).unwrap() {
+        SearchRequest::Or(tree) => {
+            assert!(matches!(&tree.queries[0], SearchRequest::Search(part) if part.path == "Author"));
+            assert!(matches!(&tree.queries[1], SearchRequest::Phrase(part) if part.path == "Title" && part.phrase == "fred feuerstein"));
+        }
+        _ => panic!("expected or"),
+    }
+
+    // a distance after quotes is a proximity
+    let query_ast = query_parser::parse("Title:\"fred feuerstein\"~1").unwrap();
+    assert!(matches!(
+        ast_to_search_request(&query_ast, &fields, &phrase_fields, &SearchQueryGeneratorParameters::default()).unwrap(),
+        SearchRequest::Proximity(_)
+    ));
+}
+
+#[test]
+fn test_wildcard_to_search_part() {
+    let fields = vec!["Title".to_string()];
+    let to_search_part = |query: &str| match ast_to_search_request(&query_parser::parse(query).unwrap(), &fields, &[], &SearchQueryGeneratorParameters::default()).unwrap() {
+        SearchRequest::Search(part) => part,
+        _ => panic!("expected search"),
+    };
+
+    let part = to_search_part("fred*");
+    assert!(part.starts_with && !part.is_wildcard && part.levenshtein_distance.is_some());
+    assert_eq!(part.terms, vec!["fred"]);
+
+    let part = to_search_part("fr?d*");
+    assert!(!part.starts_with && part.is_wildcard && part.levenshtein_distance.is_none());
+    assert_eq!(part.terms, vec!["fr?d*"]);
+
+    let part = to_search_part("*fred");
+    assert!(part.is_wildcard && !part.is_regex);
+
+    // in quotes `?` is part of the term
+    let part = to_search_part("\"*fr?d*\"");
+    assert!(part.is_regex && !part.is_wildcard);
+    assert_eq!(part.terms, vec![".*fr\\?d.*"]);
+}
+
+#[test]
+fn test_boost_to_search_request() {
+    let fields = vec!["Title".to_string(), "Author".to_string()];
+    let mut opt = SearchQueryGeneratorParameters::default();
+    opt.boost_fields = Some([("Title".to_string(), 2.0)].into_iter().collect());
+    let query_ast = query_parser::parse("(wilma Author:barney^2)^0.5 Title:fred^3").unwrap();
+    let boosts = |request: &SearchRequest| match request {
+        SearchRequest::Or(tree) => tree
+            .queries
+            .iter()
+            .map(|query| {
+                (
+                    query.as_request_search_part().path.to_string(),
+                    query.as_request_search_part().boost.map(|boost| boost.into_inner()),
+                )
+            })
+            .collect::<Vec<_>>(),
+        _ => panic!("expected or"),
+    };
+    let mut request = ast_to_search_request(&query_ast, &fields, &[], &opt).unwrap();
+    request.simplify();
+    assert_eq!(
+        boosts(&request),
+        vec![
+            ("Title".to_string(), Some(6.0)),
+            ("Author".to_string(), Some(0.5)),
+            ("Title".to_string(), Some(1.0)),
+            ("Author".to_string(), Some(1.0)),
+        ]
+    );
+}
+
+#[test]
+fn test_range_to_range_request() {
+    let fields = vec!["year".to_string()];
+    let to_range = |query: &str| match ast_to_search_request(&query_parser::parse(query).unwrap(), &fields, &[], &SearchQueryGeneratorParameters::default()).unwrap() {
+        SearchRequest::Range(part) => part.to_string(),
+        _ => panic!("expected range"),
+    };
+    assert_eq!(to_range("year:[2000 TO 2010]"), "year: >=2000 <=2010");
+    assert_eq!(to_range("year:{2000 TO *]"), "year: >2000");
+    assert_eq!(to_range("year:<2010"), "year: <2010");
+    assert_eq!(to_range("year:>=2010"), "year: >=2010");
+}
@@ -1,4 +1,9 @@
 pub(crate) mod boost;
+pub(crate) mod collapse;
+pub mod cursor;
+pub mod geo_distance;
+pub mod phrase;
+pub mod range;
 pub mod read_document;
 pub mod request;
 pub mod result;
@@ -9,10 +14,16 @@ pub mod stopwords;
 pub mod why_found;
 
 pub(crate) use self::boost::*;
+use self::{
+    collapse::{check_collapse_field, collapse_hits, merge_collapse_groups},
+    cursor::Cursor,
+    sort::{check_cursor, check_sort_fields, create_cursor, sort_by_fields, top_n_sort, SearchAfter},
+    why_found::get_why_found,
+};
 pub use self::{result::*, search_field::*, set_op::*};
-use self::{sort::top_n_sort, why_found::get_why_found};
 pub use crate::search::{read_document::read_data, read_document::read_tree, request::*};
 use crate::{
+    aggregation,
     error::VelociError,
     expression::ScoreExpression,
     facet,
@@ -75,28 +86,48 @@ pub fn to_documents(persistence: &Persistence, hits: &[Hit], select: &Option<Vec
             .collect()
     };
 
-    let doc_store_data = persistence.get_file_bytes("data").expect("could not open document store");
-    let doc_store = DocLoader::open(doc_store_data.as_ref());
+    let doc_store_data: Vec<_> = persistence
+        .get_segments()
+        .map(|(_, segment)| segment.get_file_bytes("data").expect("could not open document store"))
+        .collect();
+    let doc_stores: Vec<_> = doc_store_data.iter().map(|data| DocLoader::open(data.as_ref())).collect();
+    let to_document = |hit: &Hit| {
+        let (segment_index, segment_id) = persistence.get_segment_index_for_id(hit.id);
+        let segment = persistence.get_segment_for_id(hit.id).0;
+        if let Some(ref select) = select {
+            DocWithHit {
+                doc: read_data(segment, segment_id, select).unwrap(), // TODO validate fields
+                hit: hit.clone(),
+                explain: result.explain.get(&hit.id).cloned(),
+                why_found: result.why_found_info.get(&hit.id).cloned().unwrap_or_default(),
+                collapse: None,
+            }
+        } else {
+            let doc_str = doc_stores[segment_index].get_doc(segment_id).unwrap(); // TODO No unwrapo
+            let ayse = highlight_on_original_document(segment, &doc_str, &tokens_set);
+
+            DocWithHit {
+                doc: serde_json::from_str(&doc_str).unwrap(),
+                hit: hit.clone(),
+                explain: result.explain.get(&hit.id).cloned(),
+                why_found: ayse,
+                collapse: None,
+            }
+        }
+    };
     hits.iter()
+        .filter(|hit| {
+            let (segment, segment_id) = persistence.get_segment_for_id(hit.id);
+            !segment.is_deleted(segment_id)
+        })
         .map(|hit| {
-            if let Some(ref select) = select {
-                DocWithHit {
-                    doc: read_data(persistence, hit.id, select).unwrap(), // TODO validate fields
-                    hit: hit.clone(),
-                    explain: result.explain.get(&hit.id).cloned(),
-                    why_found: result.why_found_info.get(&hit.id).cloned().unwrap_or_default(),
-                }
-            } else {
-                let doc_str = doc_store.get_doc(hit.id).unwrap(); // TODO No unwrapo
-                let ayse = highlight_on_original_document(persistence, &doc_str, &tokens_set);
-
-                DocWithHit {
-                    doc: serde_json::from_str(&doc_str).unwrap(),
-                    hit: hit.clone(),
-                    explain: result.explain.get(&hit.id).cloned(),
-                    why_found: ayse,
-                }
-            }
+            let mut doc = to_document(hit);
+            doc.collapse = result.collapse.get(&hit.id).map(|group| CollapseGroupWithDoc {
+                value: group.value.clone(),
+                count: group.count,
+                hits: group.hits.iter().map(to_document).collect(),
+            });
+            doc
         })
         .collect::<Vec<_>>()
 }
@@ -106,7 +137,10 @@ pub fn to_search_result(persistence: &Persistence, hits: SearchResult, select: &
         data: to_documents(persistence, &hits.data, select, &hits),
         num_hits: hits.num_hits,
         facets: hits.facets,
+        stats: hits.stats,
+        aggregations: hits.aggregations,
         execution_time_ns: hits.execution_time_ns,
+        cursor: hits.cursor,
     }
 }
 
@@ -140,35 +174,231 @@ pub fn explain_plan(mut request: Request, _persistence: &Persistence) -> Result<
     Ok(String::from_utf8(dot_graph)?)
 }
 
-pub fn search(mut request: Request, persistence: &Persistence) -> Result<SearchResult, VelociError> {
-    let start_time = std::time::Instant::now();
-    info_time!("search");
-    request.top = request.top.or(Some(10));
+/// Executes the search request on all segments of the persistence.
+pub fn search(request: Request, persistence: &Persistence) -> Result<SearchResult, VelociError> {
+    if let Some(sort) = request.sort.as_ref() {
+        check_sort_fields(persistence, sort)?;
+    }
+    check_exclude_filters(&request)?;
+    let filters = request.filter.iter().chain(request.post_filter.iter()).map(|filter| filter.as_ref());
+    let named_filters = request.named_filters.iter().flatten().map(|named_filter| &named_filter.filter);
+    for search_req in request.search_req.iter().chain(filters).chain(named_filters) {
+        check_search_fields(persistence, search_req)?;
+    }
+    if let Some(collapse) = request.collapse.as_ref() {
+        check_collapse_field(persistence, collapse)?;
+    }
+    let cursor = request.search_after.as_deref().map(Cursor::decode).transpose()?;
+    if let Some(cursor) = cursor.as_ref() {
+        check_cursor(cursor, request.sort.as_deref())?;
+    }
+    let sort = request.sort.clone();
+    let mut search_result = if persistence.segments.is_empty() {
+        let search_after = cursor.as_ref().map(|cursor| SearchAfter { cursor, offset: 0 });
+        search_segment(request, persistence, search_after)?
+    } else {
+        search_segments(request, persistence, cursor.as_ref())?
+    };
+    search_result.cursor = search_result.data.last().map(|hit| create_cursor(persistence, hit, sort.as_deref()).encode());
+    Ok(search_result)
+}
 
-    let mut res = {
-        info_time!("search terms");
-        let mut plan = Plan::default();
-        if request.search_req.is_none() {
+/// Checks that facets only exclude existing named filters
+fn check_exclude_filters(request: &Request) -> Result<(), VelociError> {
+    let named_filters = request.named_filters.as_deref().unwrap_or_default();
+    for facet_req in request.facets.iter().flatten() {
+        if let Some(name) = facet_req
+            .exclude_filters
+            .iter()
+            .find(|name| !named_filters.iter().any(|named_filter| &&named_filter.name == name))
+        {
             return Err(VelociError::InvalidRequest {
-                message: format!("search_req is None, but is required in search, request: {:?}", request),
+                message: format!("facet on {:?} excludes the unknown filter {:?}", facet_req.field, name),
             });
         }
-        plan_creator(request.clone(), &mut plan);
+    }
+    Ok(())
+}
+
+/// Checks that the searched fields exist and phrases have a positional index in at least one segment, the other segments have no hits for it
+fn check_search_fields(persistence: &Persistence, search_req: &SearchRequest) -> Result<(), VelociError> {
+    match search_req {
+        SearchRequest::Or(subtree) | SearchRequest::And(subtree) => {
+            for sub_query in subtree.queries.iter().chain(subtree.must_not.iter()).chain(subtree.should.iter()) {
+                check_search_fields(persistence, sub_query)?;
+            }
+        }
+        SearchRequest::Search(part) => {
+            let path = if part.path.ends_with(TEXTINDEX) {
+                part.path.to_string()
+            } else {
+                part.path.add(TEXTINDEX)
+            };
+            if !persistence.get_segments().any(|(_, segment)| segment.indices.fst.contains_key(&path)) {
+                return Err(VelociError::FstNotFound(path));
+            }
+        }
+        SearchRequest::Phrase(part) => phrase::check_phrase_request(persistence, part)?,
+        SearchRequest::Proximity(part) => phrase::check_proximity_request(persistence, part)?,
+        SearchRequest::Range(_) | SearchRequest::GeoDistance(_) => {}
+    }
+    Ok(())
+}
 
-        if log_enabled!(log::Level::Debug) {
-            let mut dot_graph = vec![];
-            render_plan_to(&plan, &mut dot_graph);
-            debug!("{}", String::from_utf8(dot_graph)?);
+fn search_segments(request: Request, persistence: &Persistence, cursor: Option<&Cursor>) -> Result<SearchResult, VelociError> {
+    let start_time = std::time::Instant::now();
+    info_time!("search in {} segments", persistence.segments.len() + 1);
+
+    let top = request.top.or(Some(10));
+    let skip = request.skip;
+
+    // Every segment returns its top + skip hits, top and skip are applied after merging.
+    // Facets are counted completely per segment, to get correct counts after merging.
+    let mut segment_request = request.clone();
+    segment_request.top = top.map(|top| top + skip.unwrap_or(0));
+    segment_request.skip = None;
+    if let Some(facets) = segment_request.facets.as_mut() {
+        for facet in facets.iter_mut() {
+            facet.top = None;
+            facet.min_count = None;
+        }
+    }
+    if let Some(aggregations) = segment_request.aggregations.as_mut() {
+        for aggregation in aggregations.iter_mut() {
+            *aggregation = aggregation.without_top();
         }
+    }
+
+    let segment_results = persistence
+        .get_segments()
+        .collect::<Vec<_>>()
+        .par_iter()
+        .map(|(offset, segment)| {
+            let mut segment_request = segment_request.clone();
+            if segment_request.collapse.is_some() {
+                // the groups of a value can be in multiple segments, so all groups are merged and the cursor is applied afterwards
+                segment_request.top = Some(segment.metadata.num_docs as usize);
+                return search_segment(segment_request, segment, None).map(|result| (*offset, result));
+            }
+            let search_after = cursor.map(|cursor| SearchAfter { cursor, offset: *offset });
+            search_segment(segment_request, segment, search_after).map(|result| (*offset, result))
+        })
+        .collect::<Result<Vec<_>, VelociError>>()?;
 
-        let plan_result = plan.plan_result.as_ref().unwrap().clone();
-        for stepso in plan.get_ordered_steps() {
-            execute_steps(stepso, persistence)?;
+    let mut search_result = merge_segment_results(persistence, segment_results, &request);
+    if let (Some(cursor), Some(_)) = (cursor, request.collapse.as_ref()) {
+        let search_after = SearchAfter { cursor, offset: 0 };
+        if let Some(sort) = request.sort.as_ref() {
+            search_result.data = sort_by_fields(persistence, mem::take(&mut search_result.data), sort, None, Some(search_after));
+        } else {
+            search_result.data.retain(|hit| search_after.is_after(hit));
         }
-        let res = plan_result.recv().unwrap();
-        drop(plan_result);
-        res
+    }
+    apply_top_skip(&mut search_result.data, skip, top);
+    retain_collapse_groups_of_data(&mut search_result);
+    search_result.execution_time_ns = start_time.elapsed().as_nanos() as u64;
+    Ok(search_result)
+}
+
+/// Merges the results of multiple segments, the anchor ids are translated to global ids by adding the offset of the segment.
+fn merge_segment_results(persistence: &Persistence, segment_results: Vec<(u32, SearchResult)>, request: &Request) -> SearchResult {
+    let mut search_result = SearchResult::default();
+    let mut facets: FnvHashMap<String, Vec<FacetResult>> = FnvHashMap::default();
+    let mut aggregations: FnvHashMap<String, Vec<Vec<AggregationBucket>>> = FnvHashMap::default();
+    for (offset, result) in segment_results {
+        search_result.num_hits += result.num_hits;
+        search_result.data.extend(result.data.into_iter().map(|hit| Hit::new(hit.id + offset, hit.score)));
+        search_result.explain.extend(result.explain.into_iter().map(|(id, explain)| (id + offset, explain)));
+        search_result.collapse.extend(result.collapse.into_iter().map(|(id, mut group)| {
+            for hit in group.hits.iter_mut() {
+                hit.id += offset;
+            }
+            (id + offset, group)
+        }));
+        search_result
+            .why_found_info
+            .extend(result.why_found_info.into_iter().map(|(id, why_found)| (id + offset, why_found)));
+        for (path, terms) in result.why_found_terms {
+            let merged_terms = search_result.why_found_terms.entry(path).or_default();
+            for term in terms {
+                if !merged_terms.contains(&term) {
+                    merged_terms.push(term);
+                }
+            }
+        }
+        for (field, facet) in result.facets.unwrap_or_default() {
+            facets.entry(field).or_default().push(facet);
+        }
+        for (field, buckets) in result.aggregations.unwrap_or_default() {
+            aggregations.entry(field).or_default().push(buckets);
+        }
+        if let Some(segment_stats) = result.stats {
+            let merged_stats = search_result.stats.get_or_insert_with(FnvHashMap::default);
+            for (field, stats) in segment_stats {
+                merged_stats.entry(field).or_default().merge(&stats);
+            }
+        }
+    }
+    if let Some(collapse) = request.collapse.as_ref() {
+        let (best_hits, groups) = merge_collapse_groups(persistence, collapse, mem::take(&mut search_result.collapse), request.sort.as_deref());
+        search_result.num_hits = best_hits.len() as u64;
+        search_result.data = best_hits;
+        search_result.collapse = groups;
+    } else if let Some(sort) = request.sort.as_ref() {
+        search_result.data = sort_by_fields(persistence, mem::take(&mut search_result.data), sort, None, None);
+    } else {
+        search_result.data.sort_unstable_by(sort_by_score_and_id);
+    }
+
+    if let Some(facets_req) = request.facets.as_ref() {
+        search_result.facets = Some(
+            facets_req
+                .iter()
+                .map(|facet_req| {
+                    let results = facets.remove(&facet_req.field).unwrap_or_default();
+                    (facet_req.field.to_string(), facet::merge_facet_results(facet_req, results))
+                })
+                .collect(),
+        );
+    }
+    if let Some(aggregations_req) = request.aggregations.as_ref() {
+        search_result.aggregations = Some(
+            aggregations_req
+                .iter()
+                .map(|aggregation_req| {
+                    let results = aggregations.remove(&aggregation_req.field).unwrap_or_default();
+                    (aggregation_req.field.to_string(), aggregation::merge_aggregation_results(aggregation_req, results))
+                })
+                .collect(),
+        );
+    }
+    search_result
+}
+
+fn search_segment(mut request: Request, persistence: &Persistence, search_after: Option<SearchAfter<'_>>) -> Result<SearchResult, VelociError> {
+    let start_time = std::time::Instant::now();
+    info_time!("search");
+    request.top = request.top.or(Some(10));
+
+    let mut res = {
+        info_time!("search terms");
+        execute_plan(&request, persistence)?
+    };
+
+    // the named filters and the post filter are computed separately, so facets can ignore them
+    let named_filters = request
+        .named_filters
+        .iter()
+        .flatten()
+        .map(|named_filter| Ok((named_filter.name.as_str(), get_filter_ids(persistence, &named_filter.filter)?)))
+        .collect::<Result<Vec<_>, VelociError>>()?;
+    let is_in_named_filters = |id: u32, excluded: &[String]| {
+        named_filters
+            .iter()
+            .filter(|(name, _)| !excluded.iter().any(|excluded| excluded == name))
+            .all(|(_, ids)| ids.contains(&id))
     };
+    let post_filter = request.post_filter.as_ref().map(|post_filter| get_filter_ids(persistence, post_filter)).transpose()?;
 
     let mut search_result = SearchResult { ..Default::default() };
     search_result.explain = res.explain.clone();
@@ -185,37 +415,87 @@ pub fn search(mut request: Request, persistence: &Persistence) -> Result<SearchR
     let term_id_hits_in_field = res.term_id_hits_in_field;
     search_result.why_found_terms = res.term_text_in_field;
 
+    let hit_ids: Vec<u32> = if request.facets.is_some() || request.stats.is_some() || request.aggregations.is_some() {
+        // get sorted ids, for facets, stats and aggregations
+        debug_time!("get_and_sort_for_factes");
+        let mut hit_ids: Vec<u32> = res.hits_scores.iter().map(|el| el.id).collect();
+        debug_time!("get_and_sort_for_factes sort only!!!");
+        hit_ids.sort_unstable();
+        hit_ids
+    } else {
+        vec![]
+    };
+
     if let Some(facets_req) = request.facets {
         info_time!("all_facets {:?}", facets_req.iter().map(|el| el.field.clone()).collect::<Vec<_>>());
 
-        let hit_ids: Vec<u32> = {
-            // get sorted ids, for facets
-            debug_time!("get_and_sort_for_factes");
-            let mut hit_ids: Vec<u32> = res.hits_scores.iter().map(|el| el.id).collect();
-            debug_time!("get_and_sort_for_factes sort only!!!");
-            hit_ids.sort_unstable();
-            hit_ids
-        };
-
         search_result.facets = Some(
             facets_req
                 .par_iter()
-                .map(|facet_req| (facet_req.field.to_string(), facet::get_facet(persistence, facet_req, &hit_ids).unwrap()))
-                .collect(),
+                .map(|facet_req| {
+                    let facet = if named_filters.is_empty() {
+                        facet::get_facet(persistence, facet_req, &hit_ids)?
+                    } else {
+                        let hit_ids: Vec<u32> = hit_ids.iter().cloned().filter(|id| is_in_named_filters(*id, &facet_req.exclude_filters)).collect();
+                        facet::get_facet(persistence, facet_req, &hit_ids)?
+                    };
+                    Ok((facet_req.field.to_string(), facet))
+                })
+                .collect::<Result<_, VelociError>>()?,
+        );
+    }
+    let hit_ids: Vec<u32> = if named_filters.is_empty() {
+        hit_ids
+    } else {
+        res.hits_scores.retain(|hit| is_in_named_filters(hit.id, &[]));
+        hit_ids.into_iter().filter(|id| is_in_named_filters(*id, &[])).collect()
+    };
+    if let Some(aggregations_req) = request.aggregations {
+        search_result.aggregations = Some(
+            aggregations_req
+                .par_iter()
+                .map(|aggregation_req| Ok((aggregation_req.field.to_string(), aggregation::get_aggregation(persistence, aggregation_req, &hit_ids)?)))
+                .collect::<Result<_, VelociError>>()?,
+        );
+    }
+    if let Some(stats_req) = request.stats {
+        search_result.stats = Some(
+            stats_req
+                .par_iter()
+                .map(|stats_req| Ok((stats_req.field.to_string(), facet::get_stats(persistence, stats_req, &hit_ids)?)))
+                .collect::<Result<_, VelociError>>()?,
         );
     }
+    if let Some(post_filter) = post_filter.as_ref() {
+        res.hits_scores.retain(|hit| post_filter.contains(&hit.id));
+    }
+    if let Some(collapse) = request.collapse.as_ref() {
+        let (best_hits, groups) = collapse_hits(persistence, collapse, mem::take(&mut res.hits_scores), request.sort.as_deref())?;
+        res.hits_scores = best_hits;
+        search_result.collapse = groups;
+    }
     search_result.num_hits = res.hits_scores.len() as u64;
     {
         debug_time!("sort search by score");
-        if let Some(top) = request.top {
+        if let Some(sort) = request.sort.as_ref() {
+            let top_n = request.top.map(|top| top as u32 + request.skip.unwrap_or(0) as u32);
+            search_result.data = sort_by_fields(persistence, res.hits_scores, sort, top_n, search_after);
+        } else if let Some(top) = request.top {
+            if let Some(search_after) = search_after {
+                res.hits_scores.retain(|hit| search_after.is_after(hit));
+            }
             search_result.data = top_n_sort(res.hits_scores, top as u32 + request.skip.unwrap_or(0) as u32);
         } else {
             search_result.data = res.hits_scores;
+            if let Some(search_after) = search_after {
+                search_result.data.retain(|hit| search_after.is_after(hit));
+            }
             search_result.data.sort_unstable_by(sort_by_score_and_id);
         }
     }
 
     apply_top_skip(&mut search_result.data, request.skip, request.top);
+    retain_collapse_groups_of_data(&mut search_result);
 
     if request.why_found && request.select.is_some() {
         let anchor_ids: Vec<u32> = search_result.data.iter().map(|el| el.id).collect();
@@ -227,6 +507,84 @@ pub fn search(mut request: Request, persistence: &Persistence) -> Result<SearchR
     Ok(search_result)
 }
 
+/// Removes the collapse groups of the hits, which are not in the result
+fn retain_collapse_groups_of_data(search_result: &mut SearchResult) {
+    if search_result.collapse.is_empty() {
+        return;
+    }
+    let ids: FnvHashSet<u32> = search_result.data.iter().map(|hit| hit.id).collect();
+    search_result.collapse.retain(|id, _| ids.contains(id));
+}
+
+/// Executes the plan of the request and returns the hits, without deleted documents
+fn execute_plan(request: &Request, persistence: &Persistence) -> Result<SearchFieldResult, VelociError> {
+    let mut plan = Plan::default();
+    if request.search_req.is_none() {
+        return Err(VelociError::InvalidRequest {
+            message: format!("search_req is None, but is required in search, request: {:?}", request),
+        });
+    }
+    plan_creator(request.clone(), &mut plan);
+
+    if log_enabled!(log::Level::Debug) {
+        let mut dot_graph = vec![];
+        render_plan_to(&plan, &mut dot_graph);
+        debug!("{}", String::from_utf8(dot_graph)?);
+    }
+
+    let plan_result = plan.plan_result.as_ref().unwrap().clone();
+    for stepso in plan.get_ordered_steps() {
+        execute_steps(stepso, persistence)?;
+    }
+    let mut res = plan_result.recv().unwrap();
+    drop(plan_result);
+    if persistence.has_deleted_docs() {
+        res.hits_scores.retain(|hit| !persistence.is_deleted(hit.id));
+        res.hits_ids.retain(|id| !persistence.is_deleted(*id));
+    }
+    Ok(res)
+}
+
+/// Returns the ids matching a filter, as a separate plan
+fn get_filter_ids(persistence: &Persistence, filter: &SearchRequest) -> Result<FnvHashSet<u32>, VelociError> {
+    let request = Request {
+        search_req: Some(filter.clone()),
+        top: None,
+        ..Default::default()
+    };
+    let res = execute_plan(&request, persistence)?;
+    Ok(res.hits_scores.iter().map(|hit| hit.id).collect())
+}
+
+/// Returns the document for the global anchor id.
+pub fn get_document(persistence: &Persistence, id: u32) -> Result<serde_json::Value, VelociError> {
+    let (segment, segment_id) = persistence.get_segment_for_id(id);
+    let doc_store_data = segment.get_file_bytes("data")?;
+    let doc_str = DocLoader::open(doc_store_data.as_ref()).get_doc(segment_id)?;
+    Ok(serde_json::from_str(&doc_str)?)
+}
+
+/// Returns the documents for the primary keys, `None` if there is no document with the key.
+pub fn get_documents_by_keys<S: AsRef<str>>(persistence: &Persistence, keys: &[S]) -> Result<Vec<Option<serde_json::Value>>, VelociError> {
+    persistence
+        .get_anchors_by_keys(keys)
+        .into_iter()
+        .map(|id| id.map(|id| get_document(persistence, id)).transpose())
+        .collect()
+}
+
+/// Deletes all documents matching the search request and returns the number of deleted documents.
+pub fn delete_by_query(persistence: &mut Persistence, search_req: SearchRequest) -> Result<u64, VelociError> {
+    let request = Request {
+        search_req: Some(search_req),
+        top: Some(persistence.get_number_of_documents() as usize),
+        ..Default::default()
+    };
+    let ids: Vec<u32> = search(request, persistence)?.data.iter().map(|hit| hit.id).collect();
+    persistence.delete_documents(&ids)?;
+    Ok(ids.len() as u64)
+}
+
 pub fn apply_top_skip<T: Clone>(hits: &mut Vec<T>, skip: Option<usize>, top: Option<usize>) {
     if let Some(mut skip) = skip {
         skip = cmp::min(skip, hits.len());
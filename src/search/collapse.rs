@@ -0,0 +1,129 @@
+use crate::{
+    error::VelociError,
+    facet::get_text_ids_for_anchor,
+    persistence::Persistence,
+    search::{get_text_for_id, sort::sort_by_fields, sort_by_score_and_id, CollapseGroup, CollapseRequest, Hit, RequestSortPart},
+    util,
+};
+use fnv::FnvHashMap;
+
+/// Checks that the collapse field exists in at least one segment
+pub(crate) fn check_collapse_field(persistence: &Persistence, collapse: &CollapseRequest) -> Result<(), VelociError> {
+    if persistence.get_segments().any(|(_, segment)| segment.metadata.columns.contains_key(&collapse.field)) {
+        return Ok(());
+    }
+    Err(VelociError::InvalidRequest {
+        message: format!("collapse field {:?} does not exist", collapse.field),
+    })
+}
+
+/// Sorts the hits like the result, by the sort fields or by score
+pub(crate) fn sort_hits(persistence: &Persistence, hits: Vec<Hit>, sort: Option<&[RequestSortPart]>) -> Vec<Hit> {
+    match sort {
+        Some(sort) => sort_by_fields(persistence, hits, sort, None, None),
+        None => {
+            let mut hits = hits;
+            hits.sort_unstable_by(sort_by_score_and_id);
+            hits
+        }
+    }
+}
+
+/// Groups the hits of a segment by the value of the collapse field.
+///
+/// Returns the best hit per group, in the order of the result, and the groups by the id of their best hit.
+pub(crate) fn collapse_hits(
+    persistence: &Persistence,
+    collapse: &CollapseRequest,
+    hits: Vec<Hit>,
+    sort: Option<&[RequestSortPart]>,
+) -> Result<(Vec<Hit>, FnvHashMap<u32, CollapseGroup>), VelociError> {
+    let hits = sort_hits(persistence, hits, sort);
+    let steps = util::get_steps_to_anchor(&collapse.field);
+    let text_index = steps.last().unwrap();
+    let has_values = persistence.metadata.columns.contains_key(&collapse.field);
+    // texts longer than `do_not_store_text_longer_than` are not in the fst, these hits are not collapsed
+    let num_text_ids = persistence
+        .metadata
+        .columns
+        .get(&collapse.field)
+        .map(|field_info| field_info.textindex_metadata.num_text_ids as u32)
+        .unwrap_or(0);
+
+    let mut best_hits = vec![];
+    let mut groups: FnvHashMap<u32, CollapseGroup> = FnvHashMap::default();
+    let mut best_hit_per_text_id: FnvHashMap<u32, u32> = FnvHashMap::default();
+    for hit in hits {
+        let text_id = if has_values {
+            get_text_ids_for_anchor(persistence, &steps, hit.id)?
+                .first()
+                .cloned()
+                .filter(|text_id| *text_id < num_text_ids)
+        } else {
+            None
+        };
+        let best_hit_id = text_id.and_then(|text_id| best_hit_per_text_id.get(&text_id).cloned());
+        match best_hit_id {
+            Some(best_hit_id) => {
+                let group = groups.get_mut(&best_hit_id).unwrap();
+                group.count += 1;
+                if group.hits.len() < collapse.top_hits {
+                    group.hits.push(hit);
+                }
+            }
+            None => {
+                if let Some(text_id) = text_id {
+                    best_hit_per_text_id.insert(text_id, hit.id);
+                }
+                let group = CollapseGroup {
+                    value: text_id.map(|text_id| get_text_for_id(persistence, text_index, text_id)),
+                    count: 1,
+                    hits: vec![hit.clone()],
+                };
+                groups.insert(hit.id, group);
+                best_hits.push(hit);
+            }
+        }
+    }
+    Ok((best_hits, groups))
+}
+
+/// Merges the groups of the segments with the same value, the ids are already global ids.
+///
+/// Returns the best hit per group, in the order of the result, and the groups by the id of their best hit.
+pub(crate) fn merge_collapse_groups(
+    persistence: &Persistence,
+    collapse: &CollapseRequest,
+    groups: FnvHashMap<u32, CollapseGroup>,
+    sort: Option<&[RequestSortPart]>,
+) -> (Vec<Hit>, FnvHashMap<u32, CollapseGroup>) {
+    let mut merged: Vec<CollapseGroup> = vec![];
+    let mut group_per_value: FnvHashMap<String, usize> = FnvHashMap::default();
+    for group in groups.into_values() {
+        let existing = group.value.as_ref().and_then(|value| group_per_value.get(value).cloned());
+        match existing {
+            Some(index) => {
+                let merged_group = &mut merged[index];
+                merged_group.count += group.count;
+                merged_group.hits.extend(group.hits);
+            }
+            None => {
+                if let Some(value) = group.value.as_ref() {
+                    group_per_value.insert(value.to_string(), merged.len());
+                }
+                merged.push(group);
+            }
+        }
+    }
+
+    let mut best_hits = vec![];
+    let mut groups = FnvHashMap::default();
+    for mut group in merged {
+        group.hits = sort_hits(persistence, group.hits, sort);
+        group.hits.truncate(collapse.top_hits.max(1));
+        let best_hit = group.hits[0].clone();
+        groups.insert(best_hit.id, group);
+        best_hits.push(best_hit);
+    }
+    (sort_hits(persistence, best_hits, sort), groups)
+}
@@ -0,0 +1,65 @@
+use crate::{error::VelociError, indices::NumericValue, search::Hit};
+use std::fmt::Write;
+
+/// Position of a hit in the sorted results, used to continue with the next page via `search_after`.
+///
+/// Contains the sort values of the hit, so that the next page can be computed without the hits before.
+/// The cursor is passed around opaque, encoded as hex of its json representation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Cursor {
+    /// The values of the sort fields of the request, empty when sorted by score
+    pub values: Vec<Option<CursorValue>>,
+    pub score: f32,
+    /// The global anchor id
+    pub id: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum CursorValue {
+    Number(NumericValue),
+    Text(String),
+}
+
+impl Cursor {
+    pub(crate) fn hit(&self) -> Hit {
+        Hit::new(self.id, self.score)
+    }
+
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_string(self).expect("cursor is serializable");
+        json.bytes().fold(String::with_capacity(json.len() * 2), |mut hex, byte| {
+            write!(hex, "{:02x}", byte).unwrap();
+            hex
+        })
+    }
+
+    // usize::is_multiple_of requires rust 1.87
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn decode(cursor: &str) -> Result<Cursor, VelociError> {
+        let invalid = || VelociError::InvalidRequest {
+            message: format!("invalid search_after cursor {:?}", cursor),
+        };
+        if cursor.len() % 2 != 0 || !cursor.is_ascii() {
+            return Err(invalid());
+        }
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|pos| u8::from_str_radix(&cursor[pos..pos + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+        serde_json::from_slice(&bytes).map_err(|_| invalid())
+    }
+}
+
+#[test]
+fn test_cursor_encode_decode() {
+    let cursor = Cursor {
+        values: vec![Some(CursorValue::Number(NumericValue::F64(2.5))), Some(CursorValue::Text("apple".to_string())), None],
+        score: 1.5,
+        id: 7,
+    };
+    assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+    assert!(Cursor::decode("zz").is_err());
+    assert!(Cursor::decode("7b7d").is_err());
+}
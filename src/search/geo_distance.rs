@@ -0,0 +1,52 @@
+use crate::{
+    error::VelociError,
+    persistence::{Persistence, GEO_VALUES},
+    search::{result::*, search_field::should_filter, *},
+    util::StringAdd,
+};
+use std::sync::Arc;
+
+/// Returns all anchors which have a geo point within `distance_km` of the point of the request.
+///
+/// The score decreases linearly with the distance of the nearest point, from the boost (default 1.0) to 0 at `distance_km`.
+pub fn get_anchors_in_distance(persistence: &Persistence, request: &RequestGeoDistancePart, filter: &Option<Arc<FilterResult>>) -> Result<SearchFieldResult, VelociError> {
+    debug_time!("{} geo distance {}", &request.path, request);
+    if request.distance_km.is_nan() || request.distance_km <= 0.0 {
+        return Err(VelociError::InvalidRequest {
+            message: format!("geo distance query on {:?} requires a positive distance_km, got {}", request.path, request.distance_km),
+        });
+    }
+
+    let boost = request.boost.map(|boost| boost.into_inner()).unwrap_or(1.0);
+    let mut result = SearchFieldResult::default();
+    result.request = RequestSearchPart {
+        path: request.path.to_string(),
+        terms: vec![request.to_string()],
+        options: request.options.clone(),
+        ..Default::default()
+    };
+
+    // the field may only exist in other segments
+    let column = match persistence.indices.geo_values.get(&request.path.add(GEO_VALUES)) {
+        Some(column) => column,
+        None => return Ok(result),
+    };
+
+    for anchor_id in 0..column.num_ids() {
+        if should_filter(filter, anchor_id) {
+            continue;
+        }
+        let distance = match column.get_min_distance_km(anchor_id, &request.point) {
+            Some(distance) if distance <= request.distance_km => distance,
+            _ => continue,
+        };
+        let score = boost * (1.0 - distance / request.distance_km) as f32;
+        result.hits_scores.push(Hit::new(anchor_id, score));
+        result.hits_ids.push(anchor_id);
+        if request.is_explain() {
+            result.explain.insert(anchor_id, vec![Explain::GeoDistance { score, distance_km: distance }]);
+        }
+    }
+    info!("{:?}\thits for geo distance {}", result.hits_ids.len(), request);
+    Ok(result)
+}
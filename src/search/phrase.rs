@@ -0,0 +1,406 @@
+use crate::{
+    error::VelociError,
+    persistence::{Persistence, PHRASE_PAIR_TO_ANCHOR, TEXTINDEX, TEXT_ID_TO_ANCHOR, TOKENS_TO_TEXT_ID, TOKEN_TO_TEXT_ID_POSITIONS},
+    plan_creator::execution_plan::PlanRequestSearchPart,
+    search::{
+        result::*,
+        search_field::{get_anchor_for_phrases_in_field, should_filter},
+        *,
+    },
+    util::StringAdd,
+};
+use fnv::{FnvHashMap, FnvHashSet};
+use std::sync::Arc;
+
+/// The positions of a token of the query in the texts of a field, by text id
+pub(crate) type TextIdToPositions = FnvHashMap<u32, Vec<u32>>;
+
+/// Splits a query text into the tokens of the field, seperators are removed.
+///
+/// Fields without tokenizer have only one token, the whole text.
+pub(crate) fn tokenize_for_field(persistence: &Persistence, path: &str, text: &str) -> Vec<String> {
+    let tokenizer = persistence
+        .metadata
+        .columns
+        .get(path)
+        .filter(|col| col.textindex_metadata.options.tokenize)
+        .and_then(|col| col.textindex_metadata.options.tokenizer.as_ref());
+    match tokenizer {
+        Some(tokenizer) => tokenizer.iter(text).filter(|(_, is_seperator)| !is_seperator).map(|(token, _)| token.to_string()).collect(),
+        None => vec![text.to_string()],
+    }
+}
+
+/// Returns the ids of the terms matching the token and the best score of them
+fn get_term_ids_for_token(persistence: &Persistence, path: &str, token: &str, ignore_case: Option<bool>) -> Result<(Vec<u32>, f32), VelociError> {
+    let mut plan_request = PlanRequestSearchPart {
+        request: RequestSearchPart {
+            path: path.to_string(),
+            terms: vec![token.to_string()],
+            levenshtein_distance: Some(0),
+            ignore_case,
+            ..Default::default()
+        },
+        get_scores: true,
+        ..Default::default()
+    };
+    let result = get_term_ids_in_field(persistence, &mut plan_request)?;
+    let best_score = result.hits_scores.iter().map(|hit| hit.score).fold(0.0, f32::max);
+    Ok((result.hits_scores.iter().map(|hit| hit.id).collect(), best_score))
+}
+
+/// Loads the positions of the terms in the texts from the positional index.
+///
+/// Texts consisting of a single token are not in the positional index.
+pub(crate) fn get_positions_for_terms(persistence: &Persistence, path: &str, term_ids: &[u32]) -> Result<TextIdToPositions, VelociError> {
+    let positions_index = persistence.get_valueid_to_parent(path.add(TEXTINDEX).add(TOKEN_TO_TEXT_ID_POSITIONS))?;
+    let mut text_id_to_positions = TextIdToPositions::default();
+    for term_id in term_ids {
+        if let Some(text_ids_and_positions) = positions_index.get_values(u64::from(*term_id)) {
+            for pair in text_ids_and_positions.chunks_exact(2) {
+                text_id_to_positions.entry(pair[0]).or_insert_with(Vec::new).push(pair[1]);
+            }
+        }
+    }
+    Ok(text_id_to_positions)
+}
+
+/// Checks that the tokens appear at consecutive positions in the text
+fn contains_phrase(text_id: u32, positions_per_token: &[TextIdToPositions]) -> bool {
+    let start_positions = match positions_per_token[0].get(&text_id) {
+        Some(positions) => positions,
+        None => return false,
+    };
+    start_positions.iter().any(|start| {
+        positions_per_token[1..]
+            .iter()
+            .enumerate()
+            .all(|(offset, positions)| positions.get(&text_id).map(|positions| positions.contains(&(start + offset as u32 + 1))).unwrap_or(false))
+    })
+}
+
+/// Resolves text ids to anchor ids
+pub(crate) fn text_ids_to_anchors(persistence: &Persistence, path: &str, text_ids: &[u32]) -> Result<Vec<u32>, VelociError> {
+    let is_anchor_identity_column = persistence.metadata.columns.get(path).map(|el| el.is_anchor_identity_column).unwrap_or(false);
+    let mut anchor_ids: Vec<u32> = if is_anchor_identity_column {
+        // the text_id_to_anchor index is not filled for identity columns, the text ids are sorted by text and not by anchor though
+        let token_to_anchor_score = persistence.get_token_to_anchor(path.add(TEXTINDEX))?;
+        text_ids.iter().flat_map(|text_id| token_to_anchor_score.get_score_iter(*text_id).map(|el| el.id)).collect()
+    } else {
+        let text_id_to_anchor = persistence.get_valueid_to_parent(path.add(TEXTINDEX).add(TEXT_ID_TO_ANCHOR))?;
+        text_ids.iter().flat_map(|text_id| text_id_to_anchor.get_values_iter(u64::from(*text_id))).collect()
+    };
+    anchor_ids.sort_unstable();
+    anchor_ids.dedup();
+    Ok(anchor_ids)
+}
+
+/// Returns the term ids per token and the sum of the best term score per token
+fn get_term_ids_per_token(persistence: &Persistence, path: &str, tokens: &[String], ignore_case: Option<bool>) -> Result<(Vec<Vec<u32>>, f32), VelociError> {
+    let mut score = 0.0;
+    let mut term_ids_per_token = vec![];
+    for token in tokens {
+        let (term_ids, best_score) = get_term_ids_for_token(persistence, path, token, ignore_case)?;
+        score += best_score;
+        term_ids_per_token.push(term_ids);
+    }
+    Ok((term_ids_per_token, score))
+}
+
+/// A single token needs no positions, it is either the text or a token in the text
+fn get_text_ids_for_single_token(persistence: &Persistence, path: &str, term_ids: &[u32]) -> Result<Vec<u32>, VelociError> {
+    let mut text_ids: FnvHashSet<u32> = term_ids.iter().cloned().collect();
+    if has_positional_index(persistence, path) {
+        text_ids.extend(get_positions_for_terms(persistence, path, term_ids)?.keys());
+    } else if let Ok(tokens_to_text_id) = persistence.get_valueid_to_parent(path.add(TEXTINDEX).add(TOKENS_TO_TEXT_ID)) {
+        for term_id in term_ids {
+            text_ids.extend(tokens_to_text_id.get_values_iter(u64::from(*term_id)));
+        }
+    }
+    Ok(text_ids.into_iter().collect())
+}
+
+fn has_positional_index(persistence: &Persistence, path: &str) -> bool {
+    persistence.has_index(&path.add(TEXTINDEX).add(TOKEN_TO_TEXT_ID_POSITIONS))
+}
+
+/// Checks that at least one segment has the positional index, which is required for queries with more than one token.
+///
+/// Segments without the positional index, e.g. appended with another config, are handled in the segment.
+fn check_positional_index(persistence: &Persistence, path: &str, num_tokens: usize, query_type: &str) -> Result<(), VelociError> {
+    if num_tokens > 1 && !persistence.get_segments().any(|(_, segment)| has_positional_index(segment, path)) {
+        return Err(VelociError::InvalidRequest {
+            message: format!("{} query on {:?} requires the positional index, enable the feature PhraseQuery", query_type, path),
+        });
+    }
+    Ok(())
+}
+
+pub(crate) fn check_phrase_request(persistence: &Persistence, request: &RequestPhrasePart) -> Result<(), VelociError> {
+    let num_tokens = tokenize_for_field(persistence, &request.path, &request.phrase).len();
+    check_positional_index(persistence, &request.path, num_tokens, "phrase")
+}
+
+pub(crate) fn check_proximity_request(persistence: &Persistence, request: &RequestProximityPart) -> Result<(), VelociError> {
+    let num_tokens = get_proximity_tokens(persistence, request).len();
+    check_positional_index(persistence, &request.path, num_tokens, "proximity")
+}
+
+/// Returns `None` if the segment has no positional index for the field
+fn get_positions_per_token(persistence: &Persistence, path: &str, term_ids_per_token: &[Vec<u32>]) -> Result<Option<Vec<TextIdToPositions>>, VelociError> {
+    if !has_positional_index(persistence, path) {
+        return Ok(None);
+    }
+    term_ids_per_token
+        .iter()
+        .map(|term_ids| get_positions_for_terms(persistence, path, term_ids))
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+/// Approximates a phrase with the anchors containing all consecutive token pairs of the phrase, used when the segment has no positional index.
+///
+/// The pairs may be in different texts of the anchor.
+fn get_anchors_for_phrase_pairs(persistence: &Persistence, path: &str, term_ids_per_token: &[Vec<u32>]) -> Result<Vec<u32>, VelociError> {
+    let path = path.add(TEXTINDEX).add(PHRASE_PAIR_TO_ANCHOR);
+    if !persistence.indices.phrase_pair_to_anchor.contains_key(&path) {
+        return Ok(vec![]);
+    }
+    let mut anchor_ids: Option<Vec<u32>> = None;
+    for pair in term_ids_per_token.windows(2) {
+        let mut pair_anchor_ids = get_anchor_for_phrases_in_field(persistence, &path, &pair[0], &pair[1])?.hits_ids;
+        pair_anchor_ids.dedup();
+        anchor_ids = Some(match anchor_ids {
+            Some(mut anchor_ids) => {
+                anchor_ids.retain(|anchor_id| pair_anchor_ids.binary_search(anchor_id).is_ok());
+                anchor_ids
+            }
+            None => pair_anchor_ids,
+        });
+    }
+    Ok(anchor_ids.unwrap_or_default())
+}
+
+fn to_field_result(path: &str, term: String, options: &Option<SearchRequestOptions>, hits: Vec<Hit>, explain: FnvHashMap<u32, Vec<Explain>>) -> SearchFieldResult {
+    let mut result = SearchFieldResult::default();
+    result.request = RequestSearchPart {
+        path: path.to_string(),
+        terms: vec![term],
+        options: options.clone(),
+        ..Default::default()
+    };
+    result.hits_ids = hits.iter().map(|hit| hit.id).collect();
+    result.hits_scores = hits;
+    result.explain = explain;
+    result
+}
+
+/// Returns all anchors with a text, which contains the tokens of the phrase in the same order and without other tokens between them.
+///
+/// The score is the sum of the best term score per token, multiplied with the boost of the request.
+pub fn get_anchors_for_phrase(persistence: &Persistence, request: &RequestPhrasePart, filter: &Option<Arc<FilterResult>>) -> Result<SearchFieldResult, VelociError> {
+    debug_time!("{} phrase {}", &request.path, request);
+    let tokens = tokenize_for_field(persistence, &request.path, &request.phrase);
+    if tokens.is_empty() {
+        return Err(VelociError::InvalidRequest {
+            message: format!("phrase query on {:?} has no tokens", request.path),
+        });
+    }
+    // the field may only exist in other segments
+    if !persistence.indices.fst.contains_key(&request.path.add(TEXTINDEX)) {
+        return Ok(to_field_result(&request.path, request.phrase.to_string(), &request.options, vec![], FnvHashMap::default()));
+    }
+
+    let (term_ids_per_token, score) = get_term_ids_per_token(persistence, &request.path, &tokens, request.ignore_case)?;
+    let mut anchor_ids = if tokens.len() == 1 {
+        let text_ids = get_text_ids_for_single_token(persistence, &request.path, &term_ids_per_token[0])?;
+        text_ids_to_anchors(persistence, &request.path, &text_ids)?
+    } else if let Some(positions_per_token) = get_positions_per_token(persistence, &request.path, &term_ids_per_token)? {
+        let text_ids: Vec<u32> = positions_per_token[0]
+            .keys()
+            .filter(|text_id| contains_phrase(**text_id, &positions_per_token))
+            .cloned()
+            .collect();
+        text_ids_to_anchors(persistence, &request.path, &text_ids)?
+    } else {
+        get_anchors_for_phrase_pairs(persistence, &request.path, &term_ids_per_token)?
+    };
+    anchor_ids.retain(|anchor_id| !should_filter(filter, *anchor_id));
+
+    let score = score * request.boost.map(|boost| boost.into_inner()).unwrap_or(1.0);
+    let mut explain = FnvHashMap::default();
+    if request.is_explain() {
+        for anchor_id in &anchor_ids {
+            explain.insert(
+                *anchor_id,
+                vec![Explain::Phrase {
+                    score,
+                    phrase: request.phrase.to_string(),
+                }],
+            );
+        }
+    }
+    let hits = anchor_ids.iter().map(|anchor_id| Hit::new(*anchor_id, score)).collect();
+    let result = to_field_result(&request.path, request.phrase.to_string(), &request.options, hits, explain);
+    info!("{:?}\thits for phrase {}", result.hits_ids.len(), request);
+    Ok(result)
+}
+
+/// Returns the smallest number of other tokens between the tokens in the text, in any order.
+///
+/// `None` if the text doesn't contain all tokens.
+fn get_min_gaps(text_id: u32, positions_per_token: &[TextIdToPositions]) -> Option<u32> {
+    let mut positions: Vec<(u32, usize)> = vec![];
+    for (token_index, positions_of_token) in positions_per_token.iter().enumerate() {
+        positions.extend(positions_of_token.get(&text_id)?.iter().map(|position| (*position, token_index)));
+    }
+    positions.sort_unstable();
+
+    // sliding window over the positions, which covers every token at least once
+    let mut count_per_token = vec![0; positions_per_token.len()];
+    let mut num_covered = 0;
+    let mut window_start = 0;
+    let mut min_span = u32::MAX;
+    for (position, token_index) in positions.iter() {
+        if count_per_token[*token_index] == 0 {
+            num_covered += 1;
+        }
+        count_per_token[*token_index] += 1;
+        while num_covered == positions_per_token.len() {
+            let (start_position, start_token_index) = positions[window_start];
+            min_span = min_span.min(position - start_position);
+            count_per_token[start_token_index] -= 1;
+            if count_per_token[start_token_index] == 0 {
+                num_covered -= 1;
+            }
+            window_start += 1;
+        }
+    }
+    // a position can match several tokens, when their term ids overlap
+    Some((min_span + 1).saturating_sub(positions_per_token.len() as u32))
+}
+
+/// The distinct tokens of the terms of a proximity query
+fn get_proximity_tokens(persistence: &Persistence, request: &RequestProximityPart) -> Vec<String> {
+    let mut tokens: Vec<String> = request.terms.iter().flat_map(|term| tokenize_for_field(persistence, &request.path, term)).collect();
+    tokens.sort_unstable();
+    tokens.dedup();
+    tokens
+}
+
+/// Returns all anchors with a text, which contains the tokens of the terms in any order with at most `distance` other tokens between them.
+///
+/// The score is the sum of the best term score per token, multiplied with the boost of the request.
+/// Texts with fewer tokens between the terms get a bonus of `score / (1 + gaps)`, with `gaps` the number of other tokens between them.
+pub fn get_anchors_for_proximity(persistence: &Persistence, request: &RequestProximityPart, filter: &Option<Arc<FilterResult>>) -> Result<SearchFieldResult, VelociError> {
+    debug_time!("{} proximity {}", &request.path, request);
+    let tokens = get_proximity_tokens(persistence, request);
+    if tokens.is_empty() {
+        return Err(VelociError::InvalidRequest {
+            message: format!("proximity query on {:?} has no tokens", request.path),
+        });
+    }
+
+    let (mut term_ids_per_token, score) = get_term_ids_per_token(persistence, &request.path, &tokens, request.ignore_case)?;
+    // tokens with the same term ids, e.g. "Apple apple" with ignore_case, are the same token in the text
+    for term_ids in term_ids_per_token.iter_mut() {
+        term_ids.sort_unstable();
+    }
+    term_ids_per_token.sort_unstable();
+    term_ids_per_token.dedup();
+    let mut text_id_and_gaps: Vec<(u32, u32)> = if term_ids_per_token.len() == 1 {
+        get_text_ids_for_single_token(persistence, &request.path, &term_ids_per_token[0])?
+            .into_iter()
+            .map(|text_id| (text_id, 0))
+            .collect()
+    } else if let Some(positions_per_token) = get_positions_per_token(persistence, &request.path, &term_ids_per_token)? {
+        positions_per_token[0]
+            .keys()
+            .filter_map(|text_id| get_min_gaps(*text_id, &positions_per_token).map(|gaps| (*text_id, gaps)))
+            .filter(|(_text_id, gaps)| *gaps <= request.distance)
+            .collect()
+    } else {
+        // the distance can't be checked without positional index in this segment
+        vec![]
+    };
+
+    // an anchor gets the smallest gaps of its texts
+    text_id_and_gaps.sort_unstable_by_key(|(_text_id, gaps)| *gaps);
+    let mut gaps_per_anchor: FnvHashMap<u32, u32> = FnvHashMap::default();
+    for (text_id, gaps) in text_id_and_gaps {
+        for anchor_id in text_ids_to_anchors(persistence, &request.path, &[text_id])? {
+            gaps_per_anchor.entry(anchor_id).or_insert(gaps);
+        }
+    }
+    gaps_per_anchor.retain(|anchor_id, _gaps| !should_filter(filter, *anchor_id));
+
+    let score = score * request.boost.map(|boost| boost.into_inner()).unwrap_or(1.0);
+    let mut hits: Vec<Hit> = gaps_per_anchor
+        .iter()
+        .map(|(anchor_id, gaps)| Hit::new(*anchor_id, score * (1.0 + 1.0 / (1.0 + *gaps as f32))))
+        .collect();
+    hits.sort_unstable_by_key(|hit| hit.id);
+    let mut explain = FnvHashMap::default();
+    if request.is_explain() {
+        for hit in &hits {
+            explain.insert(
+                hit.id,
+                vec![Explain::Proximity {
+                    score: hit.score,
+                    gaps: gaps_per_anchor[&hit.id],
+                }],
+            );
+        }
+    }
+    let result = to_field_result(&request.path, request.to_string(), &request.options, hits, explain);
+    info!("{:?}\thits for proximity {}", result.hits_ids.len(), request);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains_phrase() {
+        let positions = |pairs: &[(u32, u32)]| {
+            let mut map = TextIdToPositions::default();
+            for (text_id, position) in pairs {
+                map.entry(*text_id).or_insert_with(Vec::new).push(*position);
+            }
+            map
+        };
+        // "a b c" in text 1, "b c a b" in text 2
+        let a = positions(&[(1, 0), (2, 2)]);
+        let b = positions(&[(1, 1), (2, 0), (2, 3)]);
+        let c = positions(&[(1, 2), (2, 1)]);
+        let phrase = [a.clone(), b.clone(), c.clone()];
+        assert!(contains_phrase(1, &phrase));
+        assert!(!contains_phrase(2, &phrase));
+        assert!(contains_phrase(2, &[b.clone(), c.clone()]));
+        assert!(contains_phrase(2, &[a, b]));
+        assert!(!contains_phrase(3, &[c]));
+    }
+
+    #[test]
+    fn test_get_min_gaps() {
+        let positions = |pairs: &[(u32, u32)]| {
+            let mut map = TextIdToPositions::default();
+            for (text_id, position) in pairs {
+                map.entry(*text_id).or_insert_with(Vec::new).push(*position);
+            }
+            map
+        };
+        // "a x b x x c" in text 1, "c b x x x x a" in text 2
+        let a = positions(&[(1, 0), (2, 6)]);
+        let b = positions(&[(1, 2), (2, 1)]);
+        let c = positions(&[(1, 5), (2, 0)]);
+        assert_eq!(get_min_gaps(1, &[a.clone(), b.clone()]), Some(1));
+        // tokens with the same positions, e.g. overlapping term ids
+        assert_eq!(get_min_gaps(1, &[a.clone(), a.clone()]), Some(0));
+        assert_eq!(get_min_gaps(1, &[a.clone(), b.clone(), c.clone()]), Some(3));
+        assert_eq!(get_min_gaps(2, &[b.clone(), c.clone()]), Some(0));
+        assert_eq!(get_min_gaps(2, &[a.clone(), b, c]), Some(4));
+        assert_eq!(get_min_gaps(3, &[a]), None);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{
+    error::VelociError,
+    indices::{NumericColumn, NumericValue},
+    persistence::{Persistence, TEXTINDEX},
+    search::{result::*, search_field::should_filter, *},
+    util::StringAdd,
+};
+use fst::{IntoStreamer, Streamer};
+use std::{cmp::Ordering, sync::Arc};
+
+/// Returns all anchors which have a value in the range of the request.
+///
+/// Numeric fields are compared with the values in the `NumericColumn`, all other fields by their terms in the fst.
+/// Every hit gets the same score, the boost of the request or 1.0.
+pub fn get_anchors_in_range(persistence: &Persistence, request: &RequestRangePart, filter: &Option<Arc<FilterResult>>) -> Result<SearchFieldResult, VelociError> {
+    debug_time!("{} range {}", &request.path, request);
+    if request.gt.is_none() && request.gte.is_none() && request.lt.is_none() && request.lte.is_none() {
+        return Err(VelociError::InvalidRequest {
+            message: format!("range query on {:?} requires at least one of gt, gte, lt or lte", request.path),
+        });
+    }
+
+    let mut anchor_ids = if let Some(column) = persistence.indices.numeric_values.get(&request.path.add(NUMERIC_VALUES)) {
+        get_numeric_anchors_in_range(column, request)?
+    } else {
+        get_text_anchors_in_range(persistence, request)?
+    };
+    anchor_ids.retain(|anchor_id| !should_filter(filter, *anchor_id) && !persistence.is_deleted(*anchor_id));
+
+    let score = request.boost.map(|boost| boost.into_inner()).unwrap_or(1.0);
+    let mut result = SearchFieldResult::default();
+    result.request = RequestSearchPart {
+        path: request.path.to_string(),
+        terms: vec![request.to_string()],
+        options: request.options.clone(),
+        ..Default::default()
+    };
+    result.hits_scores = anchor_ids.iter().map(|anchor_id| Hit::new(*anchor_id, score)).collect();
+    if request.is_explain() {
+        for anchor_id in &anchor_ids {
+            result.explain.insert(
+                *anchor_id,
+                vec![Explain::Range {
+                    score,
+                    range: request.to_string(),
+                }],
+            );
+        }
+    }
+    result.hits_ids = anchor_ids;
+    info!("{:?}\thits for range {}", result.hits_ids.len(), request);
+    Ok(result)
+}
+
+fn get_numeric_anchors_in_range(column: &NumericColumn, request: &RequestRangePart) -> Result<Vec<u32>, VelociError> {
+    let to_numeric = |bound: &Option<RangeValue>| -> Result<Option<NumericValue>, VelociError> {
+        match bound {
+            None => Ok(None),
+            Some(RangeValue::Number(val)) => Ok(Some(*val)),
+            Some(RangeValue::Text(text)) => column.parse(text).map(Some).ok_or_else(|| VelociError::InvalidRequest {
+                message: format!("range bound {:?} is not a valid {:?} value for field {:?}", text, column.numeric_type, request.path),
+            }),
+        }
+    };
+    let (gt, gte, lt, lte) = (to_numeric(&request.gt)?, to_numeric(&request.gte)?, to_numeric(&request.lt)?, to_numeric(&request.lte)?);
+    let is_below = |val: &NumericValue| {
+        gt.map(|bound| val.cmp_numeric(&bound) != Ordering::Greater).unwrap_or(false) || gte.map(|bound| val.cmp_numeric(&bound) == Ordering::Less).unwrap_or(false)
+    };
+    let is_above = |val: &NumericValue| {
+        lt.map(|bound| val.cmp_numeric(&bound) != Ordering::Less).unwrap_or(false) || lte.map(|bound| val.cmp_numeric(&bound) == Ordering::Greater).unwrap_or(false)
+    };
+
+    Ok(column.get_anchors_in_range(is_below, is_above))
+}
+
+fn get_text_anchors_in_range(persistence: &Persistence, request: &RequestRangePart) -> Result<Vec<u32>, VelociError> {
+    let path = request.path.add(TEXTINDEX);
+    // the field may only exist in other segments
+    let map = match persistence.indices.fst.get(&path) {
+        Some(map) => map,
+        None => return Ok(vec![]),
+    };
+
+    let (gt, gte, lt, lte) = (
+        request.gt.as_ref().map(|bound| bound.to_string()),
+        request.gte.as_ref().map(|bound| bound.to_string()),
+        request.lt.as_ref().map(|bound| bound.to_string()),
+        request.lte.as_ref().map(|bound| bound.to_string()),
+    );
+    // if both bounds of a side are set, the stricter one applies, same as for numeric fields
+    let mut range = map.range();
+    match (gt.as_ref(), gte.as_ref()) {
+        (Some(gt), Some(gte)) if gte > gt => range = range.ge(gte),
+        (Some(gt), _) => range = range.gt(gt),
+        (None, Some(gte)) => range = range.ge(gte),
+        (None, None) => {}
+    }
+    match (lt.as_ref(), lte.as_ref()) {
+        (Some(lt), Some(lte)) if lte < lt => range = range.le(lte),
+        (Some(lt), _) => range = range.lt(lt),
+        (None, Some(lte)) => range = range.le(lte),
+        (None, None) => {}
+    }
+
+    let token_to_anchor_score = persistence.get_token_to_anchor(&path)?;
+    let mut anchor_ids = vec![];
+    let mut stream = range.into_stream();
+    while let Some((_term, term_id)) = stream.next() {
+        anchor_ids.extend(token_to_anchor_score.get_score_iter(term_id as u32).map(|el| el.id));
+    }
+    anchor_ids.sort_unstable();
+    anchor_ids.dedup();
+    Ok(anchor_ids)
+}
@@ -1,6 +1,8 @@
 use crate::{
     error::VelociError,
-    persistence::{Persistence, PARENT_TO_VALUE_ID},
+    indices::NumericColumn,
+    metadata::NumericType,
+    persistence::{Persistence, NUMERIC_VALUES, PARENT_TO_VALUE_ID},
     search::*,
     util::NodeTree,
 };
@@ -19,18 +21,25 @@ pub fn read_tree(persistence: &Persistence, id: u32, tree: &NodeTree) -> Result<
                 let is_array = prop.ends_with("[]");
                 match *sub_tree {
                     NodeTree::IsLeaf => {
-                        if is_array {
+                        if let Some(column) = get_root_numeric_column(persistence, prop) {
+                            let values: Vec<serde_json::Value> = column.get_values(id).map(serde_json::Value::from).collect();
+                            if is_array && !values.is_empty() {
+                                json[extract_prop_name(prop)] = json!(values);
+                            } else if let Some(value) = values.into_iter().next() {
+                                json[extract_prop_name(prop)] = value;
+                            }
+                        } else if is_array {
                             if let Some(sub_ids) = join_for_1_to_n(persistence, id, &current_path)? {
                                 let mut sub_data = vec![];
                                 for sub_id in sub_ids {
                                     if let Some(texto) = join_and_get_text_for_ids(persistence, sub_id, prop)? {
-                                        sub_data.push(json!(texto));
+                                        sub_data.push(text_to_json(persistence, prop, texto));
                                     }
                                 }
                                 json[extract_prop_name(prop)] = json!(sub_data);
                             }
                         } else if let Some(texto) = join_and_get_text_for_ids(persistence, id, prop)? {
-                            json[extract_prop_name(prop)] = json!(texto);
+                            json[extract_prop_name(prop)] = text_to_json(persistence, prop, texto);
                         }
                     }
                     NodeTree::Map(ref _next) => {
@@ -57,3 +66,29 @@ pub fn read_tree(persistence: &Persistence, id: u32, tree: &NodeTree) -> Result<
 
     Ok(json)
 }
+
+/// The numeric column of a field on the root level, where the id in `read_tree` is the anchor id
+fn get_root_numeric_column<'a>(persistence: &'a Persistence, prop: &str) -> Option<&'a NumericColumn> {
+    if prop.contains('.') {
+        return None;
+    }
+    get_numeric_column(persistence, prop)
+}
+
+/// Dates are returned as they were indexed, so their column is ignored
+fn get_numeric_column<'a>(persistence: &'a Persistence, prop: &str) -> Option<&'a NumericColumn> {
+    persistence
+        .indices
+        .numeric_values
+        .get(&prop.add(NUMERIC_VALUES))
+        .filter(|column| column.numeric_type != NumericType::Date)
+}
+
+/// Values of numeric fields are returned as json numbers
+fn text_to_json(persistence: &Persistence, prop: &str, text: String) -> serde_json::Value {
+    let column = get_numeric_column(persistence, prop);
+    if let Some(value) = column.and_then(|column| column.numeric_type.parse(&text)) {
+        return value.into();
+    }
+    json!(text)
+}
@@ -0,0 +1,37 @@
+use crate::search::StatsRequest;
+
+/// Term aggregation with nested aggregations per bucket, e.g. the top tags and per tag the top languages and price stats.
+///
+/// ```json
+/// {"field": "tags[]", "top": 5, "aggregations": [{"field": "languages[]"}], "stats": [{"field": "price"}]}
+/// ```
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct AggregationRequest {
+    pub field: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default = "default_top")]
+    pub top: Option<usize>,
+    /// Aggregations on the hits of each bucket
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub aggregations: Vec<AggregationRequest>,
+    /// Stats on the hits of each bucket
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub stats: Vec<StatsRequest>,
+}
+
+impl AggregationRequest {
+    /// Removes the top limit recursively, to get complete counts for merging segments
+    pub(crate) fn without_top(&self) -> AggregationRequest {
+        AggregationRequest {
+            top: None,
+            aggregations: self.aggregations.iter().map(|aggregation| aggregation.without_top()).collect(),
+            ..self.clone()
+        }
+    }
+}
+
+fn default_top() -> Option<usize> {
+    Some(10)
+}
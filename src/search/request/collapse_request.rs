@@ -0,0 +1,13 @@
+/// Keeps only the best hit per distinct value of a field, see `Request::collapse`
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct CollapseRequest {
+    /// The field to group the hits by, for fields with multiple values per document the first value is used. Hits without a value are not collapsed.
+    pub field: String,
+    /// The number of hits returned per group in `CollapseGroup::hits`, including the best hit
+    #[serde(default = "default_top_hits")]
+    pub top_hits: usize,
+}
+
+fn default_top_hits() -> usize {
+    1
+}
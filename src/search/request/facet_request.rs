@@ -1,9 +1,111 @@
+use crate::indices::DateInterval;
+
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub struct FacetRequest {
     pub field: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default = "default_top")]
     pub top: Option<usize>,
+    /// Counts the hits per day, month or year on a date field. The buckets are sorted chronologically, `top` is ignored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub date_histogram: Option<DateInterval>,
+    /// Counts the hits per range on a numeric or boost field, e.g. `[{"to": 10}, {"from": 10, "to": 50}, {"from": 50}]`.
+    ///
+    /// `from` is inclusive and `to` exclusive. The buckets are returned in the order of the ranges, `top` is ignored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub ranges: Option<Vec<FacetRange>>,
+    /// Counts the hits per fixed interval on a numeric or boost field, e.g. `{"interval": 10}`.
+    ///
+    /// Only intervals with hits are returned, sorted by their start, `top` is ignored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub histogram: Option<HistogramOptions>,
+    /// Counts the hits per child of a path on a field with the `hierarchy` option, e.g. `{"parent": "Books"}` for `Books/Fiction` and `Books/Poetry`.
+    ///
+    /// Without a `parent` the top level is returned. The options of term facets like `order` and `top` apply.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub hierarchy: Option<HierarchyFacet>,
+    /// Names of `named_filters` of the request, which are not applied on the hits for this facet
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub exclude_filters: Vec<String>,
+    /// Order of the buckets of a term facet, by default the buckets with the highest count come first
+    #[serde(skip_serializing_if = "FacetOrder::is_count")]
+    #[serde(default)]
+    pub order: FacetOrder,
+    /// Buckets of a term facet with fewer hits are not returned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub min_count: Option<usize>,
+    /// Returns only the buckets of a term facet, whose text matches, e.g. `{"prefix": "red"}` or `{"regex": "re.*"}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub include: Option<FacetInclude>,
+    /// Counts texts which differ only by case in the same bucket of a term facet, the bucket texts are lower case
+    #[serde(skip_serializing_if = "crate::search::skip_false")]
+    #[serde(default)]
+    pub ignore_case: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FacetOrder {
+    /// Highest count first, equal counts are sorted alphabetically
+    #[default]
+    Count,
+    Alphabetical,
+    /// Sorts the texts by their numeric value, texts which are not numbers come last
+    Numeric,
+}
+
+impl FacetOrder {
+    fn is_count(&self) -> bool {
+        *self == FacetOrder::Count
+    }
+}
+
+/// Filter on the texts of a term facet. A regex has to match the whole text.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FacetInclude {
+    Prefix(String),
+    Regex(String),
+}
+
+/// A range of a range facet, the key defaults to `from-to`, e.g. `10-50` or `*-10`
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct FacetRange {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub from: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub to: Option<f64>,
+}
+
+/// Computes min, max, sum, avg and count of the values of a numeric or boost field over all hits
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct StatsRequest {
+    pub field: String,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct HierarchyFacet {
+    /// The path of which the children are returned, e.g. `Books/Fiction`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub parent: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct HistogramOptions {
+    pub interval: f64,
 }
 
 fn default_top() -> Option<usize> {
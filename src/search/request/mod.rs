@@ -1,13 +1,26 @@
+pub mod aggregation_request;
 pub mod boost_request;
+pub mod collapse_request;
 pub mod facet_request;
 pub mod search_request;
 pub mod snippet_info;
+pub mod sort_request;
 
 use crate::search::*;
+pub use aggregation_request::*;
 pub use boost_request::*;
+pub use collapse_request::*;
 pub use facet_request::*;
 pub use search_request::*;
 pub use snippet_info::*;
+pub use sort_request::*;
+
+/// A filter with a name, which can be excluded by facets
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NamedFilter {
+    pub name: String,
+    pub filter: SearchRequest,
+}
 
 /// Internal and External structure for requests. Suitable for easy requests.
 /// For more complex requests, e.g. with phrase boost, currently the convenience api `query_generator` is recommended.
@@ -31,6 +44,16 @@ pub struct Request {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub facets: Option<Vec<FacetRequest>>,
 
+    /// Will return the buckets of the term aggregations with their nested aggregations in `SearchResult.aggregations`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub aggregations: Option<Vec<AggregationRequest>>,
+
+    /// Will return min, max, sum, avg and count of the values of the hits in `SearchResult.stats` for the specified fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub stats: Option<Vec<StatsRequest>>,
+
     /// list of requests tuples to phrase boost
     #[serde(skip_serializing_if = "Option::is_none")]
     pub phrase_boosts: Option<Vec<RequestPhraseBoost>>,
@@ -44,6 +67,28 @@ pub struct Request {
     /// filter does not affect the score, it just filters the result
     pub filter: Option<Box<SearchRequest>>,
 
+    /// Filters like `filter`, but facets can ignore them with `exclude_filters`, e.g. to show the counts of the other values of a selected facet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub named_filters: Option<Vec<NamedFilter>>,
+
+    /// Filters the hits after facets, stats and aggregations are computed, so they are not affected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub post_filter: Option<Box<SearchRequest>>,
+
+    /// Sort the hits by field values instead of the score, the first entry has the highest priority
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<Vec<RequestSortPart>>,
+
+    /// Keeps only the best hit per distinct value of a field, e.g. one edition per `ISMORIDCODE`.
+    ///
+    /// `num_hits`, `top` and `skip` refer to the groups. The size and the best hits of a group are returned in `SearchResult.collapse`.
+    /// Facets, stats and aggregations are computed on all hits.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub collapse: Option<CollapseRequest>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default = "default_top")]
     pub top: Option<usize>,
@@ -52,6 +97,13 @@ pub struct Request {
     #[serde(default = "default_skip")]
     pub skip: Option<usize>,
 
+    /// Returns the hits after the `cursor` of a previous `SearchResult`, for paging without `skip`.
+    ///
+    /// The request must have the same query and sort as the one of the cursor.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub search_after: Option<String>,
+
     /// Enables highlighting to show where in the text the tokens have hit.
     ///
     /// Applies only for searches.
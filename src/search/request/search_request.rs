@@ -1,6 +1,10 @@
-use crate::search::request::{boost_request::RequestBoostPart, snippet_info::SnippetInfo};
+use crate::{
+    indices::{GeoPoint, NumericValue},
+    search::request::{boost_request::RequestBoostPart, snippet_info::SnippetInfo},
+};
 use core::cmp::Ordering;
 use ordered_float::OrderedFloat;
+use std::fmt;
 
 /// Internal and External structure for defining the search requests tree.
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -10,12 +14,29 @@ pub enum SearchRequest {
     And(SearchTree),
     /// Search on a field
     Search(RequestSearchPart),
+    /// Exact phrase on a field, requires the positional index (feature `PhraseQuery`)
+    Phrase(RequestPhrasePart),
+    /// Terms within a distance of each other on a field, requires the positional index (feature `PhraseQuery`)
+    Proximity(RequestProximityPart),
+    /// Range query on a field
+    Range(RequestRangePart),
+    /// Documents with a geo point within a distance
+    #[serde(rename = "geo_distance")]
+    GeoDistance(RequestGeoDistancePart),
 }
 
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub struct SearchTree {
     /// list of subqueries
     pub queries: Vec<SearchRequest>,
+    /// Documents matching any of these subqueries are removed from the result of `queries`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub must_not: Vec<SearchRequest>,
+    /// Documents matching any of these subqueries get the score added, but they are not required to match
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub should: Vec<SearchRequest>,
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Options which should be applied on the subqueries
@@ -28,14 +49,14 @@ impl SearchRequest {
             // Pull up Or Conditions
             SearchRequest::Or(subtree) => {
                 // move the tree down first, to do a complete simplify
-                for sub_query in &mut subtree.queries {
+                for sub_query in subtree.queries.iter_mut().chain(subtree.must_not.iter_mut()).chain(subtree.should.iter_mut()) {
                     sub_query.simplify();
                 }
                 let mut sub_ors = Vec::new();
                 for i in (0..subtree.queries.len()).rev() {
                     match &subtree.queries[i] {
-                        // We can only simplify if options are none
-                        SearchRequest::Or(req) if req.options.is_none() => match subtree.queries.remove(i) {
+                        // We can only simplify if options, must_not and should are none
+                        SearchRequest::Or(req) if req.options.is_none() && req.must_not.is_empty() && req.should.is_empty() => match subtree.queries.remove(i) {
                             SearchRequest::Or(search_tree) => sub_ors.extend(search_tree.queries),
                             _ => unreachable!(),
                         },
@@ -48,7 +69,7 @@ impl SearchRequest {
             // Pull up And Conditions
             SearchRequest::And(subtree) => {
                 // move the tree down first, to do a complete simplify
-                for sub_query in &mut subtree.queries {
+                for sub_query in subtree.queries.iter_mut().chain(subtree.must_not.iter_mut()).chain(subtree.should.iter_mut()) {
                     sub_query.simplify();
                 }
 
@@ -56,8 +77,8 @@ impl SearchRequest {
 
                 for i in (0..subtree.queries.len()).rev() {
                     match &subtree.queries[i] {
-                        // We can only simplify if options are none
-                        SearchRequest::And(req) if req.options.is_none() => match subtree.queries.remove(i) {
+                        // We can only simplify if options, must_not and should are none
+                        SearchRequest::And(req) if req.options.is_none() && req.must_not.is_empty() && req.should.is_empty() => match subtree.queries.remove(i) {
                             SearchRequest::And(search_tree) => sub_ands.extend(search_tree.queries),
                             _ => unreachable!(),
                         },
@@ -68,6 +89,10 @@ impl SearchRequest {
                 subtree.queries.extend(sub_ands);
             }
             SearchRequest::Search(_req) => {}
+            SearchRequest::Phrase(_req) => {}
+            SearchRequest::Proximity(_req) => {}
+            SearchRequest::Range(_req) => {}
+            SearchRequest::GeoDistance(_req) => {}
         }
     }
 
@@ -76,6 +101,10 @@ impl SearchRequest {
             SearchRequest::Or(SearchTree { options, .. }) => options,
             SearchRequest::And(SearchTree { options, .. }) => options,
             SearchRequest::Search(el) => &el.options,
+            SearchRequest::Phrase(el) => &el.options,
+            SearchRequest::Proximity(el) => &el.options,
+            SearchRequest::Range(el) => &el.options,
+            SearchRequest::GeoDistance(el) => &el.options,
         }
     }
 
@@ -84,6 +113,10 @@ impl SearchRequest {
             SearchRequest::Or(SearchTree { options, .. }) => options,
             SearchRequest::And(SearchTree { options, .. }) => options,
             SearchRequest::Search(el) => &mut el.options,
+            SearchRequest::Phrase(el) => &mut el.options,
+            SearchRequest::Proximity(el) => &mut el.options,
+            SearchRequest::Range(el) => &mut el.options,
+            SearchRequest::GeoDistance(el) => &mut el.options,
         }
     }
 
@@ -139,6 +172,17 @@ pub struct RequestSearchPart {
     #[serde(default)]
     pub is_regex: bool,
 
+    /// The term is a wildcard pattern, `*` matches any number of chars and `?` exactly one char
+    ///
+    /// e.g. "ha?s*" would match "haus" and "hausboot"
+    #[serde(skip_serializing_if = "is_false")]
+    #[serde(default)]
+    pub is_wildcard: bool,
+
+    /// Maximum number of terms a wildcard or regex search expands to. Wildcard searches default to `DEFAULT_MAX_EXPANSIONS`, regex searches are not capped by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_expansions: Option<usize>,
+
     /// TODO document
     #[serde(skip_serializing_if = "Option::is_none")]
     pub token_value: Option<RequestBoostPart>,
@@ -184,7 +228,173 @@ impl RequestSearchPart {
     }
 
     pub fn short_dbg_info(&self) -> String {
-        format!("{:?} in {:?} (isRegex:{},starts_with:{})", self.terms[0], self.path, self.is_regex, self.starts_with)
+        format!(
+            "{:?} in {:?} (isRegex:{},isWildcard:{},starts_with:{})",
+            self.terms[0], self.path, self.is_regex, self.is_wildcard, self.starts_with
+        )
+    }
+}
+
+/// Exact phrase query on a field, e.g. `{"path": "title", "phrase": "red apple pie"}`.
+///
+/// Matches only texts which contain the tokens of the phrase at consecutive positions.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct RequestPhrasePart {
+    pub path: String,
+    pub phrase: String,
+
+    /// Matches the tokens cases insensitive, default is to ignore case
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ignore_case: Option<bool>,
+
+    /// boosts the phrase with this value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boost: Option<OrderedFloat<f32>>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<SearchRequestOptions>,
+}
+
+impl RequestPhrasePart {
+    pub fn is_explain(&self) -> bool {
+        self.options.as_ref().map(|o| o.explain).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for RequestPhrasePart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{:?}", self.path, self.phrase)
+    }
+}
+
+/// Proximity query on a field, e.g. `{"path": "title", "terms": ["apple", "pie"], "distance": 5}`.
+///
+/// Matches only texts which contain the tokens of the terms in any order, with at most `distance` other tokens between them.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct RequestProximityPart {
+    pub path: String,
+    pub terms: Vec<String>,
+    /// The number of other tokens allowed between the terms
+    #[serde(default)]
+    pub distance: u32,
+
+    /// Matches the tokens cases insensitive, default is to ignore case
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ignore_case: Option<bool>,
+
+    /// boosts the proximity query with this value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boost: Option<OrderedFloat<f32>>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<SearchRequestOptions>,
+}
+
+impl RequestProximityPart {
+    pub fn is_explain(&self) -> bool {
+        self.options.as_ref().map(|o| o.explain).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for RequestProximityPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{:?}~{}", self.path, self.terms, self.distance)
+    }
+}
+
+/// Range query on a field, e.g. `{"path": "price", "gte": 10, "lt": 20}`.
+///
+/// On numeric fields the values are compared as numbers, on other fields the terms are compared lexicographically.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct RequestRangePart {
+    pub path: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gt: Option<RangeValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gte: Option<RangeValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lt: Option<RangeValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lte: Option<RangeValue>,
+
+    /// Every hit in the range gets this score, default is 1.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boost: Option<OrderedFloat<f32>>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<SearchRequestOptions>,
+}
+
+impl RequestRangePart {
+    pub fn is_explain(&self) -> bool {
+        self.options.as_ref().map(|o| o.explain).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for RequestRangePart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.path)?;
+        for (op, bound) in [(">", &self.gt), (">=", &self.gte), ("<", &self.lt), ("<=", &self.lte)] {
+            if let Some(bound) = bound {
+                write!(f, " {}{}", op, bound)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Geo distance query on a geo point field, e.g. `{"path": "location", "point": {"lat": 52.52, "lon": 13.405}, "distance_km": 10}`.
+///
+/// The score of a hit decreases linearly with the distance, from `boost` (default 1.0) at the point to 0 at `distance_km`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct RequestGeoDistancePart {
+    pub path: String,
+    pub point: GeoPoint,
+    pub distance_km: f64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boost: Option<OrderedFloat<f32>>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<SearchRequestOptions>,
+}
+
+impl RequestGeoDistancePart {
+    pub fn is_explain(&self) -> bool {
+        self.options.as_ref().map(|o| o.explain).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for RequestGeoDistancePart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: within {}km of {},{}", self.path, self.distance_km, self.point.lat, self.point.lon)
+    }
+}
+
+/// A bound of a range query, numbers are only allowed on numeric fields
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum RangeValue {
+    Number(NumericValue),
+    Text(String),
+}
+
+impl fmt::Display for RangeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeValue::Number(NumericValue::I64(val)) => write!(f, "{}", val),
+            RangeValue::Number(NumericValue::U64(val)) => write!(f, "{}", val),
+            RangeValue::Number(NumericValue::F64(val)) => write!(f, "{}", val),
+            RangeValue::Text(text) => write!(f, "{}", text),
+        }
     }
 }
 
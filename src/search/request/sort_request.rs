@@ -0,0 +1,50 @@
+use crate::indices::GeoPoint;
+use std::cmp::Ordering;
+
+/// Sorts by the score of the hits, e.g. `{"field": "_score", "order": "desc"}`
+pub const SCORE_FIELD: &str = "_score";
+
+/// Sorts the hits by the values of a field instead of the score, e.g. `{"field": "date", "order": "desc"}`
+///
+/// Numeric, date, boost, text and geo point fields are supported, as well as the score via `_score`.
+/// Text fields are sorted lexicographically by their full text.
+/// For multiple values in a document the smallest is used in ascending order and the largest in descending order.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct RequestSortPart {
+    pub field: String,
+    #[serde(default)]
+    pub order: SortOrder,
+    /// Where documents without a value are sorted, `"last"` (default) or `"first"`
+    #[serde(default)]
+    pub missing: MissingValues,
+    /// Sorts a geo point field by the distance to this point, e.g. `{"field": "location", "geo_distance": {"lat": 52.52, "lon": 13.405}}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub geo_distance: Option<GeoPoint>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingValues {
+    #[default]
+    Last,
+    First,
+}
+
+impl MissingValues {
+    /// The ordering of a document with a value to a document without
+    pub(crate) fn cmp_missing(self) -> Ordering {
+        match self {
+            MissingValues::Last => Ordering::Less,
+            MissingValues::First => Ordering::Greater,
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use crate::search::Stats;
+use fnv::FnvHashMap;
+
+/// A bucket of a term aggregation, with the nested aggregations over the hits of the bucket
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AggregationBucket {
+    pub key: String,
+    pub count: usize,
+    #[serde(skip_serializing_if = "FnvHashMap::is_empty")]
+    #[serde(default)]
+    pub aggregations: FnvHashMap<String, Vec<AggregationBucket>>,
+    #[serde(skip_serializing_if = "FnvHashMap::is_empty")]
+    #[serde(default)]
+    pub stats: FnvHashMap<String, Stats>,
+}
@@ -0,0 +1,21 @@
+use crate::search::{result::DocWithHit, Hit};
+
+/// A group of hits with the same value in the collapse field, see `Request::collapse`
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct CollapseGroup {
+    /// The value of the collapse field, `None` for a hit without a value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// The number of hits in the group
+    pub count: usize,
+    /// The best `top_hits` hits of the group, sorted like the result
+    pub hits: Vec<Hit>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct CollapseGroupWithDoc {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    pub count: usize,
+    pub hits: Vec<DocWithHit>,
+}
@@ -18,4 +18,20 @@ pub enum Explain {
         distinct_boost: u32,
         new_score: u32,
     },
+    Phrase {
+        score: f32,
+        phrase: String,
+    },
+    Proximity {
+        score: f32,
+        gaps: u32,
+    },
+    Range {
+        score: f32,
+        range: String,
+    },
+    GeoDistance {
+        score: f32,
+        distance_km: f64,
+    },
 }
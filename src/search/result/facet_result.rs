@@ -0,0 +1,81 @@
+/// The buckets of a facet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum FacetResult {
+    /// Number of hits per term, or per interval of a date histogram
+    Terms(Vec<(String, usize)>),
+    /// Number of hits per numeric range or histogram interval
+    Buckets(Vec<FacetBucket>),
+}
+
+impl FacetResult {
+    pub fn as_terms(&self) -> Option<&Vec<(String, usize)>> {
+        match self {
+            FacetResult::Terms(terms) => Some(terms),
+            FacetResult::Buckets(_) => None,
+        }
+    }
+
+    pub fn as_buckets(&self) -> Option<&Vec<FacetBucket>> {
+        match self {
+            FacetResult::Terms(_) => None,
+            FacetResult::Buckets(buckets) => Some(buckets),
+        }
+    }
+}
+
+impl PartialEq<Vec<(String, usize)>> for FacetResult {
+    fn eq(&self, other: &Vec<(String, usize)>) -> bool {
+        self.as_terms() == Some(other)
+    }
+}
+
+/// A numeric bucket, `from` is inclusive and `to` exclusive
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FacetBucket {
+    pub key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub from: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub to: Option<f64>,
+    pub count: usize,
+}
+
+impl FacetBucket {
+    pub(crate) fn contains(&self, value: f64) -> bool {
+        self.from.map(|from| value >= from).unwrap_or(true) && self.to.map(|to| value < to).unwrap_or(true)
+    }
+}
+
+/// Statistics over the values of all hits, `count` is the number of values.
+///
+/// `min`, `max` and `avg` are `None` without values.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub count: u64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub sum: f64,
+    pub avg: Option<f64>,
+}
+
+impl Stats {
+    pub(crate) fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = Some(self.min.map_or(value, |min| min.min(value)));
+        self.max = Some(self.max.map_or(value, |max| max.max(value)));
+        self.avg = Some(self.sum / self.count as f64);
+    }
+
+    /// Merges the stats of another segment
+    pub(crate) fn merge(&mut self, other: &Stats) {
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.into_iter().chain(other.min).reduce(f64::min);
+        self.max = self.max.into_iter().chain(other.max).reduce(f64::max);
+        self.avg = if self.count == 0 { None } else { Some(self.sum / self.count as f64) };
+    }
+}
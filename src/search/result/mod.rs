@@ -1,10 +1,16 @@
+mod aggregation_result;
+mod collapse_result;
 mod explain;
+mod facet_result;
 mod field_result;
 mod filter_result;
 mod search_result;
 mod search_result_with_doc;
 
+pub use aggregation_result::*;
+pub use collapse_result::*;
 pub use explain::*;
+pub use facet_result::*;
 pub use field_result::*;
 pub use filter_result::*;
 pub use search_result::*;
@@ -1,4 +1,7 @@
-use crate::search::{result::explain::Explain, Hit};
+use crate::search::{
+    result::{explain::Explain, AggregationBucket, CollapseGroup, FacetResult, Stats},
+    Hit,
+};
 use fnv::FnvHashMap;
 
 /// SearchResult` is the result form a search, without the document itself
@@ -12,9 +15,17 @@ pub struct SearchResult {
     pub data: Vec<Hit>,
     pub ids: Vec<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub facets: Option<FnvHashMap<String, Vec<(String, usize)>>>,
+    pub facets: Option<FnvHashMap<String, FacetResult>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<FnvHashMap<String, Stats>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aggregations: Option<FnvHashMap<String, Vec<AggregationBucket>>>,
     #[serde(skip_serializing_if = "FnvHashMap::is_empty")]
     pub explain: FnvHashMap<u32, Vec<Explain>>,
+    /// The group of each hit in `data` by its id, if the request has `collapse`
+    #[serde(skip_serializing_if = "FnvHashMap::is_empty")]
+    #[serde(default)]
+    pub collapse: FnvHashMap<u32, CollapseGroup>,
     #[serde(skip_serializing_if = "FnvHashMap::is_empty")]
     pub why_found_info: FnvHashMap<u32, FnvHashMap<String, Vec<String>>>,
     #[serde(skip_serializing_if = "FnvHashMap::is_empty")]
@@ -23,4 +34,7 @@ pub struct SearchResult {
     ///
     /// The other solution is to read all tokens of a document and rebuild the document while highlighting. This is much more costly.
     pub why_found_terms: FnvHashMap<String, Vec<String>>,
+    /// Cursor of the last hit, pass it as `search_after` to get the next page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
 }
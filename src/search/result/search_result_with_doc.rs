@@ -1,4 +1,7 @@
-use crate::search::{result::explain::Explain, Hit};
+use crate::search::{
+    result::{explain::Explain, AggregationBucket, CollapseGroupWithDoc, FacetResult, Stats},
+    Hit,
+};
 use fnv::FnvHashMap;
 
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
@@ -7,7 +10,14 @@ pub struct SearchResultWithDoc {
     pub num_hits: u64,
     pub data: Vec<DocWithHit>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub facets: Option<FnvHashMap<String, Vec<(String, usize)>>>,
+    pub facets: Option<FnvHashMap<String, FacetResult>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<FnvHashMap<String, Stats>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aggregations: Option<FnvHashMap<String, Vec<AggregationBucket>>>,
+    /// Cursor of the last hit, pass it as `search_after` to get the next page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
 }
 
 // impl SearchResultWithDoc {
@@ -28,6 +38,10 @@ pub struct DocWithHit {
     pub explain: Option<Vec<Explain>>,
     #[serde(skip_serializing_if = "FnvHashMap::is_empty")]
     pub why_found: FnvHashMap<String, Vec<String>>,
+    /// The group of the hit, if the request has `collapse`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub collapse: Option<CollapseGroupWithDoc>,
 }
 
 impl std::fmt::Display for DocWithHit {
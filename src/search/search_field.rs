@@ -8,7 +8,7 @@ use crate::{
     util::{self, StringAdd},
 };
 use fnv::FnvHashMap;
-use fst::{automaton::*, raw::Fst, IntoStreamer};
+use fst::{automaton::*, raw::Fst, IntoStreamer, Streamer};
 use itertools::Itertools;
 use ordered_float::OrderedFloat;
 use rayon::prelude::*;
@@ -24,6 +24,9 @@ pub type TermId = u32;
 pub type Score = f32;
 pub type BoostVal = f32;
 
+/// Maximum number of terms a wildcard search expands to, if not set via `max_expansions`
+pub const DEFAULT_MAX_EXPANSIONS: usize = 10_000;
+
 fn get_default_score_for_distance(distance: u8, prefix_matches: bool) -> f32 {
     if prefix_matches {
         2.0 / ((f32::from(distance) + 1.0).log2() + 0.2)
@@ -51,35 +54,70 @@ pub fn ord_to_term<T: AsRef<[u8]>>(fst: &Fst<T>, mut ord: u64, bytes: &mut Vec<u
 }
 
 #[inline]
-fn get_text_lines_with_automat<F, D: AsRef<[u8]>, A: Automaton>(map: &fst::Map<D>, dfa: A, mut fun: F) -> Result<(), VelociError>
+fn get_text_lines_with_automat<F, D: AsRef<[u8]>, A: Automaton>(map: &fst::Map<D>, dfa: A, max_terms: Option<usize>, mut fun: F) -> Result<(), VelociError>
 where
     F: FnMut(String, u32),
 {
-    let stream = map.search(&dfa).into_stream();
-    let hits = stream.into_str_vec()?;
-
-    for (term, id) in hits {
-        fun(term, id as u32);
+    let mut stream = map.search(&dfa).into_stream();
+    let mut num_terms = 0;
+    while let Some((term, id)) = stream.next() {
+        if max_terms.map(|max_terms| num_terms >= max_terms).unwrap_or(false) {
+            warn!("expansion stopped after {} terms", num_terms);
+            break;
+        }
+        fun(str::from_utf8(term)?.to_string(), id as u32);
+        num_terms += 1;
     }
     Ok(())
 }
 
+/// Converts a wildcard pattern to an anchored regex, `*` matches any number of chars and `?` exactly one char
+fn wildcard_to_regex(pattern: &str) -> String {
+    let mut regex = String::with_capacity(pattern.len() + 4);
+    for cha in pattern.chars() {
+        match cha {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            _ => regex.push_str(&regex::escape(cha.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex
+}
+
 #[inline]
 fn get_text_lines_from_fst<F, D: AsRef<[u8]>>(options: &RequestSearchPart, map: &fst::Map<D>, fun: F) -> Result<(), VelociError>
 where
     F: FnMut(String, u32),
 {
-    if options.is_regex {
+    if options.is_regex || options.is_wildcard {
         use regex_automata::dense;
-        let dfa = dense::Builder::new()
-            .case_insensitive(options.ignore_case.unwrap_or(true))
-            .build(&options.terms[0])
-            .unwrap();
+        // regex searches are only capped when `max_expansions` is set
+        let max_terms = if options.is_wildcard {
+            Some(options.max_expansions.unwrap_or(DEFAULT_MAX_EXPANSIONS))
+        } else {
+            options.max_expansions
+        };
+        let dfa = if options.is_wildcard {
+            dense::Builder::new()
+                .anchored(true)
+                .case_insensitive(options.ignore_case.unwrap_or(true))
+                .build(&wildcard_to_regex(&options.terms[0]))
+                .map_err(|err| VelociError::InvalidRequest {
+                    message: format!("invalid wildcard pattern {:?}: {}", options.terms[0], err),
+                })?
+        } else {
+            dense::Builder::new()
+                .case_insensitive(options.ignore_case.unwrap_or(true))
+                .build(&options.terms[0])
+                .map_err(|err| VelociError::InvalidRequest {
+                    message: format!("invalid regex {:?}: {}", options.terms[0], err),
+                })?
+        };
         // get_text_lines_with_automat(map, dfa, fun)?;
         if options.starts_with {
-            get_text_lines_with_automat(map, dfa.starts_with(), fun)?;
+            get_text_lines_with_automat(map, dfa.starts_with(), max_terms, fun)?;
         } else {
-            get_text_lines_with_automat(map, dfa, fun)?;
+            get_text_lines_with_automat(map, dfa, max_terms, fun)?;
         };
     } else {
         let lev = {
@@ -89,9 +127,9 @@ where
         };
 
         if options.starts_with {
-            get_text_lines_with_automat(map, lev.starts_with(), fun)?;
+            get_text_lines_with_automat(map, lev.starts_with(), None, fun)?;
         } else {
-            get_text_lines_with_automat(map, lev, fun)?;
+            get_text_lines_with_automat(map, lev, None, fun)?;
         };
     }
 
@@ -140,16 +178,58 @@ fn test_get_text_lines_from_fst_regex_search_with_starts_with() {
     assert_eq!(hits.first(), Some(&"awesome".to_string()));
 }
 
+#[test]
+fn test_get_text_lines_from_fst_invalid_regex() {
+    let map = fst::Map::from_iter(vec![("awesome", 1)]).unwrap();
+    let res = get_text_lines_from_fst(
+        &RequestSearchPart {
+            is_regex: true,
+            terms: vec!["(awesome".to_string()],
+            ..Default::default()
+        },
+        &map,
+        |_: String, _: u32| {},
+    );
+    assert!(matches!(res, Err(VelociError::InvalidRequest { .. })));
+}
+
+#[test]
+fn test_get_text_lines_from_fst_wildcard_search() {
+    let map = fst::Map::from_iter(vec![("haus", 1), ("hausboot", 2), ("hauus", 3), ("rathaus", 4)]).unwrap();
+    let search = |pattern: &str, max_expansions: Option<usize>| {
+        let mut hits = vec![];
+        get_text_lines_from_fst(
+            &RequestSearchPart {
+                is_wildcard: true,
+                terms: vec![pattern.to_string()],
+                max_expansions,
+                ..Default::default()
+            },
+            &map,
+            |text: String, _: u32| hits.push(text),
+        )
+        .unwrap();
+        hits
+    };
+
+    assert_eq!(search("haus*", None), vec!["haus", "hausboot"]);
+    assert_eq!(search("ha?s", None), vec!["haus"]);
+    assert_eq!(search("*haus", None), vec!["haus", "rathaus"]);
+    assert_eq!(search("H*S", None), vec!["haus", "hauus"]);
+    assert_eq!(search("h*", Some(2)), vec!["haus", "hausboot"]);
+    assert_eq!(search("h.u*", None), Vec::<String>::new());
+}
+
 #[inline]
 fn get_text_lines<F>(persistence: &Persistence, options: &RequestSearchPart, fun: F) -> Result<(), VelociError>
 where
     F: FnMut(String, u32),
 {
-    let map = persistence
-        .indices
-        .fst
-        .get(&options.path)
-        .ok_or_else(|| VelociError::FstNotFound(options.path.to_string()))?;
+    // the field may only exist in other segments
+    let map = match persistence.indices.fst.get(&options.path) {
+        Some(map) => map,
+        None => return Ok(()),
+    };
 
     get_text_lines_from_fst(options, map, fun)?;
     Ok(())
@@ -197,9 +277,11 @@ pub fn suggest_multi(persistence: &Persistence, req: Request) -> Result<SuggestF
         .suggest
         .ok_or_else(|| VelociError::StringError("only suggest allowed in suggest function".to_string()))?;
 
-    let search_results: Result<Vec<_>, VelociError> = search_parts
+    let segments: Vec<&Persistence> = persistence.get_segments().map(|(_, segment)| segment).collect();
+    let search_results: Result<Vec<_>, VelociError> = segments
         .into_par_iter()
-        .map(|search_part| {
+        .flat_map(|segment| search_parts.par_iter().map(move |search_part| (segment, search_part.clone())))
+        .map(|(segment, search_part)| {
             // if search_part.token_value.is_none() { //Apply top skip directly if there is no token_boosting, which alters the result afterwards.
             //     search_part.top = top;
             //     search_part.skip = skip;
@@ -211,7 +293,15 @@ pub fn suggest_multi(persistence: &Persistence, req: Request) -> Result<SuggestF
                 return_term_lowercase: true,
                 ..Default::default()
             };
-            get_term_ids_in_field(persistence, &mut search_part)
+            let mut result = get_term_ids_in_field(segment, &mut search_part)?;
+            if segment.has_deleted_docs() && segment.has_token_to_anchor(&search_part.request.path) {
+                // only suggest terms, which are still in a not deleted document
+                let token_to_anchor_score = segment.get_token_to_anchor(&search_part.request.path)?;
+                result
+                    .hits_scores
+                    .retain(|hit| token_to_anchor_score.get_score_iter(hit.id).any(|anchor| !segment.is_deleted(anchor.id)));
+            }
+            Ok(result)
         })
         .collect();
     info_time!("suggest text_id result to vec/sort");
@@ -413,6 +503,11 @@ pub fn resolve_token_to_anchor(
     debug_time!("{} token to anchor", &options.path);
     let mut anchor_ids_hits = vec![];
 
+    // the field may only exist in other segments
+    if !persistence.indices.fst.contains_key(&options.path) {
+        return Ok(res);
+    }
+
     let token_to_anchor_score = persistence.get_token_to_anchor(&options.path)?;
     {
         debug_time!("{} tokens.to_anchor_id_score", &options.path);
@@ -537,7 +632,7 @@ pub fn get_id_text_map_for_ids(persistence: &Persistence, path: &str, ids: &[u32
 }
 
 #[inline]
-fn should_filter(filter: &Option<Arc<FilterResult>>, id: u32) -> bool {
+pub(crate) fn should_filter(filter: &Option<Arc<FilterResult>>, id: u32) -> bool {
     filter
         .as_ref()
         .map(|filter| match **filter {
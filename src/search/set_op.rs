@@ -344,6 +344,111 @@ fn test_intersect_score_hits_with_ids() {
     assert_eq!(res.hits_scores, vec![Hit::new(0, 20.0), Hit::new(10, 20.0)]);
 }
 
+/// Removes all hits from the result, which are contained in any of the excluded results
+pub fn difference_hits(mut result: SearchFieldResult, excluded: Vec<SearchFieldResult>) -> SearchFieldResult {
+    let mut excluded_ids: Vec<u32> = excluded
+        .iter()
+        .flat_map(|res| res.hits_ids.iter().cloned().chain(res.hits_scores.iter().map(|hit| hit.id)))
+        .collect();
+    if excluded_ids.is_empty() {
+        return result;
+    }
+    excluded_ids.sort_unstable();
+    excluded_ids.dedup();
+
+    result.hits_scores.retain(|hit| excluded_ids.binary_search(&hit.id).is_err());
+    result.hits_ids.retain(|id| excluded_ids.binary_search(id).is_err());
+    result
+}
+
+#[test]
+fn difference_hits_test() {
+    let res = difference_hits(
+        SearchFieldResult {
+            hits_scores: vec![Hit::new(10, 20.0), Hit::new(0, 20.0), Hit::new(5, 20.0)],
+            ..Default::default()
+        },
+        vec![
+            SearchFieldResult {
+                hits_ids: vec![10, 3],
+                ..Default::default()
+            },
+            SearchFieldResult {
+                hits_scores: vec![Hit::new(5, 1.0)],
+                ..Default::default()
+            },
+        ],
+    );
+    assert_eq!(res.hits_scores, vec![Hit::new(0, 20.0)]);
+
+    let res = difference_hits(
+        SearchFieldResult {
+            hits_ids: vec![0, 3, 10, 20],
+            ..Default::default()
+        },
+        vec![SearchFieldResult {
+            hits_ids: vec![20, 3],
+            ..Default::default()
+        }],
+    );
+    assert_eq!(res.hits_ids, vec![0, 10]);
+}
+
+/// Adds the scores of the optional results to the hits of the result, hits only in the optional results are ignored
+pub fn add_optional_hits_score(result: SearchFieldResult, optional: Vec<SearchFieldResult>) -> SearchFieldResult {
+    if optional.is_empty() {
+        return result;
+    }
+    let mut results = vec![result, union_hits_score(optional)];
+    let term_id_hits_in_field = merge_term_id_hits(&mut results);
+    let term_text_in_field = merge_term_id_texts(&mut results);
+    let optional = results.pop().unwrap();
+    let mut result = results.pop().unwrap();
+
+    let optional_scores: FnvHashMap<u32, f32> = optional.hits_scores.iter().map(|hit| (hit.id, hit.score)).collect();
+    for hit in &mut result.hits_scores {
+        if let Some(score) = optional_scores.get(&hit.id) {
+            hit.score += score;
+            if let Some(exp) = optional.explain.get(&hit.id) {
+                result.explain.entry(hit.id).or_default().extend_from_slice(exp);
+            }
+        }
+    }
+    result.term_id_hits_in_field = term_id_hits_in_field;
+    result.term_text_in_field = term_text_in_field;
+    result
+}
+
+#[test]
+fn add_optional_hits_score_test() {
+    let res = add_optional_hits_score(
+        SearchFieldResult {
+            hits_scores: vec![Hit::new(10, 20.0), Hit::new(0, 20.0), Hit::new(5, 20.0)],
+            ..Default::default()
+        },
+        vec![
+            SearchFieldResult {
+                hits_scores: vec![Hit::new(3, 1.0), Hit::new(5, 1.0)],
+                request: RequestSearchPart {
+                    terms: vec!["a".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            SearchFieldResult {
+                hits_scores: vec![Hit::new(5, 2.0), Hit::new(10, 2.0)],
+                request: RequestSearchPart {
+                    terms: vec!["b".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ],
+    );
+    // 5 gets the score of the union of the optional hits, which rewards matching multiple terms
+    assert_eq!(res.hits_scores, vec![Hit::new(10, 22.0), Hit::new(0, 20.0), Hit::new(5, 32.0)]);
+}
+
 fn check_score_iter_for_id(iter_n_current: &mut (impl Iterator<Item = Hit>, Hit), current_id: u32) -> bool {
     if (iter_n_current.1).id == current_id {
         return true;
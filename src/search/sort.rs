@@ -1,5 +1,17 @@
-use crate::search::{sort_by_score_and_id, Hit};
+use crate::{
+    error::VelociError,
+    facet::get_text_ids_for_anchor,
+    indices::NumericValue,
+    persistence::*,
+    search::{
+        cursor::{Cursor, CursorValue},
+        search_field::get_text_for_id,
+        sort_by_score_and_id, Hit, RequestSortPart, SortOrder, SCORE_FIELD,
+    },
+    util::{get_steps_to_anchor, StringAdd},
+};
 use core::cmp::Ordering;
+use std::collections::BinaryHeap;
 
 #[inline]
 pub(crate) fn top_n_sort(data: Vec<Hit>, top_n: u32) -> Vec<Hit> {
@@ -21,6 +33,297 @@ pub(crate) fn top_n_sort(data: Vec<Hit>, top_n: u32) -> Vec<Hit> {
     new_data
 }
 
+/// The `search_after` cursor for a segment, whose anchor ids start at `offset`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SearchAfter<'a> {
+    pub(crate) cursor: &'a Cursor,
+    pub(crate) offset: u32,
+}
+
+impl SearchAfter<'_> {
+    /// Whether a hit of the segment comes after the cursor, when sorted by score
+    pub(crate) fn is_after(&self, hit: &Hit) -> bool {
+        sort_by_score_and_id(&Hit::new(hit.id + self.offset, hit.score), &self.cursor.hit()) == Ordering::Greater
+    }
+}
+
+/// Sorts the hits by the values of the sort fields, hits with equal values are sorted by score and id.
+///
+/// The ids can be global anchor ids, the values are read from the segment of the id.
+/// With `top_n` only the best `top_n` hits are kept in a heap, instead of sorting all hits.
+/// With `search_after` only the hits after the cursor are kept.
+pub(crate) fn sort_by_fields(persistence: &Persistence, hits: Vec<Hit>, sort: &[RequestSortPart], top_n: Option<u32>, search_after: Option<SearchAfter<'_>>) -> Vec<Hit> {
+    let fields: Vec<SortField<'_>> = sort.iter().map(SortField::new).collect();
+    let after = search_after.map(|search_after| {
+        let values: Vec<Option<SortValue<'_>>> = search_after
+            .cursor
+            .values
+            .iter()
+            .map(|value| {
+                value.as_ref().map(|value| match value {
+                    CursorValue::Number(number) => SortValue::Number(*number),
+                    CursorValue::Text(text) => SortValue::Str(text),
+                })
+            })
+            .collect();
+        (values, search_after)
+    });
+    let entries = hits
+        .into_iter()
+        .map(|hit| {
+            let (segment, segment_id) = persistence.get_segment_for_id(hit.id);
+            let values = fields.iter().map(|field| field.get_value(segment, segment_id, hit.score)).collect();
+            SortEntry { values, hit, fields: &fields }
+        })
+        .filter(|entry| match after.as_ref() {
+            Some((values, search_after)) => cmp_sort_values(&entry.values, values, &fields)
+                .then_with(|| sort_by_score_and_id(&Hit::new(entry.hit.id + search_after.offset, entry.hit.score), &search_after.cursor.hit()))
+                .is_gt(),
+            None => true,
+        });
+
+    let sorted = if let Some(top_n) = top_n.map(|top_n| top_n as usize) {
+        // the heap has the worst hit on top, so it can be replaced by a better one
+        let mut heap = BinaryHeap::with_capacity(top_n + 1);
+        for entry in entries {
+            if heap.len() < top_n {
+                heap.push(entry);
+            } else if let Some(mut worst) = heap.peek_mut() {
+                if entry < *worst {
+                    *worst = entry;
+                }
+            }
+        }
+        heap.into_sorted_vec()
+    } else {
+        let mut entries: Vec<SortEntry<'_>> = entries.collect();
+        entries.sort_unstable();
+        entries
+    };
+    sorted.into_iter().map(|entry| entry.hit).collect()
+}
+
+/// Creates the cursor to continue after the hit, the id of the hit is a global anchor id
+pub(crate) fn create_cursor(persistence: &Persistence, hit: &Hit, sort: Option<&[RequestSortPart]>) -> Cursor {
+    let (segment, segment_id) = persistence.get_segment_for_id(hit.id);
+    let values = sort
+        .unwrap_or_default()
+        .iter()
+        .map(|part| {
+            let field = SortField::new(part);
+            field.get_value(segment, segment_id, hit.score).map(|value| match value {
+                SortValue::Number(number) => CursorValue::Number(number),
+                SortValue::Text { segment, path, ordinal } => CursorValue::Text(get_text_for_id(segment, path, ordinal)),
+                SortValue::Str(text) => CursorValue::Text(text.to_string()),
+            })
+        })
+        .collect();
+    Cursor {
+        values,
+        score: hit.score,
+        id: hit.id,
+    }
+}
+
+/// Checks that the cursor has a value for every sort field
+pub(crate) fn check_cursor(cursor: &Cursor, sort: Option<&[RequestSortPart]>) -> Result<(), VelociError> {
+    let num_sort_fields = sort.map(|sort| sort.len()).unwrap_or(0);
+    if cursor.values.len() != num_sort_fields {
+        return Err(VelociError::InvalidRequest {
+            message: format!(
+                "search_after cursor has {} sort values, but the request has {} sort fields",
+                cursor.values.len(),
+                num_sort_fields
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Checks that every sort field is `_score` or has a numeric, boost or text index in at least one segment
+pub(crate) fn check_sort_fields(persistence: &Persistence, sort: &[RequestSortPart]) -> Result<(), VelociError> {
+    for part in sort {
+        if part.field == SCORE_FIELD {
+            continue;
+        }
+        if part.geo_distance.is_some() {
+            if !persistence.get_segments().any(|(_, segment)| segment.has_geo_column(&part.field)) {
+                return Err(VelociError::InvalidRequest {
+                    message: format!("can not sort by distance on {:?}, it is not a geo point field", part.field),
+                });
+            }
+            continue;
+        }
+        let is_sortable = |segment: &Persistence| {
+            segment.has_numeric_column(&part.field)
+                || segment.indices.boost_valueid_to_value.contains_key(&part.field.add(BOOST_VALID_TO_VALUE))
+                || segment.indices.fst.contains_key(&part.field.add(TEXTINDEX))
+        };
+        if !persistence.get_segments().any(|(_, segment)| is_sortable(segment)) {
+            return Err(VelociError::InvalidRequest {
+                message: format!("can not sort by {:?}, it is not a numeric, date, boost or text field", part.field),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// A sort part with the paths to its indices
+#[derive(Debug)]
+struct SortField<'a> {
+    part: &'a RequestSortPart,
+    numeric_path: String,
+    boost_path: String,
+    text_path: String,
+    steps: Vec<String>,
+}
+
+impl<'a> SortField<'a> {
+    fn new(part: &'a RequestSortPart) -> Self {
+        SortField {
+            part,
+            numeric_path: part.field.add(NUMERIC_VALUES),
+            boost_path: part.field.add(BOOST_VALID_TO_VALUE),
+            text_path: part.field.add(TEXTINDEX),
+            steps: get_steps_to_anchor(&part.field),
+        }
+    }
+
+    /// The value of a document, in a multi value field the smallest value is used in ascending order and the largest in descending order
+    fn get_value(&'a self, segment: &'a Persistence, segment_id: u32, score: f32) -> Option<SortValue<'a>> {
+        let part = self.part;
+        if part.field == SCORE_FIELD {
+            return Some(SortValue::Number(NumericValue::F64(f64::from(score))));
+        }
+        if let Some(point) = part.geo_distance.as_ref() {
+            let column = segment.indices.geo_values.get(&part.field.add(GEO_VALUES))?;
+            let distance = match part.order {
+                SortOrder::Asc => column.get_min_distance_km(segment_id, point),
+                SortOrder::Desc => column.get_max_distance_km(segment_id, point),
+            };
+            return distance.map(NumericValue::F64).map(SortValue::Number);
+        }
+        if let Some(column) = segment.indices.numeric_values.get(&self.numeric_path) {
+            let values = column.get_values(segment_id);
+            let value = match part.order {
+                SortOrder::Asc => values.min_by(|a, b| a.cmp_numeric(b)),
+                SortOrder::Desc => values.max_by(|a, b| a.cmp_numeric(b)),
+            };
+            return value.map(SortValue::Number);
+        }
+        if let Some(boost) = segment.indices.boost_valueid_to_value.get(&self.boost_path) {
+            return boost
+                .get_value(u64::from(segment_id))
+                .map(|bits| SortValue::Number(NumericValue::F64(f64::from(f32::from_bits(bits)))));
+        }
+        if segment.indices.fst.contains_key(&self.text_path) {
+            // the text ids are the ordinals in the fst, so they are sorted like the texts
+            let text_ids = self.get_text_ids(segment, segment_id)?;
+            let ordinal = match part.order {
+                SortOrder::Asc => text_ids.into_iter().min(),
+                SortOrder::Desc => text_ids.into_iter().max(),
+            }?;
+            return Some(SortValue::Text {
+                segment,
+                path: &self.text_path,
+                ordinal,
+            });
+        }
+        None
+    }
+
+    fn get_text_ids(&self, segment: &Persistence, segment_id: u32) -> Option<Vec<u32>> {
+        get_text_ids_for_anchor(segment, &self.steps, segment_id).ok().filter(|text_ids| !text_ids.is_empty())
+    }
+}
+
+#[derive(Debug)]
+enum SortValue<'a> {
+    Number(NumericValue),
+    /// Ordinals can be compared directly in the same segment, otherwise the texts are compared
+    Text {
+        segment: &'a Persistence,
+        path: &'a str,
+        ordinal: u32,
+    },
+    /// A text from a cursor
+    Str(&'a str),
+}
+
+impl SortValue<'_> {
+    fn cmp_value(&self, other: &SortValue<'_>) -> Ordering {
+        match (self, other) {
+            (SortValue::Number(a), SortValue::Number(b)) => a.cmp_numeric(b),
+            (SortValue::Number(_), _) => Ordering::Less,
+            (_, SortValue::Number(_)) => Ordering::Greater,
+            (SortValue::Str(a), SortValue::Str(b)) => a.cmp(b),
+            (SortValue::Text { segment, path, ordinal }, SortValue::Str(text)) => get_text_for_id(segment, path, *ordinal).as_str().cmp(text),
+            (SortValue::Str(text), SortValue::Text { segment, path, ordinal }) => text.cmp(&get_text_for_id(segment, path, *ordinal).as_str()),
+            (
+                SortValue::Text { segment, path, ordinal },
+                SortValue::Text {
+                    segment: other_segment,
+                    ordinal: other_ordinal,
+                    ..
+                },
+            ) => {
+                if std::ptr::eq(*segment, *other_segment) {
+                    ordinal.cmp(other_ordinal)
+                } else {
+                    get_text_for_id(segment, path, *ordinal).cmp(&get_text_for_id(other_segment, path, *other_ordinal))
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SortEntry<'a> {
+    values: Vec<Option<SortValue<'a>>>,
+    hit: Hit,
+    fields: &'a [SortField<'a>],
+}
+
+/// Better hits are smaller
+impl Ord for SortEntry<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_sort_values(&self.values, &other.values, self.fields).then_with(|| sort_by_score_and_id(&self.hit, &other.hit))
+    }
+}
+
+impl PartialOrd for SortEntry<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SortEntry<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SortEntry<'_> {}
+
+/// Missing values are sorted according to the `missing` policy of the sort part, independent of the sort order
+fn cmp_sort_values(a: &[Option<SortValue<'_>>], b: &[Option<SortValue<'_>>], fields: &[SortField<'_>]) -> Ordering {
+    for ((a, b), field) in a.iter().zip(b.iter()).zip(fields.iter()) {
+        let ordering = match (a, b) {
+            (Some(a), Some(b)) => match field.part.order {
+                SortOrder::Asc => a.cmp_value(b),
+                SortOrder::Desc => b.cmp_value(a),
+            },
+            (Some(_), None) => field.part.missing.cmp_missing(),
+            (None, Some(_)) => field.part.missing.cmp_missing().reverse(),
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
 #[inline]
 pub(crate) fn check_apply_top_n_sort<T: std::fmt::Debug>(new_data: &mut Vec<T>, top_n: u32, sort_compare: &dyn Fn(&T, &T) -> Ordering, new_worst: &mut dyn FnMut(&T)) {
     if !new_data.is_empty() && new_data.len() as u32 == top_n + 200 {
@@ -161,6 +161,11 @@ pub(crate) fn get_steps_to_anchor(path: &str) -> Vec<String> {
     paths
 }
 
+/// Returns the field with all path prefixes of a field with the `hierarchy` option, e.g. `category__path_prefixes[]` for `category` or `category[]`
+pub fn get_path_prefixes_field(field: &str) -> String {
+    format!("{}__path_prefixes[]", field.strip_suffix("[]").unwrap_or(field))
+}
+
 #[allow(unused_macros)]
 macro_rules! print_json {
     ($e:expr) => {
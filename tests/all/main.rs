@@ -12,11 +12,31 @@ extern crate more_asserts;
 
 #[macro_use]
 mod common;
+mod test_aggregation;
 mod test_code_search;
+mod test_collapse;
+mod test_date;
+mod test_delete;
+mod test_disjunctive_facets;
+mod test_facet_buckets;
+mod test_facet_options;
+mod test_geo;
+mod test_hierarchy_facets;
+mod test_merge;
+mod test_must_not;
+mod test_numeric;
 mod test_phrase;
+mod test_phrase_query;
+mod test_primary_key;
+mod test_proximity;
 mod test_query_generator;
+mod test_range;
 mod test_scores;
+mod test_segments;
+mod test_sort;
+mod test_stats;
 mod test_why_found;
+mod test_wildcard;
 mod tests;
 mod tests_facet;
 mod tests_large;
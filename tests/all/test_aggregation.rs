@@ -0,0 +1,90 @@
+use serde_json::Value;
+use veloci::{search::AggregationBucket, *};
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_aggregation";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["price"]
+    numeric = "f64"
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        create::add_segment_from_str(
+            &mut pers,
+            &json!({ "title": "movie four", "tags": ["action"], "languages": ["en", "fr"], "price": 8 }).to_string(),
+            INDICES,
+        )
+        .unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "movie one", "tags": ["action", "comedy"], "languages": ["en"], "price": 10 },
+        { "title": "movie two", "tags": ["comedy"], "languages": ["de", "en"], "price": 4 },
+        { "title": "movie three", "tags": ["action", "drama"], "languages": ["de"], "price": 6 },
+        { "title": "book five", "tags": ["drama"], "languages": ["en"], "price": 20 }
+    ])
+}
+
+fn get_aggregation(aggregation: Value) -> Vec<AggregationBucket> {
+    let req = json!({
+        "search_req": { "search": { "terms": ["movie"], "path": "title" } },
+        "aggregations": [aggregation]
+    });
+    let hits = search_testo_to_doc!(req);
+    hits.aggregations.unwrap().values().next().unwrap().clone()
+}
+
+fn keys_and_counts(buckets: &[AggregationBucket]) -> Vec<(&str, usize)> {
+    buckets.iter().map(|bucket| (bucket.key.as_str(), bucket.count)).collect()
+}
+
+#[test]
+fn aggregation_without_nesting() {
+    let buckets = get_aggregation(json!({ "field": "tags[]" }));
+    assert_eq!(keys_and_counts(&buckets), vec![("action", 3), ("comedy", 2), ("drama", 1)]);
+}
+
+#[test]
+fn nested_term_aggregation() {
+    let buckets = get_aggregation(json!({ "field": "tags[]", "top": 2, "aggregations": [{ "field": "languages[]" }] }));
+    assert_eq!(keys_and_counts(&buckets), vec![("action", 3), ("comedy", 2)]);
+    assert_eq!(keys_and_counts(&buckets[0].aggregations["languages[]"]), vec![("en", 2), ("de", 1), ("fr", 1)]);
+    assert_eq!(keys_and_counts(&buckets[1].aggregations["languages[]"]), vec![("en", 2), ("de", 1)]);
+}
+
+#[test]
+fn nested_aggregation_top_is_applied_per_bucket() {
+    let buckets = get_aggregation(json!({ "field": "tags[]", "top": 1, "aggregations": [{ "field": "languages[]", "top": 1 }] }));
+    assert_eq!(keys_and_counts(&buckets), vec![("action", 3)]);
+    assert_eq!(keys_and_counts(&buckets[0].aggregations["languages[]"]), vec![("en", 2)]);
+}
+
+#[test]
+fn stats_per_bucket() {
+    let buckets = get_aggregation(json!({ "field": "tags[]", "stats": [{ "field": "price" }] }));
+    let action_stats = &buckets[0].stats["price"];
+    assert_eq!((action_stats.count, action_stats.min, action_stats.max), (3, Some(6.0), Some(10.0)));
+    assert_eq!(action_stats.avg, Some(8.0));
+    let drama_stats = &buckets[2].stats["price"];
+    assert_eq!((drama_stats.count, drama_stats.sum), (1, 6.0));
+}
+
+#[test]
+fn three_levels() {
+    let buckets = get_aggregation(json!({
+        "field": "languages[]",
+        "aggregations": [{ "field": "tags[]", "aggregations": [{ "field": "title" }] }]
+    }));
+    assert_eq!(keys_and_counts(&buckets), vec![("en", 3), ("de", 2), ("fr", 1)]);
+    let en_tags = &buckets[0].aggregations["tags[]"];
+    assert_eq!(keys_and_counts(en_tags), vec![("action", 2), ("comedy", 2)]);
+    assert_eq!(keys_and_counts(&en_tags[0].aggregations["title"]), vec![("movie four", 1), ("movie one", 1)]);
+}
@@ -128,6 +128,13 @@ fn token_code_search_disable_parser_query_generator() {
         no_parentheses: true,
         no_attributes: true,
         no_levensthein: true,
+        no_negation: true,
+        no_required: true,
+        no_proximity: true,
+        no_wildcard: false,
+        no_boost: true,
+        no_range: true,
+        lenient: false,
     });
     params.search_term = "*myfun(param1: Type1)*".to_string();
 
@@ -0,0 +1,145 @@
+use serde_json::Value;
+use veloci::*;
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_collapse";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["work"]
+    fulltext.tokenize = false
+["price"]
+    numeric = "f64"
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        let segment = [
+            json!({ "title": "crime stories reprint", "work": "w2", "price": 9 }).to_string(),
+            json!({ "title": "crime thriller", "work": "w3", "price": 20 }).to_string(),
+        ];
+        create::add_segment_from_str(&mut pers, &segment.join("\n"), INDICES).unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "crime novel first edition", "work": "w1", "price": 10 },
+        { "title": "crime novel second edition", "work": "w1", "price": 12 },
+        { "title": "crime stories", "work": "w2", "price": 8 },
+        { "title": "crime poems", "price": 5 },
+        { "title": "crime novel paperback", "work": "w1", "price": 7 }
+    ])
+}
+
+fn crime_search(extra: Value) -> search::SearchResultWithDoc {
+    let mut req = json!({
+        "search_req": { "search": { "terms": ["crime"], "path": "title" } },
+        "sort": [{ "field": "price" }],
+        "collapse": { "field": "work" }
+    });
+    for (key, value) in extra.as_object().unwrap() {
+        req[key] = value.clone();
+    }
+    search_testo_to_doc!(req)
+}
+
+fn titles(docs: &[search::DocWithHit]) -> Vec<String> {
+    docs.iter().map(|doc| doc.doc["title"].as_str().unwrap().to_string()).collect()
+}
+
+fn groups(hits: &search::SearchResultWithDoc) -> Vec<(Option<String>, usize)> {
+    hits.data
+        .iter()
+        .map(|doc| {
+            let group = doc.collapse.as_ref().unwrap();
+            (group.value.clone(), group.count)
+        })
+        .collect()
+}
+
+#[test]
+fn collapse_keeps_best_hit_per_value() {
+    let hits = crime_search(json!({}));
+    assert_eq!(titles(&hits.data), vec!["crime poems", "crime novel paperback", "crime stories", "crime thriller"]);
+    assert_eq!(hits.num_hits, 4);
+    assert_eq!(
+        groups(&hits),
+        vec![(None, 1), (Some("w1".to_string()), 3), (Some("w2".to_string()), 2), (Some("w3".to_string()), 1)]
+    );
+}
+
+#[test]
+fn collapse_best_hit_follows_sort_order() {
+    let hits = crime_search(json!({ "sort": [{ "field": "price", "order": "desc" }] }));
+    assert_eq!(
+        titles(&hits.data),
+        vec!["crime thriller", "crime novel second edition", "crime stories reprint", "crime poems"]
+    );
+}
+
+#[test]
+fn collapse_top_hits_per_group() {
+    let hits = crime_search(json!({ "collapse": { "field": "work", "top_hits": 2 } }));
+    let group_titles: Vec<Vec<String>> = hits.data.iter().map(|doc| titles(&doc.collapse.as_ref().unwrap().hits)).collect();
+    assert_eq!(
+        group_titles,
+        vec![
+            vec!["crime poems"],
+            vec!["crime novel paperback", "crime novel first edition"],
+            vec!["crime stories", "crime stories reprint"],
+            vec!["crime thriller"],
+        ]
+    );
+}
+
+#[test]
+fn collapse_top_and_skip_apply_to_groups() {
+    let hits = crime_search(json!({ "top": 2, "skip": 1 }));
+    assert_eq!(titles(&hits.data), vec!["crime novel paperback", "crime stories"]);
+    assert_eq!(hits.num_hits, 4);
+    assert_eq!(groups(&hits), vec![(Some("w1".to_string()), 3), (Some("w2".to_string()), 2)]);
+}
+
+#[test]
+fn collapse_with_search_after() {
+    let first_page = crime_search(json!({ "top": 2 }));
+    assert_eq!(titles(&first_page.data), vec!["crime poems", "crime novel paperback"]);
+
+    let second_page = crime_search(json!({ "top": 2, "search_after": first_page.cursor.unwrap() }));
+    assert_eq!(titles(&second_page.data), vec!["crime stories", "crime thriller"]);
+}
+
+#[test]
+fn collapse_with_search_after_does_not_repeat_groups_of_other_segments() {
+    // "w2" is shown with "crime stories", "crime stories reprint" of the second segment comes after the cursor
+    let first_page = crime_search(json!({ "top": 3 }));
+    assert_eq!(titles(&first_page.data), vec!["crime poems", "crime novel paperback", "crime stories"]);
+
+    let second_page = crime_search(json!({ "top": 3, "search_after": first_page.cursor.unwrap() }));
+    assert_eq!(titles(&second_page.data), vec!["crime thriller"]);
+}
+
+#[test]
+fn collapse_without_sort() {
+    let req = json!({
+        "search_req": { "search": { "terms": ["novel"], "path": "title" } },
+        "collapse": { "field": "work" }
+    });
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(hits.num_hits, 1);
+    assert_eq!(groups(&hits), vec![(Some("w1".to_string()), 3)]);
+}
+
+#[test]
+fn collapse_on_unknown_field_is_invalid() {
+    let requesto: search::Request = serde_json::from_value(json!({
+        "search_req": { "search": { "terms": ["crime"], "path": "title" } },
+        "collapse": { "field": "edition" }
+    }))
+    .unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+}
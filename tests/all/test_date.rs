@@ -0,0 +1,185 @@
+use serde_json::Value;
+use veloci::{indices::NumericValue, *};
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_date";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["published"]
+    date = {}
+    fulltext.tokenize = false
+["updated"]
+    date = { format = "%Y%m%d" }
+["price"]
+    numeric = "f64"
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        create::add_segment_from_str(
+            &mut pers,
+            &json!({ "title": "new book", "published": "2021-03-01T10:00:00Z", "updated": "20210301", "price": 20 }).to_string(),
+            INDICES,
+        )
+        .unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "old book", "published": "1999-12-31", "updated": "20000101", "price": 5 },
+        { "title": "blue book", "published": "2020-01-15T08:30:00", "updated": "20200115", "price": 12.5 },
+        { "title": "red book", "published": "2020-01-20", "updated": "20200115", "price": 7 },
+        { "title": "no date book", "price": 1 }
+    ])
+}
+
+fn search_titles(req: Value) -> Vec<String> {
+    let hits = search_testo_to_doc!(req);
+    hits.data.iter().map(|doc| doc.doc["title"].as_str().unwrap().to_string()).collect()
+}
+
+#[test]
+fn date_column_values() {
+    let column = TEST_PERSISTENCE.get_numeric_column("published").unwrap();
+    assert_eq!(column.get_value(0), Some(NumericValue::I64(946_598_400_000)));
+    assert_eq!(column.get_value(3), None);
+
+    let column = TEST_PERSISTENCE.get_numeric_column("updated").unwrap();
+    assert_eq!(column.get_value(0), Some(NumericValue::I64(946_684_800_000)));
+}
+
+#[test]
+fn read_data_returns_original_date() {
+    let fields = ["title".to_string(), "published".to_string()];
+    let doc = search::read_data(&TEST_PERSISTENCE, 1, &fields).unwrap();
+    assert_eq!(doc, json!({ "title": "blue book", "published": "2020-01-15T08:30:00" }));
+}
+
+#[test]
+fn date_range() {
+    let req = json!({
+        "search_req": { "range": { "path": "published", "gte": "2020-01-01", "lt": "2021-01-01" } },
+        "sort": [{ "field": "published" }]
+    });
+    assert_eq!(search_titles(req), vec!["blue book", "red book"]);
+
+    let req = json!({
+        "search_req": { "search": { "terms": ["book"], "path": "title" } },
+        "filter": { "range": { "path": "updated", "gt": "2020-02-01" } }
+    });
+    assert_eq!(search_titles(req), vec!["new book"]);
+}
+
+#[test]
+fn date_range_in_query() {
+    let mut params = query_generator::SearchQueryGeneratorParameters::default();
+    params.search_term = "published:>=2020-01-20".to_string();
+    let mut titles: Vec<String> = search_testo_to_doco_qp!(params)
+        .data
+        .iter()
+        .map(|doc| doc.doc["title"].as_str().unwrap().to_string())
+        .collect();
+    titles.sort();
+    assert_eq!(titles, vec!["new book", "red book"]);
+}
+
+#[test]
+fn date_range_with_custom_format() {
+    let req = json!({
+        "search_req": { "range": { "path": "updated", "gte": "20200115", "lt": "20210301" } },
+        "sort": [{ "field": "published" }]
+    });
+    assert_eq!(search_titles(req), vec!["blue book", "red book"]);
+
+    let mut params = query_generator::SearchQueryGeneratorParameters::default();
+    params.search_term = "updated:<20200115".to_string();
+    let titles: Vec<String> = search_testo_to_doco_qp!(params)
+        .data
+        .iter()
+        .map(|doc| doc.doc["title"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(titles, vec!["old book"]);
+}
+
+#[test]
+fn sort_by_date() {
+    let req = json!({
+        "search_req": { "search": { "terms": ["book"], "path": "title" } },
+        "sort": [{ "field": "published", "order": "desc" }]
+    });
+    assert_eq!(search_titles(req), vec!["new book", "red book", "blue book", "old book", "no date book"]);
+
+    let req = json!({
+        "search_req": { "search": { "terms": ["book"], "path": "title" } },
+        "sort": [{ "field": "published" }],
+        "top": 2,
+        "skip": 1
+    });
+    assert_eq!(search_titles(req), vec!["blue book", "red book"]);
+}
+
+#[test]
+fn sort_by_multiple_fields() {
+    let req = json!({
+        "search_req": { "search": { "terms": ["book"], "path": "title" } },
+        "sort": [{ "field": "updated", "order": "desc" }, { "field": "price" }]
+    });
+    assert_eq!(search_titles(req), vec!["new book", "red book", "blue book", "old book", "no date book"]);
+}
+
+#[test]
+fn sort_by_unknown_field_is_invalid() {
+    let requesto: search::Request = serde_json::from_value(json!({
+        "search_req": { "search": { "terms": ["book"], "path": "title" } },
+        "sort": [{ "field": "isbn" }]
+    }))
+    .unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+}
+
+#[test]
+fn date_histogram() {
+    let req = json!({
+        "search_req": { "search": { "terms": ["book"], "path": "title" } },
+        "facets": [{ "field": "published", "date_histogram": "month" }]
+    });
+    let hits = search_testo_to_doc!(req);
+    let facets = hits.facets.unwrap();
+    assert_eq!(
+        facets.get("published").unwrap(),
+        &vec![("1999-12".to_string(), 1), ("2020-01".to_string(), 2), ("2021-03".to_string(), 1)]
+    );
+
+    let req = json!({
+        "search_req": { "search": { "terms": ["book"], "path": "title" } },
+        "facets": [{ "field": "updated", "date_histogram": "year" }]
+    });
+    let hits = search_testo_to_doc!(req);
+    let facets = hits.facets.unwrap();
+    assert_eq!(
+        facets.get("updated").unwrap(),
+        &vec![("2000".to_string(), 1), ("2020".to_string(), 2), ("2021".to_string(), 1)]
+    );
+}
+
+#[test]
+fn date_histogram_on_numeric_field_is_invalid() {
+    let requesto: search::Request = serde_json::from_value(json!({
+        "search_req": { "search": { "terms": ["book"], "path": "title" } },
+        "facets": [{ "field": "price", "date_histogram": "day" }]
+    }))
+    .unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+}
+
+#[test]
+fn invalid_date_value() {
+    let mut pers = persistence::Persistence::create_im().unwrap();
+    let res = create::create_indices_from_str(&mut pers, &json!({ "updated": "2020-01-01" }).to_string(), INDICES, true);
+    assert!(res.is_err());
+}
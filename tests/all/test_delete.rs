@@ -0,0 +1,115 @@
+use serde_json::Value;
+use veloci::*;
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_delete";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["tags[]"]
+    facet = true
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        let segment_data = json!({ "title": "red cherry", "tags": ["fruit", "red"] }).to_string();
+        create::add_segment_from_str(&mut pers, &segment_data, INDICES).unwrap();
+
+        pers.delete_documents(&[1]).unwrap();
+        let query: search::SearchRequest = serde_json::from_value(json!({"search": {"terms":["car"], "path": "title", "levenshtein_distance": 0}})).unwrap();
+        assert_eq!(search::delete_by_query(&mut pers, query).unwrap(), 1);
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "red apple", "tags": ["fruit", "red"] },
+        { "title": "red pear", "tags": ["fruit", "green"] },
+        { "title": "red car", "tags": ["vehicle", "red"] },
+        { "title": "blue bike", "tags": ["vehicle", "blue"] }
+    ])
+}
+
+#[test]
+fn deleted_docs_are_not_found() {
+    let req = json!({
+        "search_req": { "search": {"terms":["red"], "path": "title", "levenshtein_distance": 0} }
+    });
+
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(hits.num_hits, 2);
+    let mut ids: Vec<u32> = hits.data.iter().map(|doc| doc.hit.id).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, vec![0, 4]);
+}
+
+#[test]
+fn deleted_docs_are_not_counted_in_facets() {
+    let req = json!({
+        "search_req": { "search": {"terms":["red"], "path": "title", "levenshtein_distance": 0} },
+        "facets": [{"field":"tags[]"}]
+    });
+
+    let hits = search_testo_to_doc!(req);
+    let facets = hits.facets.unwrap();
+    assert_eq!(facets.get("tags[]").unwrap(), &vec![("fruit".to_string(), 2), ("red".to_string(), 2)]);
+
+    let facet = facet::get_facet(
+        &TEST_PERSISTENCE,
+        &search::FacetRequest {
+            field: "tags[]".to_string(),
+            top: Some(10),
+            ..Default::default()
+        },
+        &[0, 1, 2, 3],
+    )
+    .unwrap();
+    assert!(!facet.as_terms().unwrap().iter().any(|(text, _)| text == "green"));
+}
+
+#[test]
+fn deleted_docs_are_not_suggested() {
+    let req = search::Request {
+        suggest: Some(vec![search::RequestSearchPart {
+            path: "title".to_string(),
+            terms: vec!["ca".to_string()],
+            starts_with: true,
+            ..Default::default()
+        }]),
+        ..Default::default()
+    };
+    let suggestions = search_field::suggest_multi(&TEST_PERSISTENCE, req).unwrap();
+    assert!(!suggestions.iter().any(|suggestion| suggestion.0 == "car"));
+}
+
+#[test]
+fn deleted_docs_are_not_returned_as_documents() {
+    let pers = &TEST_PERSISTENCE;
+    let hits = vec![search::Hit::new(1, 1.0), search::Hit::new(3, 1.0)];
+    let docs = search::to_documents(pers, &hits, &None, &Default::default());
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0].doc["title"], "blue bike");
+}
+
+#[test]
+fn deleted_docs_are_persisted() {
+    let pers = persistence::Persistence::open(TEST_PERSISTENCE.directory.box_clone()).unwrap();
+    assert_eq!(pers.get_number_of_deleted_documents(), 2);
+    assert!(pers.is_deleted(1));
+    assert!(pers.is_deleted(2));
+    assert!(!pers.is_deleted(3));
+}
+
+#[test]
+fn delete_in_segment() {
+    let mut pers = persistence::Persistence::create_im().unwrap();
+    create::create_indices_from_str(&mut pers, &json!({ "title": "red apple" }).to_string(), INDICES, true).unwrap();
+    create::add_segment_from_str(&mut pers, &json!({ "title": "red cherry" }).to_string(), INDICES).unwrap();
+    pers.delete_documents(&[1]).unwrap();
+    assert!(!pers.is_deleted(0));
+    assert!(pers.segments[0].is_deleted(0));
+    assert!(pers.delete_documents(&[2]).is_err());
+}
@@ -0,0 +1,123 @@
+use serde_json::Value;
+use veloci::*;
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_disjunctive_facets";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["price"]
+    numeric = "f64"
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        create::add_segment_from_str(
+            &mut pers,
+            &json!({ "title": "shirt five", "color": "blue", "brand": "acme", "price": 30 }).to_string(),
+            INDICES,
+        )
+        .unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "shirt one", "color": "red", "brand": "acme", "price": 10 },
+        { "title": "shirt two", "color": "red", "brand": "zeta", "price": 20 },
+        { "title": "shirt three", "color": "blue", "brand": "zeta", "price": 15 },
+        { "title": "shirt four", "color": "green", "brand": "acme", "price": 25 }
+    ])
+}
+
+fn search_shirts(extra: Value) -> search::SearchResultWithDoc {
+    let mut req = json!({
+        "search_req": { "search": { "terms": ["shirt"], "path": "title" } },
+        "facets": [{ "field": "color" }, { "field": "brand" }]
+    });
+    for (key, value) in extra.as_object().unwrap() {
+        req[key] = value.clone();
+    }
+    search_testo_to_doc!(req)
+}
+
+fn titles(hits: &search::SearchResultWithDoc) -> Vec<String> {
+    let mut titles: Vec<String> = hits.data.iter().map(|doc| doc.doc["title"].as_str().unwrap().to_string()).collect();
+    titles.sort();
+    titles
+}
+
+fn sorted_facet(hits: &search::SearchResultWithDoc, field: &str) -> Vec<(String, usize)> {
+    let mut terms = hits.facets.as_ref().unwrap().get(field).unwrap().as_terms().unwrap().clone();
+    terms.sort();
+    terms
+}
+
+fn color_filter(color: &str) -> Value {
+    json!({ "name": "color", "filter": { "search": { "terms": [color], "path": "color" } } })
+}
+
+#[test]
+fn facet_ignores_its_own_filter() {
+    let hits = search_shirts(json!({
+        "named_filters": [color_filter("red")],
+        "facets": [{ "field": "color", "exclude_filters": ["color"] }, { "field": "brand" }]
+    }));
+    assert_eq!(titles(&hits), vec!["shirt one", "shirt two"]);
+    assert_eq!(hits.num_hits, 2);
+
+    assert_eq!(
+        sorted_facet(&hits, "color"),
+        vec![("blue".to_string(), 2), ("green".to_string(), 1), ("red".to_string(), 2)]
+    );
+    assert_eq!(sorted_facet(&hits, "brand"), vec![("acme".to_string(), 1), ("zeta".to_string(), 1)]);
+}
+
+#[test]
+fn facets_apply_other_named_filters() {
+    let brand_filter = json!({ "name": "brand", "filter": { "search": { "terms": ["acme"], "path": "brand" } } });
+    let hits = search_shirts(json!({
+        "named_filters": [color_filter("blue"), brand_filter],
+        "facets": [{ "field": "color", "exclude_filters": ["color"] }, { "field": "brand" }]
+    }));
+    assert_eq!(titles(&hits), vec!["shirt five"]);
+
+    // color ignores the color filter, but not the brand filter
+    assert_eq!(
+        sorted_facet(&hits, "color"),
+        vec![("blue".to_string(), 1), ("green".to_string(), 1), ("red".to_string(), 1)]
+    );
+    assert_eq!(sorted_facet(&hits, "brand"), vec![("acme".to_string(), 1)]);
+}
+
+#[test]
+fn post_filter_does_not_affect_facets() {
+    let hits = search_shirts(json!({ "post_filter": { "range": { "path": "price", "gte": 20 } } }));
+    assert_eq!(titles(&hits), vec!["shirt five", "shirt four", "shirt two"]);
+    assert_eq!(hits.num_hits, 3);
+
+    assert_eq!(sorted_facet(&hits, "brand"), vec![("acme".to_string(), 3), ("zeta".to_string(), 2)]);
+}
+
+#[test]
+fn post_filter_does_not_affect_stats() {
+    let hits = search_shirts(json!({
+        "post_filter": { "search": { "terms": ["zeta"], "path": "brand" } },
+        "stats": [{ "field": "price" }]
+    }));
+    assert_eq!(titles(&hits), vec!["shirt three", "shirt two"]);
+    assert_eq!(hits.stats.unwrap()["price"].count, 5);
+}
+
+#[test]
+fn unknown_excluded_filter_is_invalid() {
+    let requesto: search::Request = serde_json::from_value(json!({
+        "search_req": { "search": { "terms": ["shirt"], "path": "title" } },
+        "facets": [{ "field": "color", "exclude_filters": ["size"] }]
+    }))
+    .unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+}
@@ -0,0 +1,113 @@
+use serde_json::Value;
+use veloci::{search::FacetBucket, *};
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_facet_buckets";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["price"]
+    numeric = "f64"
+["sizes[]"]
+    numeric = "u64"
+["commonness".boost]
+    boost_type = "f32"
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        create::add_segment_from_str(
+            &mut pers,
+            &json!({ "title": "shirt large", "price": 55, "sizes": [40, 42], "commonness": 12 }).to_string(),
+            INDICES,
+        )
+        .unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "shirt cheap", "price": 5, "sizes": [36, 38], "commonness": 3 },
+        { "title": "shirt blue", "price": 10, "sizes": [38], "commonness": 15 },
+        { "title": "shirt red", "price": 49.99, "sizes": [44], "commonness": 1 },
+        { "title": "shirt no price" },
+        { "title": "hat", "price": 20 }
+    ])
+}
+
+fn get_buckets(facet: Value) -> Vec<FacetBucket> {
+    let req = json!({
+        "search_req": { "search": { "terms": ["shirt"], "path": "title" } },
+        "facets": [facet]
+    });
+    let hits = search_testo_to_doc!(req);
+    hits.facets.unwrap().values().next().unwrap().as_buckets().unwrap().clone()
+}
+
+fn keys_and_counts(buckets: &[FacetBucket]) -> Vec<(&str, usize)> {
+    buckets.iter().map(|bucket| (bucket.key.as_str(), bucket.count)).collect()
+}
+
+#[test]
+fn range_facet() {
+    let buckets = get_buckets(json!({ "field": "price", "ranges": [{ "to": 10 }, { "from": 10, "to": 50 }, { "key": "expensive", "from": 50 }, { "from": 100 }] }));
+    assert_eq!(keys_and_counts(&buckets), vec![("*-10", 1), ("10-50", 2), ("expensive", 1), ("100-*", 0)]);
+    assert_eq!(buckets[1].from, Some(10.0));
+    assert_eq!(buckets[1].to, Some(50.0));
+}
+
+#[test]
+fn range_facet_counts_documents_once() {
+    let buckets = get_buckets(json!({ "field": "sizes[]", "ranges": [{ "from": 36, "to": 40 }, { "from": 40 }] }));
+    assert_eq!(keys_and_counts(&buckets), vec![("36-40", 2), ("40-*", 2)]);
+}
+
+#[test]
+fn histogram_facet() {
+    let buckets = get_buckets(json!({ "field": "price", "histogram": { "interval": 25 } }));
+    assert_eq!(keys_and_counts(&buckets), vec![("0", 2), ("25", 1), ("50", 1)]);
+    assert_eq!((buckets[2].from, buckets[2].to), (Some(50.0), Some(75.0)));
+
+    let buckets = get_buckets(json!({ "field": "sizes[]", "histogram": { "interval": 5 } }));
+    assert_eq!(keys_and_counts(&buckets), vec![("35", 2), ("40", 2)]);
+}
+
+#[test]
+fn facets_on_boost_field() {
+    let buckets = get_buckets(json!({ "field": "commonness", "histogram": { "interval": 10 } }));
+    assert_eq!(keys_and_counts(&buckets), vec![("0", 2), ("10", 2)]);
+
+    let buckets = get_buckets(json!({ "field": "commonness", "ranges": [{ "from": 10 }] }));
+    assert_eq!(keys_and_counts(&buckets), vec![("10-*", 2)]);
+}
+
+#[test]
+fn bucket_json() {
+    let req = json!({
+        "search_req": { "search": { "terms": ["hat"], "path": "title" } },
+        "facets": [{ "field": "price", "ranges": [{ "from": 10, "to": 30 }] }]
+    });
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(
+        serde_json::to_value(hits.facets.unwrap()).unwrap(),
+        json!({ "price": [{ "key": "10-30", "from": 10.0, "to": 30.0, "count": 1 }] })
+    );
+}
+
+#[test]
+fn invalid_bucket_facets() {
+    let search = |facet: Value| {
+        let requesto: search::Request = serde_json::from_value(json!({
+            "search_req": { "search": { "terms": ["shirt"], "path": "title" } },
+            "facets": [facet]
+        }))
+        .unwrap();
+        search::search(requesto, &TEST_PERSISTENCE)
+    };
+    assert!(search(json!({ "field": "price", "histogram": { "interval": 0 } })).is_err());
+    assert!(search(json!({ "field": "title", "ranges": [{ "to": 10 }] })).is_err());
+    assert!(search(json!({ "field": "price", "ranges": [{ "to": 10 }], "histogram": { "interval": 10 } })).is_err());
+}
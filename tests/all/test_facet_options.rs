@@ -0,0 +1,119 @@
+use serde_json::Value;
+use veloci::*;
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_facet_options";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["color"]
+    facet = true
+["tags[]"]
+    facet = true
+["size"]
+    facet = true
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        create::add_segment_from_str(
+            &mut pers,
+            &json!({ "title": "shirt five", "color": "RED", "tags": ["NEW"], "size": "10" }).to_string(),
+            INDICES,
+        )
+        .unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "shirt one", "color": "Red", "tags": ["Sale", "new"], "size": "10" },
+        { "title": "shirt two", "color": "red", "tags": ["sale", "Sale"], "size": "9" },
+        { "title": "shirt three", "color": "Blue", "tags": ["new"], "size": "100" },
+        { "title": "shirt four", "color": "green", "tags": ["sale"], "size": "9" }
+    ])
+}
+
+fn get_facet(facet: Value) -> Vec<(String, usize)> {
+    let field = facet["field"].as_str().unwrap().to_string();
+    let req = json!({
+        "search_req": { "search": { "terms": ["shirt"], "path": "title" } },
+        "facets": [facet]
+    });
+    let hits = search_testo_to_doc!(req);
+    hits.facets.unwrap().remove(&field).unwrap().as_terms().unwrap().clone()
+}
+
+fn terms(expected: &[(&str, usize)]) -> Vec<(String, usize)> {
+    expected.iter().map(|(text, count)| (text.to_string(), *count)).collect()
+}
+
+#[test]
+fn facet_default_order_is_count_then_text() {
+    assert_eq!(get_facet(json!({ "field": "tags[]" })), terms(&[("Sale", 2), ("new", 2), ("sale", 2), ("NEW", 1)]));
+}
+
+#[test]
+fn facet_ignore_case_merges_buckets() {
+    assert_eq!(get_facet(json!({ "field": "color", "ignore_case": true })), terms(&[("red", 3), ("blue", 1), ("green", 1)]));
+}
+
+#[test]
+fn facet_ignore_case_counts_a_hit_once_per_bucket() {
+    // "shirt two" has the tags "sale" and "Sale"
+    assert_eq!(get_facet(json!({ "field": "tags[]", "ignore_case": true })), terms(&[("new", 3), ("sale", 3)]));
+}
+
+#[test]
+fn facet_order_alphabetical() {
+    assert_eq!(
+        get_facet(json!({ "field": "color", "order": "alphabetical" })),
+        terms(&[("Blue", 1), ("RED", 1), ("Red", 1), ("green", 1), ("red", 1)])
+    );
+    assert_eq!(
+        get_facet(json!({ "field": "color", "order": "alphabetical", "top": 2, "ignore_case": true })),
+        terms(&[("blue", 1), ("green", 1)])
+    );
+}
+
+#[test]
+fn facet_order_numeric() {
+    assert_eq!(get_facet(json!({ "field": "size", "order": "numeric" })), terms(&[("9", 2), ("10", 2), ("100", 1)]));
+}
+
+#[test]
+fn facet_min_count_is_applied_after_merging_segments() {
+    // "red" has two hits in the first segment and one in the second
+    assert_eq!(get_facet(json!({ "field": "color", "ignore_case": true, "min_count": 3 })), terms(&[("red", 3)]));
+    assert_eq!(get_facet(json!({ "field": "color", "min_count": 2 })), terms(&[]));
+}
+
+#[test]
+fn facet_include_prefix() {
+    assert_eq!(get_facet(json!({ "field": "tags[]", "include": { "prefix": "s" } })), terms(&[("sale", 2)]));
+    assert_eq!(
+        get_facet(json!({ "field": "tags[]", "include": { "prefix": "s" }, "ignore_case": true })),
+        terms(&[("sale", 3)])
+    );
+}
+
+#[test]
+fn facet_include_regex_matches_whole_text() {
+    assert_eq!(
+        get_facet(json!({ "field": "color", "include": { "regex": "gr|blue|red" }, "ignore_case": true })),
+        terms(&[("red", 3), ("blue", 1)])
+    );
+}
+
+#[test]
+fn facet_include_invalid_regex() {
+    let requesto: search::Request = serde_json::from_value(json!({
+        "search_req": { "search": { "terms": ["shirt"], "path": "title" } },
+        "facets": [{ "field": "color", "include": { "regex": "(red" } }]
+    }))
+    .unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+}
@@ -0,0 +1,144 @@
+use serde_json::Value;
+use veloci::{indices::GeoPoint, *};
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_geo";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["location"]
+    geo_point = true
+["stops[]"]
+    geo_point = true
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        create::add_segment_from_str(&mut pers, &json!({ "title": "munich city", "location": "48.1351,11.5820" }).to_string(), INDICES).unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "berlin city", "location": "52.5200,13.4050", "stops": [{ "lat": 48.8566, "lon": 2.3522 }] },
+        { "title": "potsdam city", "location": { "lat": 52.3906, "lon": 13.0645 } },
+        { "title": "paris city", "location": "48.8566,2.3522", "stops": [{ "lat": 52.5200, "lon": 13.4050 }, { "lat": 50.8503, "lon": 4.3517 }] },
+        { "title": "nowhere city" }
+    ])
+}
+
+fn search_titles(req: Value) -> Vec<String> {
+    let hits = search_testo_to_doc!(req);
+    hits.data.iter().map(|doc| doc.doc["title"].as_str().unwrap().to_string()).collect()
+}
+
+const BERLIN: &str = r#"{ "lat": 52.52, "lon": 13.405 }"#;
+
+fn berlin() -> Value {
+    serde_json::from_str(BERLIN).unwrap()
+}
+
+#[test]
+fn geo_column_values() {
+    let column = TEST_PERSISTENCE.get_segments().next().unwrap().1.get_geo_column("location").unwrap();
+    assert_eq!(column.get_points(1).collect::<Vec<_>>(), vec![GeoPoint { lat: 52.3906, lon: 13.0645 }]);
+    assert_eq!(column.get_points(3).count(), 0);
+}
+
+#[test]
+fn geo_distance_query() {
+    let req = json!({ "search_req": { "geo_distance": { "path": "location", "point": berlin(), "distance_km": 50 } } });
+    assert_eq!(search_titles(req), vec!["berlin city", "potsdam city"]);
+
+    let req = json!({ "search_req": { "geo_distance": { "path": "location", "point": berlin(), "distance_km": 600 } } });
+    assert_eq!(search_titles(req), vec!["berlin city", "potsdam city", "munich city"]);
+}
+
+#[test]
+fn geo_distance_query_on_array_matches_any_point() {
+    let req = json!({ "search_req": { "geo_distance": { "path": "stops[]", "point": berlin(), "distance_km": 10 } } });
+    assert_eq!(search_titles(req), vec!["paris city"]);
+}
+
+#[test]
+fn geo_distance_filter() {
+    let req = json!({
+        "search_req": { "search": { "terms": ["city"], "path": "title" } },
+        "filter": { "geo_distance": { "path": "location", "point": { "lat": 48.8566, "lon": 2.3522 }, "distance_km": 1000 } },
+        "sort": [{ "field": "location", "geo_distance": { "lat": 48.8566, "lon": 2.3522 } }]
+    });
+    assert_eq!(search_titles(req), vec!["paris city", "munich city", "potsdam city", "berlin city"]);
+}
+
+#[test]
+fn sort_by_distance() {
+    let req = json!({
+        "search_req": { "search": { "terms": ["city"], "path": "title" } },
+        "sort": [{ "field": "location", "geo_distance": berlin() }]
+    });
+    assert_eq!(search_titles(req), vec!["berlin city", "potsdam city", "munich city", "paris city", "nowhere city"]);
+
+    let req = json!({
+        "search_req": { "search": { "terms": ["city"], "path": "title" } },
+        "sort": [{ "field": "location", "geo_distance": berlin(), "order": "desc" }],
+        "top": 2
+    });
+    assert_eq!(search_titles(req), vec!["paris city", "munich city"]);
+}
+
+#[test]
+fn sort_by_distance_on_non_geo_field_is_invalid() {
+    let requesto: search::Request = serde_json::from_value(json!({
+        "search_req": { "search": { "terms": ["city"], "path": "title" } },
+        "sort": [{ "field": "title", "geo_distance": berlin() }]
+    }))
+    .unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+}
+
+#[test]
+fn geo_distance_boosts_nearer_documents() {
+    let req = json!({
+        "search_req": { "or": { "queries": [
+            { "search": { "terms": ["city"], "path": "title" } },
+            { "geo_distance": { "path": "location", "point": berlin(), "distance_km": 1000, "boost": 10 } }
+        ] } }
+    });
+    assert_eq!(search_titles(req), vec!["berlin city", "potsdam city", "munich city", "paris city", "nowhere city"]);
+}
+
+#[test]
+fn geo_distance_explain() {
+    let req = json!({
+        "search_req": { "geo_distance": { "path": "location", "point": berlin(), "distance_km": 100, "options": { "explain": true } } }
+    });
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(hits.data[0].doc["title"], "berlin city");
+    let explain = hits.data[0].explain.as_ref().unwrap();
+    assert!(
+        explain
+            .iter()
+            .any(|el| matches!(el, search::Explain::GeoDistance { distance_km, .. } if *distance_km < 0.01)),
+        "{:?}",
+        explain
+    );
+}
+
+#[test]
+fn geo_distance_requires_positive_distance() {
+    let requesto: search::Request = serde_json::from_value(json!({ "search_req": { "geo_distance": { "path": "location", "point": berlin(), "distance_km": 0 } } })).unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+}
+
+#[test]
+fn invalid_geo_value() {
+    let mut pers = persistence::Persistence::create_im().unwrap();
+    let res = create::create_indices_from_str(&mut pers, &json!({ "location": "north pole" }).to_string(), INDICES, true);
+    assert!(res.is_err());
+
+    let res = create::create_indices_from_str(&mut pers, &json!({ "location": { "lat": 91, "lon": 0 } }).to_string(), INDICES, true);
+    assert!(res.is_err());
+}
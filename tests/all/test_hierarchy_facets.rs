@@ -0,0 +1,147 @@
+use serde_json::Value;
+use veloci::*;
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_hierarchy_facets";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["category"]
+    hierarchy = {}
+["topics[]"]
+    hierarchy = { separator = " > " }
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        create::add_segment_from_str(
+            &mut pers,
+            &json!({ "title": "item five", "category": "Books/Fiction/Crime", "topics": ["History"] }).to_string(),
+            INDICES,
+        )
+        .unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "item one", "category": "Books/Fiction/Crime", "topics": ["Science > Physics", "Science > Chemistry"] },
+        { "title": "item two", "category": "Books/Fiction/Fantasy", "topics": ["Science > Physics > Optics"] },
+        { "title": "item three", "category": "Books/Poetry", "topics": ["History > Rome"] },
+        { "title": "item four", "category": "Music/Jazz" }
+    ])
+}
+
+fn search_items(search_req: Value, facet: Value) -> search::SearchResultWithDoc {
+    let req = json!({
+        "search_req": search_req,
+        "facets": [facet]
+    });
+    search_testo_to_doc!(req)
+}
+
+fn get_facet(facet: Value) -> Vec<(String, usize)> {
+    let field = facet["field"].as_str().unwrap().to_string();
+    let hits = search_items(json!({ "search": { "terms": ["item"], "path": "title" } }), facet);
+    hits.facets.unwrap().remove(&field).unwrap().as_terms().unwrap().clone()
+}
+
+fn terms(expected: &[(&str, usize)]) -> Vec<(String, usize)> {
+    expected.iter().map(|(text, count)| (text.to_string(), *count)).collect()
+}
+
+#[test]
+fn hierarchy_facet_top_level() {
+    assert_eq!(get_facet(json!({ "field": "category", "hierarchy": {} })), terms(&[("Books", 4), ("Music", 1)]));
+}
+
+#[test]
+fn hierarchy_facet_children() {
+    assert_eq!(
+        get_facet(json!({ "field": "category", "hierarchy": { "parent": "Books" } })),
+        terms(&[("Books/Fiction", 3), ("Books/Poetry", 1)])
+    );
+    assert_eq!(
+        get_facet(json!({ "field": "category", "hierarchy": { "parent": "Books/Fiction" } })),
+        terms(&[("Books/Fiction/Crime", 2), ("Books/Fiction/Fantasy", 1)])
+    );
+    assert_eq!(get_facet(json!({ "field": "category", "hierarchy": { "parent": "Books/Fiction/Crime" } })), terms(&[]));
+}
+
+#[test]
+fn hierarchy_facet_term_options() {
+    assert_eq!(
+        get_facet(json!({ "field": "category", "hierarchy": { "parent": "Books" }, "order": "alphabetical", "top": 1 })),
+        terms(&[("Books/Fiction", 3)])
+    );
+    assert_eq!(
+        get_facet(json!({ "field": "category", "hierarchy": { "parent": "books" }, "ignore_case": true })),
+        terms(&[("books/fiction", 3), ("books/poetry", 1)])
+    );
+}
+
+#[test]
+fn hierarchy_facet_counts_a_hit_once_per_path() {
+    // "item one" has two topics in "Science"
+    assert_eq!(get_facet(json!({ "field": "topics[]", "hierarchy": {} })), terms(&[("History", 2), ("Science", 2)]));
+    assert_eq!(
+        get_facet(json!({ "field": "topics[]", "hierarchy": { "parent": "Science" } })),
+        terms(&[("Science > Physics", 2), ("Science > Chemistry", 1)])
+    );
+}
+
+#[test]
+fn hierarchy_facet_values_are_not_changed() {
+    assert_eq!(
+        get_facet(json!({ "field": "category" })),
+        terms(&[("Books/Fiction/Crime", 2), ("Books/Fiction/Fantasy", 1), ("Books/Poetry", 1), ("Music/Jazz", 1)])
+    );
+    let hits = search_items(json!({ "search": { "terms": ["four"], "path": "title" } }), json!({ "field": "category" }));
+    assert_eq!(hits.data[0].doc, json!({ "title": "item four", "category": "Music/Jazz" }));
+}
+
+#[test]
+fn hierarchy_search_on_path_prefix() {
+    let hits = search_items(
+        json!({ "search": { "terms": ["Books/Fiction"], "path": "category__path_prefixes[]" } }),
+        json!({ "field": "category", "hierarchy": { "parent": "Books/Fiction" } }),
+    );
+    assert_eq!(hits.num_hits, 3);
+    assert_eq!(
+        hits.facets.unwrap().get("category").unwrap(),
+        &vec![("Books/Fiction/Crime".to_string(), 2), ("Books/Fiction/Fantasy".to_string(), 1)]
+    );
+}
+
+#[test]
+fn hierarchy_facet_on_field_without_hierarchy_is_invalid() {
+    let requesto: search::Request = serde_json::from_value(json!({
+        "search_req": { "search": { "terms": ["item"], "path": "title" } },
+        "facets": [{ "field": "title", "hierarchy": {} }]
+    }))
+    .unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+}
+
+#[test]
+fn hierarchy_path_prefixes_are_not_searched_by_default() {
+    let mut params = query_generator::SearchQueryGeneratorParameters::default();
+    params.search_term = "Music/Jazz".to_string();
+    params.why_found = Some(true);
+    let requesto = query_generator::search_query(&TEST_PERSISTENCE, params.clone()).unwrap();
+    assert!(!serde_json::to_string(&requesto).unwrap().contains("__path_prefixes"));
+
+    let hits = search_testo_to_doco_qp!(params);
+    assert_eq!(hits.num_hits, 1);
+    assert!(hits.data.iter().all(|hit| hit.why_found.keys().all(|field| !field.contains("__path_prefixes"))));
+}
+
+#[test]
+fn hierarchy_path_prefixes_field_in_document_is_rejected() {
+    let mut pers = persistence::Persistence::create_im().unwrap();
+    let data = json!({ "title": "item", "category": "Books/Poetry", "category__path_prefixes": ["Music"] }).to_string();
+    assert!(create::create_indices_from_str(&mut pers, &data, INDICES, true).is_err());
+}
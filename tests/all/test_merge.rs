@@ -0,0 +1,231 @@
+use serde_json::Value;
+use veloci::*;
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_merge";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["tags[]"]
+    facet = true
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        create::add_segment_from_str(&mut pers, &json!({ "title": "red cherry", "tags": ["fruit", "red"] }).to_string(), INDICES).unwrap();
+        create::add_segment_from_str(&mut pers, &json!({ "title": "red bus", "tags": ["vehicle", "red"] }).to_string(), INDICES).unwrap();
+        create::add_segment_from_str(&mut pers, &json!({ "title": "blue bike", "tags": ["vehicle", "blue"] }).to_string(), INDICES).unwrap();
+        // red bus
+        pers.delete_documents(&[3]).unwrap();
+        create::merge_segments(&mut pers, 1..4).unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "red apple", "tags": ["fruit", "red"] },
+        { "title": "green pear", "tags": ["fruit", "green"] }
+    ])
+}
+
+fn search_titles(pers: &persistence::Persistence, term: &str) -> Vec<(u32, String)> {
+    let requesto: search::Request = serde_json::from_value(json!({
+        "search_req": { "search": {"terms":[term], "path": "title", "levenshtein_distance": 0} }
+    }))
+    .unwrap();
+    let hits = search::to_search_result(pers, search::search(requesto.clone(), pers).unwrap(), &requesto.select);
+    let mut titles: Vec<(u32, String)> = hits.data.iter().map(|doc| (doc.hit.id, doc.doc["title"].as_str().unwrap().to_string())).collect();
+    titles.sort();
+    titles
+}
+
+#[test]
+fn merged_segment_drops_deleted_docs() {
+    let pers = &TEST_PERSISTENCE;
+    assert_eq!(pers.metadata.segments.len(), 1);
+    assert_eq!(pers.metadata.segments[0].num_docs, 2);
+    assert_eq!(pers.get_number_of_documents(), 4);
+    assert_eq!(pers.get_number_of_deleted_documents(), 0);
+}
+
+#[test]
+fn search_after_merge() {
+    assert_eq!(search_titles(&TEST_PERSISTENCE, "red"), vec![(0, "red apple".to_string()), (2, "red cherry".to_string())]);
+    assert_eq!(search_titles(&TEST_PERSISTENCE, "bike"), vec![(3, "blue bike".to_string())]);
+    assert_eq!(search_titles(&TEST_PERSISTENCE, "bus"), vec![]);
+}
+
+#[test]
+fn facets_after_merge() {
+    let req = json!({
+        "search_req": { "search": {"terms":["red"], "path": "title", "levenshtein_distance": 0} },
+        "facets": [{"field":"tags[]"}]
+    });
+
+    let hits = search_testo_to_doc!(req);
+    let facets = hits.facets.unwrap();
+    assert_eq!(facets.get("tags[]").unwrap(), &vec![("fruit".to_string(), 2), ("red".to_string(), 2)]);
+}
+
+#[test]
+fn reopen_after_merge() {
+    let pers = persistence::Persistence::open(TEST_PERSISTENCE.root_directory.box_clone()).unwrap();
+    assert_eq!(pers.segments.len(), 1);
+    assert_eq!(search_titles(&pers, "bike"), vec![(3, "blue bike".to_string())]);
+}
+
+#[test]
+fn deletes_between_prepare_and_commit_are_kept() {
+    let mut pers = persistence::Persistence::create_im().unwrap();
+    create::create_indices_from_str(&mut pers, &json!({ "title": "red apple" }).to_string(), INDICES, true).unwrap();
+    create::add_segment_from_str(&mut pers, &json!({ "title": "red cherry" }).to_string(), INDICES).unwrap();
+    create::add_segment_from_str(&mut pers, &json!({ "title": "red bus" }).to_string(), INDICES).unwrap();
+
+    let merge = create::prepare_merge(&pers, 1..3).unwrap();
+    pers.delete_documents(&[2]).unwrap();
+    create::commit_merge(&mut pers, merge).unwrap();
+
+    assert_eq!(pers.segments.len(), 1);
+    assert_eq!(search_titles(&pers, "red"), vec![(0, "red apple".to_string()), (1, "red cherry".to_string())]);
+}
+
+#[test]
+fn commit_fails_if_segments_changed() {
+    let mut pers = persistence::Persistence::create_im().unwrap();
+    create::create_indices_from_str(&mut pers, &json!({ "title": "red apple" }).to_string(), INDICES, true).unwrap();
+    create::add_segment_from_str(&mut pers, &json!({ "title": "red cherry" }).to_string(), INDICES).unwrap();
+    create::add_segment_from_str(&mut pers, &json!({ "title": "red bus" }).to_string(), INDICES).unwrap();
+
+    let first = create::prepare_merge(&pers, 1..3).unwrap();
+    let second = create::prepare_merge(&pers, 2..3).unwrap();
+    create::commit_merge(&mut pers, first).unwrap();
+    assert!(create::commit_merge(&mut pers, second).is_err());
+    assert_eq!(search_titles(&pers, "bus"), vec![(2, "red bus".to_string())]);
+}
+
+#[test]
+fn merge_policy() {
+    let mut pers = persistence::Persistence::create_im().unwrap();
+    create::create_indices_from_str(&mut pers, &json!({ "title": "red apple" }).to_string(), INDICES, true).unwrap();
+    for title in ["a b c", "d", "e", "f g"] {
+        let data = title.split(' ').map(|title| json!({ "title": title }).to_string()).collect::<Vec<_>>().join("\n");
+        create::add_segment_from_str(&mut pers, &data, INDICES).unwrap();
+    }
+    let policy = create::MergePolicy {
+        max_segments: 4,
+        max_deleted_ratio: 0.4,
+    };
+    assert_eq!(policy.find_merge(&pers), Some(2..4));
+
+    assert!(create::merge_by_policy(&mut pers, &policy).unwrap());
+    assert_eq!(pers.metadata.segments.iter().map(|info| info.num_docs).collect::<Vec<_>>(), vec![3, 2, 2]);
+    assert_eq!(policy.find_merge(&pers), None);
+
+    // "g", half of the last segment is deleted
+    pers.delete_documents(&[7]).unwrap();
+    assert_eq!(policy.find_merge(&pers), Some(3..4));
+    assert!(create::merge_by_policy(&mut pers, &policy).unwrap());
+    assert_eq!(pers.get_number_of_documents(), 7);
+    assert_eq!(search_titles(&pers, "d"), vec![(4, "d".to_string())]);
+    assert_eq!(search_titles(&pers, "f"), vec![(6, "f".to_string())]);
+}
+
+#[test]
+fn merge_fully_deleted_segment() {
+    let mut pers = persistence::Persistence::create_im().unwrap();
+    create::create_indices_from_str(&mut pers, &json!({ "title": "red apple" }).to_string(), INDICES, true).unwrap();
+    create::add_segment_from_str(&mut pers, &json!({ "title": "red cherry" }).to_string(), INDICES).unwrap();
+    pers.delete_documents(&[1]).unwrap();
+    create::merge_segments(&mut pers, 1..2).unwrap();
+    assert_eq!(pers.get_number_of_documents(), 1);
+    assert_eq!(search_titles(&pers, "red"), vec![(0, "red apple".to_string())]);
+}
+
+#[test]
+fn merge_initial_segment() {
+    let mut pers = persistence::Persistence::create_im().unwrap();
+    let data = ["red apple", "green pear"]
+        .iter()
+        .map(|title| json!({ "title": title }).to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    create::create_indices_from_str(&mut pers, &data, INDICES, true).unwrap();
+    create::add_segment_from_str(&mut pers, &json!({ "title": "red cherry" }).to_string(), INDICES).unwrap();
+    create::add_segment_from_str(&mut pers, &json!({ "title": "red bus" }).to_string(), INDICES).unwrap();
+    // green pear
+    pers.delete_documents(&[1]).unwrap();
+
+    create::merge_segments(&mut pers, 0..2).unwrap();
+    assert_eq!(pers.metadata.num_docs, 2);
+    assert_eq!(pers.segments.len(), 1);
+    assert_eq!(pers.get_number_of_deleted_documents(), 0);
+    assert_eq!(
+        search_titles(&pers, "red"),
+        vec![(0, "red apple".to_string()), (1, "red cherry".to_string()), (2, "red bus".to_string())]
+    );
+    assert_eq!(search_titles(&pers, "pear"), vec![]);
+
+    let mut pers = persistence::Persistence::open(pers.root_directory.box_clone()).unwrap();
+    assert_eq!(search_titles(&pers, "cherry"), vec![(1, "red cherry".to_string())]);
+
+    // the merged initial segment can be merged again
+    create::add_segment_from_str(&mut pers, &json!({ "title": "blue bike" }).to_string(), INDICES).unwrap();
+    create::merge_segments(&mut pers, 0..3).unwrap();
+    assert!(pers.segments.is_empty());
+    let pers = persistence::Persistence::open(pers.root_directory.box_clone()).unwrap();
+    assert_eq!(search_titles(&pers, "bike"), vec![(3, "blue bike".to_string())]);
+}
+
+#[test]
+fn merge_policy_removes_deleted_docs_of_initial_segment() {
+    let mut pers = persistence::Persistence::create_im().unwrap();
+    create::create_indices_from_str(&mut pers, &json!({ "title": "red apple" }).to_string(), INDICES, true).unwrap();
+    create::add_segment_from_str(&mut pers, &json!({ "title": "red cherry" }).to_string(), INDICES).unwrap();
+    let policy = create::MergePolicy::default();
+
+    // the initial segment can't be empty, so the next segment is merged too
+    pers.delete_documents(&[0]).unwrap();
+    assert_eq!(policy.find_merge(&pers), Some(0..2));
+    assert!(create::merge_segments(&mut pers, 0..1).is_err());
+    assert!(create::merge_by_policy(&mut pers, &policy).unwrap());
+    assert_eq!(pers.get_number_of_documents(), 1);
+    assert!(pers.deleted_docs.is_none());
+    assert_eq!(search_titles(&pers, "red"), vec![(0, "red cherry".to_string())]);
+
+    pers.delete_documents(&[0]).unwrap();
+    assert_eq!(policy.find_merge(&pers), None);
+}
+
+#[test]
+fn segments_with_different_index_configs_are_not_merged() {
+    let phrase_indices = r#"
+    ["*GLOBAL*"]
+        features = ["All", "PhraseQuery"]
+    "#;
+    let mut pers = persistence::Persistence::create_im().unwrap();
+    let data = ["red apple", "green pear"]
+        .iter()
+        .map(|title| json!({ "title": title }).to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    create::create_indices_from_str(&mut pers, &data, INDICES, true).unwrap();
+    create::add_segment_from_str(&mut pers, &json!({ "title": "red cherry" }).to_string(), INDICES).unwrap();
+    create::add_segment_from_str(&mut pers, &json!({ "title": "red bus" }).to_string(), phrase_indices).unwrap();
+    create::add_segment_from_str(&mut pers, &json!({ "title": "blue bike" }).to_string(), phrase_indices).unwrap();
+
+    assert!(create::merge_segments(&mut pers, 1..3).is_err());
+    let policy = create::MergePolicy {
+        max_segments: 3,
+        max_deleted_ratio: 0.3,
+    };
+    assert_eq!(policy.find_merge(&pers), Some(2..4));
+    assert!(create::merge_by_policy(&mut pers, &policy).unwrap());
+    assert_eq!(pers.segments.len(), 2);
+    assert_eq!(
+        search_titles(&pers, "red"),
+        vec![(0, "red apple".to_string()), (2, "red cherry".to_string()), (3, "red bus".to_string())]
+    );
+}
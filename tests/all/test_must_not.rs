@@ -0,0 +1,173 @@
+use serde_json::Value;
+use veloci::*;
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_must_not";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["genre"]
+    fulltext.tokenize = false
+["price"]
+    numeric = "f64"
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        let segment = [
+            json!({ "title": "red apple tart", "genre": "dessert", "price": 6 }).to_string(),
+            json!({ "title": "apple cider", "genre": "drink", "price": 4 }).to_string(),
+        ];
+        create::add_segment_from_str(&mut pers, &segment.join("\n"), INDICES).unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "red apple pie", "genre": "dessert", "price": 12 },
+        { "title": "green apple salad", "genre": "salad", "price": 9 },
+        { "title": "apple juice", "genre": "drink", "price": 3 },
+        { "title": "red berry pie", "genre": "dessert", "price": 11 }
+    ])
+}
+
+fn search_title(term: &str) -> Value {
+    json!({ "search": { "terms": [term], "path": "title" } })
+}
+
+fn sorted_titles(hits: &search::SearchResultWithDoc) -> Vec<String> {
+    let mut titles: Vec<String> = hits.data.iter().map(|doc| doc.doc["title"].as_str().unwrap().to_string()).collect();
+    titles.sort();
+    titles
+}
+
+fn query_titles(search_term: &str) -> Vec<String> {
+    let mut params = query_generator::SearchQueryGeneratorParameters::default();
+    params.search_term = search_term.to_string();
+    params.levenshtein = Some(0);
+    sorted_titles(&search_testo_to_doco_qp!(params))
+}
+
+#[test]
+fn must_not_removes_hits_from_or() {
+    let req = json!({
+        "search_req": { "or": { "queries": [search_title("apple")], "must_not": [search_title("red")] } }
+    });
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(sorted_titles(&hits), vec!["apple cider", "apple juice", "green apple salad"]);
+    assert_eq!(hits.num_hits, 3);
+}
+
+#[test]
+fn must_not_removes_hits_from_and() {
+    let req = json!({
+        "search_req": { "and": {
+            "queries": [search_title("apple"), search_title("red")],
+            "must_not": [{ "search": { "terms": ["dessert"], "path": "genre" } }]
+        } }
+    });
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(hits.num_hits, 0);
+
+    let req = json!({
+        "search_req": { "and": {
+            "queries": [search_title("apple")],
+            "must_not": [{ "search": { "terms": ["dessert"], "path": "genre" } }, search_title("juice")]
+        } }
+    });
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(sorted_titles(&hits), vec!["apple cider", "green apple salad"]);
+}
+
+#[test]
+fn must_not_with_range() {
+    let req = json!({
+        "search_req": { "or": {
+            "queries": [search_title("apple"), search_title("pie")],
+            "must_not": [{ "range": { "path": "price", "gte": 10 } }]
+        } }
+    });
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(sorted_titles(&hits), vec!["apple cider", "apple juice", "green apple salad", "red apple tart"]);
+}
+
+#[test]
+fn must_not_in_filter() {
+    let req = json!({
+        "search_req": search_title("apple"),
+        "filter": { "and": {
+            "queries": [{ "range": { "path": "price", "lte": 10 } }],
+            "must_not": [{ "range": { "path": "price", "lte": 4 } }]
+        } }
+    });
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(sorted_titles(&hits), vec!["green apple salad", "red apple tart"]);
+}
+
+#[test]
+fn only_must_not_matches_nothing() {
+    let req = json!({
+        "search_req": { "or": { "queries": [], "must_not": [search_title("red")] } }
+    });
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(hits.num_hits, 0);
+}
+
+#[test]
+fn query_parser_negation() {
+    let expected = vec!["apple cider", "apple juice", "green apple salad"];
+    assert_eq!(query_titles("apple -red"), expected);
+    assert_eq!(query_titles("apple NOT red"), expected);
+    assert_eq!(query_titles("-red apple"), expected);
+    assert_eq!(query_titles("apple AND NOT red"), expected);
+    assert_eq!(query_titles("apple -(red green)"), vec!["apple cider", "apple juice"]);
+    assert_eq!(query_titles("-red"), Vec::<String>::new());
+}
+
+#[test]
+fn query_parser_negation_on_field() {
+    assert_eq!(query_titles("apple genre:-drink"), vec!["green apple salad", "red apple pie", "red apple tart"]);
+    assert_eq!(query_titles("apple -genre:drink"), vec!["green apple salad", "red apple pie", "red apple tart"]);
+    // the negation only applies on the field
+    assert_eq!(query_titles("pie genre:-pie"), vec!["red apple pie", "red berry pie"]);
+}
+
+fn ranked_titles(hits: &search::SearchResultWithDoc) -> Vec<String> {
+    hits.data.iter().map(|doc| doc.doc["title"].as_str().unwrap().to_string()).collect()
+}
+
+#[test]
+fn should_only_adds_score() {
+    let req = json!({
+        "search_req": { "and": { "queries": [search_title("apple")], "should": [search_title("red")] } }
+    });
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(hits.num_hits, 5);
+    let titles = ranked_titles(&hits);
+    let mut top_titles = titles[..2].to_vec();
+    top_titles.sort();
+    assert_eq!(top_titles, vec!["red apple pie", "red apple tart"]);
+    assert!(!titles.contains(&"red berry pie".to_string()));
+}
+
+#[test]
+fn query_parser_required() {
+    let params = query_generator::SearchQueryGeneratorParameters {
+        search_term: "+apple red -drink".to_string(),
+        levenshtein: Some(0),
+        ..Default::default()
+    };
+    let hits = search_testo_to_doco_qp!(params);
+    assert_eq!(hits.num_hits, 3);
+    let mut top_titles = ranked_titles(&hits)[..2].to_vec();
+    top_titles.sort();
+    assert_eq!(top_titles, vec!["red apple pie", "red apple tart"]);
+
+    assert_eq!(query_titles("+red +pie"), vec!["red apple pie", "red berry pie"]);
+    assert_eq!(query_titles("+genre:dessert pie"), vec!["red apple pie", "red apple tart", "red berry pie"]);
+    assert_eq!(query_titles("genre:+drink apple"), vec!["apple cider", "apple juice"]);
+    assert_eq!(query_titles("+(juice cider) apple"), vec!["apple cider", "apple juice"]);
+}
@@ -0,0 +1,88 @@
+use serde_json::Value;
+use veloci::{indices::NumericValue, *};
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_numeric";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["price"]
+    numeric = "f64"
+["year"]
+    numeric = "i64"
+["sizes[]"]
+    numeric = "u64"
+["offers[].discount"]
+    numeric = "i64"
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "old map", "price": 12.5, "year": -300, "sizes": [10, 20], "offers": [{ "discount": -5 }] },
+        { "title": "new map", "price": 3, "year": 2020 },
+        { "title": "no price" }
+    ])
+}
+
+#[test]
+fn numeric_column_values() {
+    let price = TEST_PERSISTENCE.get_numeric_column("price").unwrap();
+    assert_eq!(price.get_value(0), Some(NumericValue::F64(12.5)));
+    assert_eq!(price.get_value(1), Some(NumericValue::F64(3.0)));
+    assert_eq!(price.get_value(2), None);
+
+    let year = TEST_PERSISTENCE.get_numeric_column("year").unwrap();
+    assert_eq!(year.get_value(0), Some(NumericValue::I64(-300)));
+
+    let sizes = TEST_PERSISTENCE.get_numeric_column("sizes[]").unwrap();
+    assert_eq!(sizes.get_values(0).collect::<Vec<_>>(), vec![NumericValue::U64(10), NumericValue::U64(20)]);
+    assert_eq!(sizes.get_values(1).count(), 0);
+
+    assert!(!TEST_PERSISTENCE.has_numeric_column("title"));
+}
+
+#[test]
+fn read_data_returns_numbers() {
+    let fields = ["title", "price", "year", "sizes[]", "offers[].discount"]
+        .iter()
+        .map(|field| field.to_string())
+        .collect::<Vec<_>>();
+    let doc = search::read_data(&TEST_PERSISTENCE, 0, &fields).unwrap();
+    assert_eq!(
+        doc,
+        json!({ "title": "old map", "price": 12.5, "year": -300, "sizes": [10, 20], "offers": [{ "discount": -5 }] })
+    );
+
+    let doc = search::read_data(&TEST_PERSISTENCE, 2, &fields).unwrap();
+    assert_eq!(doc, json!({ "title": "no price" }));
+}
+
+#[test]
+fn numeric_fields_are_still_searchable_as_text() {
+    let req = json!({
+        "search_req": { "search": {"terms":["2020"], "path": "year", "levenshtein_distance": 0} },
+        "select": ["title", "year"]
+    });
+
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(hits.num_hits, 1);
+    assert_eq!(hits.data[0].doc, json!({ "title": "new map", "year": 2020 }));
+}
+
+#[test]
+fn numeric_columns_are_loaded_on_open() {
+    let pers = persistence::Persistence::open(TEST_PERSISTENCE.directory.box_clone()).unwrap();
+    assert_eq!(pers.get_numeric_column("year").unwrap().get_value(1), Some(NumericValue::I64(2020)));
+}
+
+#[test]
+fn invalid_numeric_value() {
+    let mut pers = persistence::Persistence::create_im().unwrap();
+    let res = create::create_indices_from_str(&mut pers, &json!({ "year": "unknown" }).to_string(), INDICES, true);
+    assert!(res.is_err());
+}
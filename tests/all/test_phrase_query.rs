@@ -0,0 +1,132 @@
+use serde_json::Value;
+use veloci::*;
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_phrase_query";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All", "PhraseQuery"]
+["genre"]
+    fulltext.tokenize = false
+["description"]
+    features = ["Search"]
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        let segment = [
+            json!({ "title": "Grandma's Red Apple Pie", "genre": "dessert", "description": "red apple pie", "tags": ["apple"] }).to_string(),
+            json!({ "title": "apple pie", "genre": "apple pie", "description": "apple pie", "tags": ["pie"] }).to_string(),
+        ];
+        create::add_segment_from_str(&mut pers, &segment.join("\n"), INDICES).unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "red apple pie", "genre": "dessert", "description": "red apple pie", "tags": ["sweet red apple", "pie"] },
+        { "title": "red apple and apple pie", "genre": "dessert", "description": "two dishes", "tags": ["red", "apple pie"] },
+        { "title": "pie with red apple", "genre": "dessert", "description": "pie", "tags": ["fruit"] },
+        { "title": "green apple salad", "genre": "salad", "description": "salad", "tags": ["red apple"] }
+    ])
+}
+
+fn phrase(path: &str, phrase: &str) -> Value {
+    json!({ "phrase": { "path": path, "phrase": phrase } })
+}
+
+fn sorted_titles(hits: &search::SearchResultWithDoc) -> Vec<String> {
+    let mut titles: Vec<String> = hits.data.iter().map(|doc| doc.doc["title"].as_str().unwrap().to_string()).collect();
+    titles.sort();
+    titles
+}
+
+fn phrase_titles(path: &str, text: &str) -> Vec<String> {
+    let req = json!({ "search_req": phrase(path, text) });
+    sorted_titles(&search_testo_to_doc!(req))
+}
+
+#[test]
+fn phrase_requires_consecutive_tokens() {
+    // "red apple and apple pie" contains the pairs "red apple" and "apple pie", but not the phrase
+    assert_eq!(phrase_titles("title", "red apple pie"), vec!["Grandma's Red Apple Pie", "red apple pie"]);
+    assert_eq!(
+        phrase_titles("title", "red apple"),
+        vec!["Grandma's Red Apple Pie", "pie with red apple", "red apple and apple pie", "red apple pie"]
+    );
+    assert_eq!(phrase_titles("title", "apple red"), Vec::<String>::new());
+}
+
+#[test]
+fn phrase_ignores_seperators_and_case() {
+    assert_eq!(phrase_titles("title", "RED, apple-pie"), vec!["Grandma's Red Apple Pie", "red apple pie"]);
+
+    let req = json!({ "search_req": { "phrase": { "path": "title", "phrase": "Red Apple", "ignore_case": false } } });
+    assert_eq!(sorted_titles(&search_testo_to_doc!(req)), vec!["Grandma's Red Apple Pie"]);
+}
+
+#[test]
+fn phrase_with_single_token() {
+    assert_eq!(phrase_titles("title", "salad"), vec!["green apple salad"]);
+    assert_eq!(phrase_titles("tags[]", "pie"), vec!["apple pie", "red apple and apple pie", "red apple pie"]);
+    assert_eq!(phrase_titles("genre", "apple pie"), vec!["apple pie"]);
+}
+
+#[test]
+fn phrase_does_not_match_across_array_values() {
+    assert_eq!(phrase_titles("tags[]", "red apple"), vec!["green apple salad", "red apple pie"]);
+    assert_eq!(phrase_titles("tags[]", "apple pie"), vec!["red apple and apple pie"]);
+}
+
+#[test]
+fn phrase_in_tree_and_filter() {
+    let req = json!({
+        "search_req": { "or": { "queries": [phrase("title", "apple pie")], "must_not": [phrase("title", "red apple")] } }
+    });
+    assert_eq!(sorted_titles(&search_testo_to_doc!(req)), vec!["apple pie"]);
+
+    let req = json!({
+        "search_req": { "search": { "terms": ["apple"], "path": "title" } },
+        "filter": phrase("title", "red apple")
+    });
+    assert_eq!(
+        sorted_titles(&search_testo_to_doc!(req)),
+        vec!["Grandma's Red Apple Pie", "pie with red apple", "red apple and apple pie", "red apple pie"]
+    );
+}
+
+#[test]
+fn phrase_boost_and_explain() {
+    let req = json!({
+        "search_req": { "phrase": { "path": "title", "phrase": "apple salad", "boost": 2.0 } },
+        "explain": true
+    });
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(hits.num_hits, 1);
+    let score = hits.data[0].hit.score;
+
+    let hits = search_testo_to_doc!(json!({ "search_req": phrase("title", "apple salad") }));
+    assert_eq!(hits.data[0].hit.score * 2.0, score);
+}
+
+#[test]
+fn phrase_without_positional_index_is_invalid() {
+    let requesto: search::Request = serde_json::from_value(json!({ "search_req": phrase("description", "red apple") })).unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+}
+
+#[test]
+fn query_parser_quotes_are_phrases() {
+    let mut params = query_generator::SearchQueryGeneratorParameters::default();
+    params.search_term = "title:\"red apple pie\"".to_string();
+    assert_eq!(sorted_titles(&search_testo_to_doco_qp!(params)), vec!["Grandma's Red Apple Pie", "red apple pie"]);
+
+    // without positional index the quoted text is searched as before
+    let mut params = query_generator::SearchQueryGeneratorParameters::default();
+    params.search_term = "description:\"red apple pie\"".to_string();
+    params.levenshtein = Some(0);
+    assert_eq!(sorted_titles(&search_testo_to_doco_qp!(params)), vec!["Grandma's Red Apple Pie", "red apple pie"]);
+}
@@ -0,0 +1,87 @@
+use serde_json::Value;
+use veloci::*;
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_primary_key";
+static INDICES: &str = r#"
+[primary_key]
+    field = "ent_seq"
+    on_duplicate = "last_write_wins"
+[fields_config."*GLOBAL*"]
+    features = ["All"]
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        let upsert_data = [
+            json!({ "ent_seq": "2", "title": "yellow banana" }).to_string(),
+            json!({ "ent_seq": "4", "title": "purple plum" }).to_string(),
+        ]
+        .join("\n");
+        create::upsert_documents_from_str(&mut pers, &upsert_data, INDICES).unwrap();
+        pers.delete_documents_by_keys(&["3"]).unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "ent_seq": "1", "title": "red apple" },
+        { "ent_seq": "2", "title": "green pear" },
+        { "ent_seq": "3", "title": "red car" },
+        { "ent_seq": "1", "title": "red cherry" }
+    ])
+}
+
+#[test]
+fn lookup_last_write_wins() {
+    let pers = &TEST_PERSISTENCE;
+    assert_eq!(pers.get_anchor_by_key("1"), Some(3));
+    assert!(pers.is_deleted(0));
+    let doc = search::get_documents_by_keys(pers, &["1"]).unwrap();
+    assert_eq!(doc[0].as_ref().unwrap()["title"], "red cherry");
+}
+
+#[test]
+fn multi_get() {
+    let docs = search::get_documents_by_keys(&TEST_PERSISTENCE, &["2", "3", "4", "5"]).unwrap();
+    assert_eq!(docs[0].as_ref().unwrap()["title"], "yellow banana");
+    assert_eq!(docs[1], None);
+    assert_eq!(docs[2].as_ref().unwrap()["title"], "purple plum");
+    assert_eq!(docs[3], None);
+}
+
+#[test]
+fn upserted_document_replaces_old_one() {
+    let req = json!({
+        "search_req": { "search": {"terms":["pear"], "path": "title", "levenshtein_distance": 0} }
+    });
+    assert_eq!(search_testo_to_doc!(req).num_hits, 0);
+
+    let req = json!({
+        "search_req": { "search": {"terms":["banana"], "path": "title", "levenshtein_distance": 0} }
+    });
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(hits.num_hits, 1);
+    assert_eq!(hits.data[0].doc["ent_seq"], "2");
+}
+
+#[test]
+fn delete_by_key() {
+    let req = json!({
+        "search_req": { "search": {"terms":["car"], "path": "title", "levenshtein_distance": 0} }
+    });
+    assert_eq!(search_testo_to_doc!(req).num_hits, 0);
+    assert_eq!(TEST_PERSISTENCE.get_anchor_by_key("3"), None);
+}
+
+#[test]
+fn reject_duplicate_keys() {
+    let indices = r#"{"primary_key": {"field": "ent_seq"}, "fields_config": {}}"#;
+    let data = [json!({ "ent_seq": "1" }).to_string(), json!({ "ent_seq": "1" }).to_string()].join("\n");
+    let mut pers = persistence::Persistence::create_im().unwrap();
+    let res = create::create_indices_from_str(&mut pers, &data, indices, true);
+    assert!(matches!(res, Err(error::VelociError::DuplicatePrimaryKey { key }) if key == "1"));
+}
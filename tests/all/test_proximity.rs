@@ -0,0 +1,116 @@
+use serde_json::Value;
+use veloci::*;
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_proximity";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All", "PhraseQuery"]
+["description"]
+    features = ["Search"]
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        let segment = [json!({ "title": "pie of apple", "description": "pie apple" }).to_string()];
+        create::add_segment_from_str(&mut pers, &segment.join("\n"), INDICES).unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "apple pie", "description": "apple pie" },
+        { "title": "apple and pear pie", "description": "apple pear pie" },
+        { "title": "apple tart with a side of cherry pie", "description": "apple cherry pie" },
+        { "title": "search engine", "description": "search engine" },
+        { "title": "search for a fast engine", "description": "search fast engine" }
+    ])
+}
+
+fn proximity(terms: &[&str], distance: u32) -> Value {
+    json!({ "proximity": { "path": "title", "terms": terms, "distance": distance } })
+}
+
+fn sorted_titles(hits: &search::SearchResultWithDoc) -> Vec<String> {
+    let mut titles: Vec<String> = hits.data.iter().map(|doc| doc.doc["title"].as_str().unwrap().to_string()).collect();
+    titles.sort();
+    titles
+}
+
+fn titles_by_score(hits: &search::SearchResultWithDoc) -> Vec<String> {
+    hits.data.iter().map(|doc| doc.doc["title"].as_str().unwrap().to_string()).collect()
+}
+
+fn query_titles(search_term: &str) -> Vec<String> {
+    let mut params = query_generator::SearchQueryGeneratorParameters::default();
+    params.search_term = search_term.to_string();
+    params.levenshtein = Some(0);
+    sorted_titles(&search_testo_to_doco_qp!(params))
+}
+
+#[test]
+fn proximity_matches_within_distance() {
+    let hits = search_testo_to_doc!(json!({ "search_req": proximity(&["apple", "pie"], 0) }));
+    assert_eq!(sorted_titles(&hits), vec!["apple pie"]);
+
+    let hits = search_testo_to_doc!(json!({ "search_req": proximity(&["apple", "pie"], 2) }));
+    assert_eq!(sorted_titles(&hits), vec!["apple and pear pie", "apple pie", "pie of apple"]);
+
+    let hits = search_testo_to_doc!(json!({ "search_req": proximity(&["apple", "pie"], 10) }));
+    assert_eq!(hits.num_hits, 4);
+}
+
+#[test]
+fn proximity_score_decays_with_distance() {
+    let hits = search_testo_to_doc!(json!({ "search_req": proximity(&["apple", "pie"], 10), "explain": true }));
+    assert_eq!(
+        titles_by_score(&hits),
+        vec!["apple pie", "pie of apple", "apple and pear pie", "apple tart with a side of cherry pie"]
+    );
+    assert_gt!(hits.data[0].hit.score, hits.data[1].hit.score);
+    assert_gt!(hits.data[1].hit.score, hits.data[2].hit.score);
+    assert_gt!(hits.data[2].hit.score, hits.data[3].hit.score);
+}
+
+#[test]
+fn proximity_tokenizes_terms() {
+    let hits = search_testo_to_doc!(json!({ "search_req": proximity(&["search engine"], 3) }));
+    assert_eq!(sorted_titles(&hits), vec!["search engine", "search for a fast engine"]);
+
+    let hits = search_testo_to_doc!(json!({ "search_req": proximity(&["search engine"], 2) }));
+    assert_eq!(sorted_titles(&hits), vec!["search engine"]);
+}
+
+#[test]
+fn proximity_with_case_folded_tokens() {
+    let req = json!({ "proximity": { "path": "title", "terms": ["Apple", "apple", "pie"], "distance": 0, "ignore_case": true } });
+    let hits = search_testo_to_doc!(json!({ "search_req": req }));
+    assert_eq!(sorted_titles(&hits), vec!["apple pie"]);
+
+    let req = json!({ "proximity": { "path": "title", "terms": ["Apple apple"], "distance": 2, "ignore_case": true } });
+    let hits = search_testo_to_doc!(json!({ "search_req": req }));
+    assert_eq!(hits.num_hits, 4);
+}
+
+#[test]
+fn proximity_without_positional_index_is_invalid() {
+    let requesto: search::Request = serde_json::from_value(json!({
+        "search_req": { "proximity": { "path": "description", "terms": ["apple", "pie"], "distance": 1 } }
+    }))
+    .unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+}
+
+#[test]
+fn query_parser_proximity() {
+    assert_eq!(query_titles("title:\"search engine\"~3"), vec!["search engine", "search for a fast engine"]);
+    assert_eq!(query_titles("title:(apple NEAR/1 pie)"), vec!["apple pie", "pie of apple"]);
+    // without positional index all terms are required
+    assert_eq!(
+        query_titles("description:(apple NEAR/1 pie)"),
+        vec!["apple and pear pie", "apple pie", "apple tart with a side of cherry pie", "pie of apple"]
+    );
+}
@@ -379,6 +379,40 @@ fn no_matching_fields_from_query() {
     assert_contains!(requesto.unwrap_err().to_string(), "Field notexistingfield not found in");
 }
 
+#[test]
+fn invalid_query_returns_parse_error() {
+    let mut params = query_generator::SearchQueryGeneratorParameters::default();
+    params.search_term = "urge (いよく".to_string();
+
+    match query_generator::search_query(&TEST_PERSISTENCE, params) {
+        Err(error::VelociError::QueryParseError(err)) => {
+            assert_eq!(err.kind, query_parser::error::ParseErrorKind::UnbalancedParentheses);
+            assert_eq!((err.start, err.stop), (5, 6));
+            assert_eq!(err.expected, ")");
+        }
+        other => panic!("expected parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn lenient_query_treats_unbalanced_syntax_as_text() {
+    let mut params = query_generator::SearchQueryGeneratorParameters::default();
+    params.parser_options = Some(query_generator::QueryParserOptions {
+        lenient: true,
+        ..Default::default()
+    });
+    params.search_term = "urge)".to_string();
+    let hits = search_testo_to_doco_qp!(params.clone()).data;
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].doc["ent_seq"], "1587690");
+
+    params.search_term = "\"urge".to_string();
+    assert_eq!(search_testo_to_doco_qp!(params.clone()).data.len(), 1);
+
+    params.search_term = "urge AND ".to_string();
+    assert_eq!(search_testo_to_doco_qp!(params).data.len(), 1);
+}
+
 // #[test]
 // fn no_matching_fields_in_facet() {
 //     let params = json!({
@@ -0,0 +1,164 @@
+use serde_json::Value;
+use veloci::*;
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_range";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["price"]
+    numeric = "f64"
+["sizes[]"]
+    numeric = "u64"
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        create::add_segment_from_str(
+            &mut pers,
+            &json!({ "title": "red bike", "price": 250, "sizes": [26, 28], "color": "red" }).to_string(),
+            INDICES,
+        )
+        .unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "old map", "price": 12.5, "sizes": [10, 20], "color": "brown" },
+        { "title": "new map", "price": 3, "sizes": [20], "color": "blue" },
+        { "title": "red apple", "price": 0.5, "color": "red" },
+        { "title": "no price", "color": "green" }
+    ])
+}
+
+fn search_titles(req: Value) -> Vec<String> {
+    let hits = search_testo_to_doc!(req);
+    let mut titles: Vec<String> = hits.data.iter().map(|doc| doc.doc["title"].as_str().unwrap().to_string()).collect();
+    titles.sort();
+    titles
+}
+
+#[test]
+fn numeric_range() {
+    let titles = search_titles(json!({ "search_req": { "range": { "path": "price", "gte": 3, "lt": 250 } } }));
+    assert_eq!(titles, vec!["new map", "old map"]);
+
+    let titles = search_titles(json!({ "search_req": { "range": { "path": "price", "gt": 3, "lte": 250 } } }));
+    assert_eq!(titles, vec!["old map", "red bike"]);
+}
+
+#[test]
+fn numeric_range_open_bounds() {
+    let titles = search_titles(json!({ "search_req": { "range": { "path": "price", "lt": 5 } } }));
+    assert_eq!(titles, vec!["new map", "red apple"]);
+
+    let titles = search_titles(json!({ "search_req": { "range": { "path": "price", "gte": 12.5 } } }));
+    assert_eq!(titles, vec!["old map", "red bike"]);
+}
+
+#[test]
+fn numeric_range_on_array_matches_any_value() {
+    let titles = search_titles(json!({ "search_req": { "range": { "path": "sizes[]", "gt": 15, "lt": 27 } } }));
+    assert_eq!(titles, vec!["new map", "old map", "red bike"]);
+}
+
+#[test]
+fn numeric_range_with_text_bounds() {
+    let titles = search_titles(json!({ "search_req": { "range": { "path": "price", "gte": "3", "lt": "12.5" } } }));
+    assert_eq!(titles, vec!["new map"]);
+}
+
+#[test]
+fn text_range() {
+    let titles = search_titles(json!({ "search_req": { "range": { "path": "color", "gte": "blue", "lt": "green" } } }));
+    assert_eq!(titles, vec!["new map", "old map"]);
+}
+
+#[test]
+fn text_range_with_both_bounds_on_a_side() {
+    let titles = search_titles(json!({ "search_req": { "range": { "path": "color", "gt": "blue", "gte": "brown" } } }));
+    assert_eq!(titles, vec!["no price", "old map", "red apple", "red bike"]);
+    let titles = search_titles(json!({ "search_req": { "range": { "path": "color", "gt": "brown", "gte": "blue" } } }));
+    assert_eq!(titles, vec!["no price", "red apple", "red bike"]);
+    let titles = search_titles(json!({ "search_req": { "range": { "path": "color", "lt": "green", "lte": "blue" } } }));
+    assert_eq!(titles, vec!["new map"]);
+    let titles = search_titles(json!({ "search_req": { "range": { "path": "color", "lt": "blue", "lte": "brown" } } }));
+    assert_eq!(titles, Vec::<String>::new());
+}
+
+#[test]
+fn range_combined_with_search() {
+    let req = json!({
+        "search_req": { "and": { "queries": [
+            { "search": { "terms": ["map"], "path": "title" } },
+            { "range": { "path": "price", "gt": 5 } }
+        ] } }
+    });
+    assert_eq!(search_titles(req), vec!["old map"]);
+}
+
+#[test]
+fn range_as_filter() {
+    let req = json!({
+        "search_req": { "search": { "terms": ["red"], "path": "title" } },
+        "filter": { "range": { "path": "price", "gte": 100 } }
+    });
+    assert_eq!(search_titles(req), vec!["red bike"]);
+
+    let req = json!({
+        "search_req": { "range": { "path": "price", "lte": 250 } },
+        "filter": { "or": { "queries": [
+            { "range": { "path": "sizes[]", "lt": 15 } },
+            { "range": { "path": "color", "gte": "red" } }
+        ] } }
+    });
+    assert_eq!(search_titles(req), vec!["old map", "red apple", "red bike"]);
+}
+
+#[test]
+fn range_boost_is_score() {
+    let hits = search_testo_to_doc!(json!({ "search_req": { "range": { "path": "price", "lt": 1, "boost": 2.0 } } }));
+    assert_eq!(hits.num_hits, 1);
+    assert_eq!(hits.data[0].hit.score, 2.0);
+}
+
+#[test]
+fn range_without_bounds_is_invalid() {
+    let requesto: search::Request = serde_json::from_value(json!({ "search_req": { "range": { "path": "price" } } })).unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+
+    let requesto: search::Request = serde_json::from_value(json!({ "search_req": { "range": { "path": "price", "gt": "cheap" } } })).unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+}
+
+fn query_titles(search_term: &str, filter: Option<&str>) -> Vec<String> {
+    let mut params = query_generator::SearchQueryGeneratorParameters::default();
+    params.search_term = search_term.to_string();
+    params.filter = filter.map(|filter| filter.to_string());
+    params.levenshtein = Some(0);
+    let hits = search_testo_to_doco_qp!(params);
+    let mut titles: Vec<String> = hits.data.iter().map(|doc| doc.doc["title"].as_str().unwrap().to_string()).collect();
+    titles.sort();
+    titles
+}
+
+#[test]
+fn query_parser_ranges() {
+    assert_eq!(query_titles("price:[3 TO 12.5]", None), vec!["new map", "old map"]);
+    assert_eq!(query_titles("price:{3 TO 12.5]", None), vec!["old map"]);
+    assert_eq!(query_titles("price:[3 TO *]", None), vec!["new map", "old map", "red bike"]);
+    assert_eq!(query_titles("price:<3", None), vec!["red apple"]);
+    assert_eq!(query_titles("price:<=3", None), vec!["new map", "red apple"]);
+    assert_eq!(query_titles("map AND sizes[]:>15", None), vec!["new map", "old map"]);
+    assert_eq!(query_titles("color:[blue TO green}", None), vec!["new map", "old map"]);
+}
+
+#[test]
+fn query_parser_range_in_filter() {
+    assert_eq!(query_titles("red", Some("price:>=100")), vec!["red bike"]);
+    assert_eq!(query_titles("map OR red", Some("price:[1 TO 20]")), vec!["new map", "old map"]);
+}
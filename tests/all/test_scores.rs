@@ -103,6 +103,29 @@ fn test_boost_simple() {
     assert_eq!(hits[2].doc["commonness"], 10);
 }
 
+#[test]
+fn check_score_boost_in_query() {
+    let score_for_query = |search_term: &str| {
+        let mut params = query_generator::SearchQueryGeneratorParameters::default();
+        params.search_term = search_term.to_string();
+        params.levenshtein = Some(0);
+        let hits = search_testo_to_doco_qp!(params).data;
+        assert_eq!(hits[0].doc["title"], "greg tagebuch");
+        hits[0].hit.score
+    };
+    let score = score_for_query("title:tagebuch -title:05");
+    assert_eq!(score_for_query("title:tagebuch^2 -title:05"), score * 2.0);
+    assert_eq!(score_for_query("title:(tagebuch -05)^0.5"), score * 0.5);
+    assert_eq!(score_for_query("(title:tagebuch^2 -title:05)^3"), score * 6.0);
+
+    // the boost changes the order
+    let mut params = query_generator::SearchQueryGeneratorParameters::default();
+    params.search_term = "title:tagebuch title:05^10".to_string();
+    let hits = search_testo_to_doco_qp!(params).data;
+    assert_eq!(hits[0].doc["title"], "greg tagebuch 05");
+    assert_eq!(hits[2].doc["title"], "greg tagebuch");
+}
+
 #[test]
 fn check_score_regarding_to_length() {
     let req_with_single_phrase = json!({
@@ -0,0 +1,202 @@
+use serde_json::Value;
+use veloci::*;
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_segments";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["tags[]"]
+    facet = true
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        create::add_segment_from_str(&mut pers, &to_line_delimited(&get_segment_data()), INDICES).unwrap();
+        pers
+    };
+}
+
+fn to_line_delimited(data: &Value) -> String {
+    let mut out: Vec<u8> = vec![];
+    create::convert_any_json_data_to_line_delimited(data.to_string().as_bytes(), &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "red apple", "tags": ["fruit", "red"] },
+        { "title": "green pear", "tags": ["fruit", "green"] },
+        { "title": "red car", "tags": ["vehicle", "red"] }
+    ])
+}
+
+pub fn get_segment_data() -> Value {
+    json!([
+        { "title": "red cherry", "tags": ["fruit", "red"] },
+        { "title": "blue bike", "tags": ["vehicle", "blue"] }
+    ])
+}
+
+#[test]
+fn segment_metadata() {
+    assert_eq!(TEST_PERSISTENCE.metadata.segments.len(), 1);
+    assert_eq!(TEST_PERSISTENCE.metadata.segments[0].num_docs, 2);
+    assert_eq!(TEST_PERSISTENCE.get_number_of_documents(), 5);
+}
+
+#[test]
+fn search_across_segments() {
+    let req = json!({
+        "search_req": { "search": {"terms":["red"], "path": "title", "levenshtein_distance": 0} }
+    });
+
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(hits.num_hits, 3);
+    let mut ids: Vec<u32> = hits.data.iter().map(|doc| doc.hit.id).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, vec![0, 2, 3]);
+    let cherry = hits.data.iter().find(|doc| doc.hit.id == 3).unwrap();
+    assert_eq!(cherry.doc["title"], "red cherry");
+}
+
+#[test]
+fn search_only_in_segment() {
+    let req = json!({
+        "search_req": { "search": {"terms":["bike"], "path": "title", "levenshtein_distance": 0} },
+        "select": ["title"]
+    });
+
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(hits.num_hits, 1);
+    assert_eq!(hits.data[0].hit.id, 4);
+    assert_eq!(hits.data[0].doc["title"], "blue bike");
+}
+
+#[test]
+fn top_skip_across_segments() {
+    let req = json!({
+        "search_req": { "search": {"terms":["red"], "path": "title", "levenshtein_distance": 0} },
+        "top": 1,
+        "skip": 1
+    });
+
+    let hits = search_testo_to_doc!(req);
+    assert_eq!(hits.num_hits, 3);
+    assert_eq!(hits.data.len(), 1);
+}
+
+#[test]
+fn facets_across_segments() {
+    let req = json!({
+        "search_req": { "search": {"terms":["red"], "path": "title", "levenshtein_distance": 0} },
+        "facets": [{"field":"tags[]"}]
+    });
+
+    let hits = search_testo_to_doc!(req);
+    let facets = hits.facets.unwrap();
+    assert_eq!(
+        facets.get("tags[]").unwrap(),
+        &vec![("red".to_string(), 3), ("fruit".to_string(), 2), ("vehicle".to_string(), 1)]
+    );
+}
+
+#[test]
+fn reopen_with_segments() {
+    let pers = persistence::Persistence::open(TEST_PERSISTENCE.directory.box_clone()).unwrap();
+    assert_eq!(pers.segments.len(), 1);
+    let requesto: search::Request = serde_json::from_str(
+        &json!({
+            "search_req": { "search": {"terms":["cherry"], "path": "title", "levenshtein_distance": 0} }
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let hits = search::to_search_result(&pers, search::search(requesto.clone(), &pers).unwrap(), &requesto.select);
+    assert_eq!(hits.data.len(), 1);
+    assert_eq!(hits.data[0].hit.id, 3);
+}
+
+#[test]
+fn segments_with_different_fields() {
+    let indices = r#"
+    ["*GLOBAL*"]
+        features = ["All"]
+    ["price"]
+        numeric = "f64"
+    "#;
+    let mut pers = persistence::Persistence::create_im().unwrap();
+    let data = [json!({ "title": "red apple", "price": 5 }), json!({ "title": "green pear", "price": 3 })];
+    let data = data.iter().map(|doc| doc.to_string()).collect::<Vec<_>>().join("\n");
+    create::create_indices_from_str(&mut pers, &data, indices, true).unwrap();
+    create::add_segment_from_str(&mut pers, &json!({ "name": "red car" }).to_string(), indices).unwrap();
+
+    let search_ids = |req: Value| {
+        let requesto: search::Request = serde_json::from_value(req).unwrap();
+        let hits = search::to_search_result(&pers, search::search(requesto.clone(), &pers).unwrap(), &requesto.select);
+        hits.data.iter().map(|doc| doc.hit.id).collect::<Vec<_>>()
+    };
+    assert_eq!(search_ids(json!({ "search_req": { "search": {"terms":["red"], "path": "title"} } })), vec![0]);
+    assert_eq!(search_ids(json!({ "search_req": { "search": {"terms":["red"], "path": "name"} } })), vec![2]);
+    let req = json!({ "search_req": { "search": {"terms":["red"], "path": "title"} }, "sort": [{ "field": "price" }] });
+    assert_eq!(search_ids(req), vec![0]);
+    let req = json!({ "search_req": { "range": { "path": "price", "gte": 1 } }, "sort": [{ "field": "price" }] });
+    assert_eq!(search_ids(req), vec![1, 0]);
+    assert_eq!(search_ids(json!({ "search_req": { "range": { "path": "title", "gte": "red" } } })), vec![0]);
+
+    let query_ids = |search_term: &str| {
+        let mut params = query_generator::SearchQueryGeneratorParameters::default();
+        params.search_term = search_term.to_string();
+        let requesto = query_generator::search_query(&pers, params).unwrap();
+        let hits = search::to_search_result(&pers, search::search(requesto.clone(), &pers).unwrap(), &requesto.select);
+        let mut ids = hits.data.iter().map(|doc| doc.hit.id).collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    };
+    assert_eq!(query_ids("red"), vec![0]);
+    assert_eq!(query_ids("title:red"), vec![0]);
+    assert_eq!(query_ids("red apple"), vec![0]);
+    assert_eq!(query_ids("\"red apple\""), vec![0]);
+}
+
+#[test]
+fn phrase_on_segment_without_positional_index() {
+    let indices = r#"
+    ["*GLOBAL*"]
+        features = ["All", "PhraseQuery"]
+    "#;
+    let segment_indices = r#"
+    ["*GLOBAL*"]
+        features = ["All"]
+    "#;
+    let mut pers = persistence::Persistence::create_im().unwrap();
+    let data = [json!({ "title": "red apple pie" }), json!({ "title": "apple red" })];
+    let data = data.iter().map(|doc| doc.to_string()).collect::<Vec<_>>().join("\n");
+    create::create_indices_from_str(&mut pers, &data, indices, true).unwrap();
+    let segment = [json!({ "title": "sweet red apple" }), json!({ "title": "red pear and apple" })];
+    let segment = segment.iter().map(|doc| doc.to_string()).collect::<Vec<_>>().join("\n");
+    create::add_segment_from_str(&mut pers, &segment, segment_indices).unwrap();
+
+    let search_ids = |req: Value| {
+        let requesto: search::Request = serde_json::from_value(req).unwrap();
+        let hits = search::to_search_result(&pers, search::search(requesto.clone(), &pers).unwrap(), &requesto.select);
+        let mut ids = hits.data.iter().map(|doc| doc.hit.id).collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    };
+    // the segment without positional index approximates the phrase with the phrase pairs
+    assert_eq!(search_ids(json!({ "search_req": { "phrase": { "path": "title", "phrase": "red apple" } } })), vec![0, 2]);
+    // the distance can only be checked with the positional index
+    let req = json!({ "search_req": { "proximity": { "path": "title", "terms": ["red", "apple"], "distance": 2 } } });
+    assert_eq!(search_ids(req), vec![0, 1]);
+
+    let mut params = query_generator::SearchQueryGeneratorParameters::default();
+    params.search_term = "\"red apple\"".to_string();
+    let requesto = query_generator::search_query(&pers, params).unwrap();
+    let hits = search::to_search_result(&pers, search::search(requesto.clone(), &pers).unwrap(), &requesto.select);
+    let mut ids = hits.data.iter().map(|doc| doc.hit.id).collect::<Vec<_>>();
+    ids.sort_unstable();
+    assert_eq!(ids, vec![0, 2]);
+}
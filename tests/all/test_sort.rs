@@ -0,0 +1,161 @@
+use serde_json::Value;
+use veloci::*;
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_sort";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["name"]
+    fulltext.tokenize = false
+["price"]
+    numeric = "f64"
+["commonness".boost]
+    boost_type = "f32"
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        create::add_segment_from_str(
+            &mut pers,
+            &json!({ "title": "fruit banana", "name": "banana", "tags": ["yellow"], "price": 2, "commonness": 30 }).to_string(),
+            INDICES,
+        )
+        .unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "fruit cherry", "name": "cherry", "tags": ["red", "small"], "price": 5, "commonness": 10 },
+        { "title": "fruit apple", "name": "apple", "tags": ["red", "green"], "price": 2, "commonness": 20 },
+        { "title": "fruit fruit date", "name": "date", "price": 8 },
+        { "title": "fruit", "tags": ["brown"], "commonness": 5 }
+    ])
+}
+
+fn search_titles(req: Value) -> Vec<String> {
+    let hits = search_testo_to_doc!(req);
+    hits.data.iter().map(|doc| doc.doc["title"].as_str().unwrap().to_string()).collect()
+}
+
+fn fruit_search(sort: Value) -> Value {
+    json!({ "search_req": { "search": { "terms": ["fruit"], "path": "title" } }, "sort": sort })
+}
+
+#[test]
+fn sort_by_text_field() {
+    let titles = search_titles(fruit_search(json!([{ "field": "name" }])));
+    assert_eq!(titles, vec!["fruit apple", "fruit banana", "fruit cherry", "fruit fruit date", "fruit"]);
+
+    let titles = search_titles(fruit_search(json!([{ "field": "name", "order": "desc" }])));
+    assert_eq!(titles, vec!["fruit fruit date", "fruit cherry", "fruit banana", "fruit apple", "fruit"]);
+}
+
+#[test]
+fn sort_by_text_array_uses_smallest_or_largest_value() {
+    let titles = search_titles(fruit_search(json!([{ "field": "tags[]" }, { "field": "name" }])));
+    assert_eq!(titles, vec!["fruit", "fruit apple", "fruit cherry", "fruit banana", "fruit fruit date"]);
+
+    let titles = search_titles(fruit_search(json!([{ "field": "tags[]", "order": "desc" }, { "field": "name" }])));
+    assert_eq!(titles, vec!["fruit banana", "fruit cherry", "fruit apple", "fruit", "fruit fruit date"]);
+}
+
+#[test]
+fn sort_missing_first() {
+    let titles = search_titles(fruit_search(json!([{ "field": "price", "missing": "first" }, { "field": "name" }])));
+    assert_eq!(titles, vec!["fruit", "fruit apple", "fruit banana", "fruit cherry", "fruit fruit date"]);
+}
+
+#[test]
+fn sort_by_boost_field() {
+    let titles = search_titles(fruit_search(json!([{ "field": "commonness", "order": "desc" }])));
+    assert_eq!(titles, vec!["fruit banana", "fruit apple", "fruit cherry", "fruit", "fruit fruit date"]);
+}
+
+#[test]
+fn sort_by_score() {
+    let hits = search_testo_to_doc!(fruit_search(json!([{ "field": "_score", "order": "desc" }])));
+    let scores: Vec<f32> = hits.data.iter().map(|hit| hit.hit.score).collect();
+    assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]), "{:?}", scores);
+
+    let hits = search_testo_to_doc!(fruit_search(json!([{ "field": "_score" }])));
+    let scores: Vec<f32> = hits.data.iter().map(|hit| hit.hit.score).collect();
+    assert!(scores.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", scores);
+}
+
+#[test]
+fn sort_by_field_then_score() {
+    let req = fruit_search(json!([{ "field": "price" }, { "field": "_score", "order": "desc" }, { "field": "name", "order": "desc" }]));
+    assert_eq!(search_titles(req), vec!["fruit banana", "fruit apple", "fruit cherry", "fruit fruit date", "fruit"]);
+}
+
+#[test]
+fn sort_with_top_and_skip() {
+    let mut req = fruit_search(json!([{ "field": "name" }]));
+    req["top"] = json!(2);
+    req["skip"] = json!(1);
+    assert_eq!(search_titles(req), vec!["fruit banana", "fruit cherry"]);
+
+    let mut req = fruit_search(json!([{ "field": "name", "order": "desc" }]));
+    req["top"] = json!(1);
+    assert_eq!(search_titles(req), vec!["fruit fruit date"]);
+}
+
+#[test]
+fn sort_by_unknown_field_is_invalid() {
+    let requesto: search::Request = serde_json::from_value(fruit_search(json!([{ "field": "unknown" }]))).unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+}
+
+fn search_all_pages(mut req: Value, top: usize) -> Vec<Vec<String>> {
+    req["top"] = json!(top);
+    let mut pages = vec![];
+    loop {
+        let requesto: search::Request = serde_json::from_value(req.clone()).unwrap();
+        let hits = search::to_search_result(&TEST_PERSISTENCE, search::search(requesto, &TEST_PERSISTENCE).unwrap(), &None);
+        if hits.data.is_empty() {
+            return pages;
+        }
+        pages.push(hits.data.iter().map(|doc| doc.doc["title"].as_str().unwrap().to_string()).collect());
+        req["search_after"] = json!(hits.cursor.unwrap());
+    }
+}
+
+#[test]
+fn search_after_by_field() {
+    let pages = search_all_pages(fruit_search(json!([{ "field": "name" }])), 2);
+    assert_eq!(pages, vec![vec!["fruit apple", "fruit banana"], vec!["fruit cherry", "fruit fruit date"], vec!["fruit"]]);
+
+    let pages = search_all_pages(fruit_search(json!([{ "field": "price", "order": "desc" }])), 2);
+    assert_eq!(pages, vec![vec!["fruit fruit date", "fruit cherry"], vec!["fruit banana", "fruit apple"], vec!["fruit"]]);
+}
+
+#[test]
+fn search_after_by_score() {
+    let req = json!({ "search_req": { "search": { "terms": ["fruit"], "path": "title" } } });
+    let all: Vec<String> = search_titles(req.clone());
+    let pages = search_all_pages(req, 2);
+    assert_eq!(pages.len(), 3);
+    assert_eq!(pages.concat(), all);
+}
+
+#[test]
+fn search_after_with_invalid_cursor() {
+    let mut req = fruit_search(json!([{ "field": "name" }]));
+    req["search_after"] = json!("not a cursor");
+    let requesto: search::Request = serde_json::from_value(req).unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+
+    // the cursor of a request sorted by score has no sort values
+    let req = json!({ "search_req": { "search": { "terms": ["fruit"], "path": "title" } }, "top": 1 });
+    let requesto: search::Request = serde_json::from_value(req).unwrap();
+    let cursor = search::search(requesto, &TEST_PERSISTENCE).unwrap().cursor.unwrap();
+    let mut req = fruit_search(json!([{ "field": "name" }]));
+    req["search_after"] = json!(cursor);
+    let requesto: search::Request = serde_json::from_value(req).unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+}
@@ -0,0 +1,96 @@
+use serde_json::Value;
+use veloci::{search::Stats, *};
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_stats";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+["price"]
+    numeric = "f64"
+["sizes[]"]
+    numeric = "u64"
+["commonness".boost]
+    boost_type = "f32"
+["reviews[].rating".boost]
+    boost_type = "f32"
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        create::add_segment_from_str(
+            &mut pers,
+            &json!({ "title": "lamp big", "price": 100, "sizes": [3], "commonness": 8, "reviews": [{ "rating": 2 }] }).to_string(),
+            INDICES,
+        )
+        .unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "lamp small", "price": 10, "sizes": [1, 2], "commonness": 2, "reviews": [{ "rating": 5 }, { "rating": 3 }] },
+        { "title": "lamp medium", "price": 40, "commonness": 4, "reviews": [{ "rating": 4 }] },
+        { "title": "lamp broken" },
+        { "title": "chair", "price": 1000, "sizes": [9], "reviews": [{ "rating": 1 }] }
+    ])
+}
+
+fn get_stats(field: &str) -> Stats {
+    let req = json!({
+        "search_req": { "search": { "terms": ["lamp"], "path": "title" } },
+        "stats": [{ "field": field }]
+    });
+    let hits = search_testo_to_doc!(req);
+    hits.stats.unwrap().remove(field).unwrap()
+}
+
+#[test]
+fn stats_on_numeric_field() {
+    let stats = get_stats("price");
+    assert_eq!(stats.count, 3);
+    assert_eq!(stats.min, Some(10.0));
+    assert_eq!(stats.max, Some(100.0));
+    assert_eq!(stats.sum, 150.0);
+    assert_eq!(stats.avg, Some(50.0));
+}
+
+#[test]
+fn stats_on_numeric_array() {
+    let stats = get_stats("sizes[]");
+    assert_eq!((stats.count, stats.min, stats.max, stats.sum), (3, Some(1.0), Some(3.0), 6.0));
+}
+
+#[test]
+fn stats_on_boost_fields() {
+    let stats = get_stats("commonness");
+    assert_eq!((stats.count, stats.min, stats.max, stats.sum), (3, Some(2.0), Some(8.0), 14.0));
+
+    let stats = get_stats("reviews[].rating");
+    assert_eq!((stats.count, stats.min, stats.max, stats.sum), (4, Some(2.0), Some(5.0), 14.0));
+    assert_eq!(stats.avg, Some(3.5));
+}
+
+#[test]
+fn stats_without_values() {
+    let req = json!({
+        "search_req": { "search": { "terms": ["broken"], "path": "title" } },
+        "stats": [{ "field": "price" }]
+    });
+    let hits = search_testo_to_doc!(req);
+    let stats = hits.stats.unwrap().remove("price").unwrap();
+    assert_eq!(stats, Stats::default());
+}
+
+#[test]
+fn stats_on_text_field_is_invalid() {
+    let requesto: search::Request = serde_json::from_value(json!({
+        "search_req": { "search": { "terms": ["lamp"], "path": "title" } },
+        "stats": [{ "field": "title" }]
+    }))
+    .unwrap();
+    assert!(search::search(requesto, &TEST_PERSISTENCE).is_err());
+}
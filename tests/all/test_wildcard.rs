@@ -0,0 +1,87 @@
+use serde_json::Value;
+use veloci::*;
+
+use super::common;
+
+static TEST_FOLDER: &str = "test_wildcard";
+static INDICES: &str = r#"
+["*GLOBAL*"]
+    features = ["All"]
+"#;
+
+lazy_static! {
+    static ref TEST_PERSISTENCE: persistence::Persistence = {
+        let mut pers = common::create_test_persistence(TEST_FOLDER, INDICES, get_test_data().to_string().as_bytes(), None);
+        let segment = [json!({ "title": "Rathaus am Markt" }).to_string()];
+        create::add_segment_from_str(&mut pers, &segment.join("\n"), INDICES).unwrap();
+        pers
+    };
+}
+
+pub fn get_test_data() -> Value {
+    json!([
+        { "title": "Haus am See" },
+        { "title": "Hausboot mieten" },
+        { "title": "Hund und Katze" },
+        { "title": "die drei ???" },
+        { "title": "Team Meeting" },
+        { "title": "Term Sheet" }
+    ])
+}
+
+fn sorted_titles(hits: &search::SearchResultWithDoc) -> Vec<String> {
+    let mut titles: Vec<String> = hits.data.iter().map(|doc| doc.doc["title"].as_str().unwrap().to_string()).collect();
+    titles.sort();
+    titles
+}
+
+fn query_titles(search_term: &str) -> Vec<String> {
+    let mut params = query_generator::SearchQueryGeneratorParameters::default();
+    params.search_term = search_term.to_string();
+    params.levenshtein = Some(0);
+    sorted_titles(&search_testo_to_doco_qp!(params))
+}
+
+fn wildcard_titles(pattern: &str, max_expansions: Option<usize>) -> Vec<String> {
+    let req = json!({
+        "search_req": { "search": { "terms": [pattern], "path": "title", "is_wildcard": true, "max_expansions": max_expansions } }
+    });
+    sorted_titles(&search_testo_to_doc!(req))
+}
+
+#[test]
+fn wildcard_search() {
+    assert_eq!(wildcard_titles("haus*", None), vec!["Haus am See", "Hausboot mieten"]);
+    assert_eq!(wildcard_titles("*haus", None), vec!["Haus am See", "Rathaus am Markt"]);
+    assert_eq!(wildcard_titles("te?m", None), vec!["Team Meeting", "Term Sheet"]);
+    assert_eq!(wildcard_titles("h*d", None), vec!["Hund und Katze"]);
+    assert_eq!(wildcard_titles("h?d", None), Vec::<String>::new());
+}
+
+#[test]
+fn wildcard_search_max_expansions() {
+    // "team" and "term" are both matched, the expansion stops after the first term
+    assert_eq!(wildcard_titles("te?m", Some(1)).len(), 1);
+}
+
+#[test]
+fn query_parser_wildcards() {
+    assert_eq!(query_titles("te?m"), vec!["Team Meeting", "Term Sheet"]);
+    assert_eq!(query_titles("*haus"), vec!["Haus am See", "Rathaus am Markt"]);
+    assert_eq!(query_titles("haus*"), vec!["Haus am See", "Hausboot mieten"]);
+    assert_eq!(query_titles("title:h*d"), vec!["Hund und Katze"]);
+    // only wildcards are searched as is
+    assert_eq!(query_titles("???"), vec!["die drei ???"]);
+}
+
+#[test]
+fn query_parser_wildcards_disabled() {
+    let mut params = query_generator::SearchQueryGeneratorParameters::default();
+    params.search_term = "te?m".to_string();
+    params.levenshtein = Some(0);
+    params.parser_options = Some(query_generator::QueryParserOptions {
+        no_wildcard: true,
+        ..Default::default()
+    });
+    assert_eq!(search_testo_to_doco_qp!(params).num_hits, 0);
+}
@@ -107,10 +107,14 @@ fn facets() {
         &search::FacetRequest {
             field: "tags[]".to_string(),
             top: Some(10),
+            ..Default::default()
         },
         &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10],
     )
-    .unwrap();
+    .unwrap()
+    .as_terms()
+    .unwrap()
+    .clone();
 
     yep.sort_by(|a, b| format!("{:?}{:?}", b.1, b.0).cmp(&format!("{:?}{:?}", a.1, a.0)));
     assert_eq!(vec![("nice".to_string(), 3), ("cool".to_string(), 3), ("Eis".to_string(), 1),], yep);
@@ -106,7 +106,7 @@ fn search_and_get_facet_with_facet_index() {
 
     let hits = search_testo_to_doc!(req);
     let facets = hits.facets.unwrap();
-    let mut yep = facets.get("tags[]").unwrap().clone();
+    let mut yep = facets.get("tags[]").unwrap().as_terms().unwrap().clone();
     yep.sort_by(|a, b| format!("{:?}{:?}", b.1, b.0).cmp(&format!("{:?}{:?}", a.1, a.0)));
     assert_eq!(yep, vec![("nice".to_string(), 300), ("cool".to_string(), 300)]);
 }